@@ -15,6 +15,22 @@ fn main() {
         "x <= 42 // comment\nlet uwusenpai = \"uwu senpai\""
     ]
 )]
-fn test_lex(src: &str) -> Lexed {
+fn test_lex(src: &str) -> Lexed<'_> {
     lex(src)
-}
\ No newline at end of file
+}
+// A large program, so the numbers are about lexing rather than setup
+fn generated(functions: usize) -> String {
+    (0..functions)
+        .map(|i| {
+            format!(
+                "// function {i}\npub fn f{i}(n: i32, s: str) i32 {{\n    let total: i32 = n * {i} + 1;\n    if total >= 100 && n != 0 {{\n        console.out(\"large value\");\n    }}\n    return total % 7;\n}}\n"
+            )
+        })
+        .collect()
+}
+
+#[divan::bench(args = [100, 1000, 10000])]
+fn lex_generated(bencher: divan::Bencher, functions: usize) {
+    let src = generated(functions);
+    bencher.counter(divan::counter::BytesCount::of_str(&src)).bench(|| lex(divan::black_box(&src)).tokens.len());
+}
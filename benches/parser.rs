@@ -18,3 +18,55 @@ fn test_parse(src: &str) {
 
     let _ = black_box(p_let_stmt(&mut stream));
 }
+
+// A large file, for comparing a full parse with reparsing after one edit
+fn generated(functions: usize) -> String {
+    (0..functions).map(|i| format!("fn f{i}(n: i32) i32 {{\n    let x = n * {i};\n    return x + 1;\n}}\n")).collect()
+}
+
+#[divan::bench(args = [100, 1000])]
+fn full_parse_after_edit(bencher: divan::Bencher, functions: usize) {
+    use rotor::parser::parse_program_recovering;
+
+    let mut src = generated(functions);
+    let middle = src.len() / 2;
+    let at = middle + src[middle..].find('*').unwrap();
+    src.replace_range(at..at + 1, "+");
+    bencher.bench(|| {
+        let mut stream = TokenStream::new(lex(black_box(&src)).tokens);
+        parse_program_recovering(&mut stream)
+    });
+}
+
+#[divan::bench(args = [100, 1000])]
+fn reparse_after_edit(bencher: divan::Bencher, functions: usize) {
+    use rotor::lexer::Edit;
+    use rotor::parser::{reparse, Parsed};
+
+    let src = generated(functions);
+    let middle = src.len() / 2;
+    let at = middle + src[middle..].find('*').unwrap();
+    let edit = Edit::new(at..at + 1, "+");
+    let mut edited = src.clone();
+    edit.apply(&mut edited);
+    bencher
+        .with_inputs(|| {
+            let lexed = lex(&src);
+            let parsed = Parsed::new(&lexed);
+            (lexed, parsed)
+        })
+        .bench_values(|(lexed, parsed)| reparse(parsed, lexed, black_box(&edited), &edit));
+}
+
+// Parsing straight from the lexer, without building the token list first
+#[divan::bench(args = [100, 1000])]
+fn parse_streaming(bencher: divan::Bencher, functions: usize) {
+    use rotor::lexer::Lexer;
+    use rotor::parser::parse_program_recovering;
+
+    let src = generated(functions);
+    bencher.bench(|| {
+        let mut stream = TokenStream::from_lexer(Lexer::new(black_box(&src)));
+        parse_program_recovering(&mut stream)
+    });
+}
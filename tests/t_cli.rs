@@ -0,0 +1,246 @@
+// Runs the `rotor` binary itself
+
+fn rotor(args: &[&str]) -> std::process::Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_rotor")).args(args).output().unwrap()
+}
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+// Writes a throwaway source file
+fn source(name: &str, text: &str) -> String {
+    let file = std::env::temp_dir().join(format!("rotor-test-cli-{}-{}.rt", name, std::process::id()));
+    std::fs::write(&file, text).unwrap();
+    file.to_string_lossy().to_string()
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let output = rotor(&[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Commands:"));
+
+    // Used to index past the end of the arguments and panic
+    let output = rotor(&["lex"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Usage: rotor lex [options] <file>"));
+
+    assert_eq!(rotor(&["frobnicate"]).status.code(), Some(2));
+    assert_eq!(rotor(&["build", "--fast"]).status.code(), Some(2));
+    assert_eq!(rotor(&["--color", "sometimes", "check"]).status.code(), Some(2));
+}
+
+#[test]
+fn help_for_each_command() {
+    let output = rotor(&["build", "--help"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("Build the current project, or a single file\n\nUsage: rotor build [options] [file]"));
+    assert!(stdout(&output).contains("--emit <c|asm|wat|ir>"));
+
+    assert_eq!(stdout(&rotor(&["help", "build"])), stdout(&output));
+    assert_eq!(rotor(&["help", "nothing"]).status.code(), Some(2));
+    assert!(stdout(&rotor(&["--version"])).starts_with("Version: "));
+}
+
+#[test]
+fn check_reports_errors_and_fails() {
+    let file = source("check", "pub fn main() i32 {\n    return true;\n}");
+
+    let output = rotor(&["--color=never", "check", &file]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with(&format!("error: {}:2:", file)));
+
+    assert!(stderr(&output).ends_with(": 1 error, 0 warnings\n"));
+
+    let output = rotor(&["check", &file, "--message-format", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let line = stdout(&output);
+    assert!(line.starts_with("{\"level\":\"error\",\"message\":"));
+    assert!(line.contains(&format!("\"file\":\"{}\"", file)));
+    assert!(line.contains("\"kind\":\"TypeMismatch\",\"line\":2,"));
+}
+
+#[test]
+fn json_output_is_only_json() {
+    let file = source("json", "pub fn main() i32 {\n    let unused = 1;\n    return 0;\n}");
+
+    let output = rotor(&["check", &file, "--message-format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let lines: Vec<String> = stdout(&output).lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.starts_with('{') && line.ends_with('}')));
+    assert!(lines[1].starts_with("{\"level\":\"status\",\"message\":\"Checked "));
+}
+
+#[test]
+fn check_counts_warnings() {
+    let file = source("warnings", "pub fn main() i32 {\n    let unused = 1;\n    return 0;\n}");
+
+    let output = rotor(&["--color=never", "check", &file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr(&output).starts_with(&format!("warning: {}:2:5: Variable `unused` is never read\n", file)));
+    assert!(stdout(&output).ends_with(": 0 errors, 1 warning\n"));
+
+    let output = rotor(&["--quiet", "check", &file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stderr(&output), "");
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn check_points_at_the_stator_with_the_error() {
+    let dir = std::env::temp_dir().join(format!("rotor-test-cli-stators-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.rt"), "use util [helper]\npub fn main() i32 {\n    return helper();\n}").unwrap();
+    std::fs::write(dir.join("util.rt"), "pub fn helper() i32 {\n    return 0;\n}\n\nfn broken() bool {\n    return 1;\n}").unwrap();
+
+    let output = rotor(&["--color=never", "check", &dir.join("main.rt").to_string_lossy()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with(&format!("error: {}:6:", dir.join("util.rt").display())));
+}
+
+#[test]
+fn fmt_check_fails_on_unformatted_files() {
+    let file = source("fmt", "pub fn main() i32 { return 0; }");
+
+    let output = rotor(&["--color=never", "fmt", "--check", &file]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("is not formatted (from line 1)"));
+
+    assert_eq!(rotor(&["fmt", &file]).status.code(), Some(0));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "pub fn main() i32 {\n    return 0;\n}\n");
+    assert_eq!(rotor(&["fmt", "--check", &file]).status.code(), Some(0));
+}
+
+#[test]
+fn run_passes_arguments_and_exit_code() {
+    let file = source(
+        "run",
+        "use std.io [console]\nuse std.process [arg, arg_count]\npub fn main() i32 {\n    console.out(arg(1));\n    return arg_count();\n}",
+    );
+
+    let output = rotor(&["run", &file, "--", "spinning", "two"]);
+    assert_eq!(stdout(&output), "spinning\n");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn every_backend_is_optimized() {
+    let file = source("optimized", "pub fn main() i32 {\n    let x = 2 + 3;\n    return x * 4;\n}");
+
+    for backend in ["c", "wasm"] {
+        let output_file = format!("{}.{}", file, backend);
+        let output = rotor(&["build", &file, "--backend", backend, "-O", "2", "--dump-passes", "-o", &output_file]);
+        let _ = std::fs::remove_file(&output_file);
+        assert_eq!(output.status.code(), Some(0), "with the {} backend", backend);
+        assert!(stderr(&output).contains("; IR after const-fold"), "with the {} backend", backend);
+    }
+}
+
+#[test]
+fn backend_errors_have_no_position() {
+    let file = source("no_main", "fn helper() {}");
+    let output = rotor(&["build", &file, "-o", &format!("{}.exe", file)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "error: No `main` function found\n");
+
+    let file = source("bad_cc", "pub fn main() {}");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_rotor"))
+        .args(["build", &file, "-o", &format!("{}.exe", file)])
+        .env("CC", "false")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("error: `false` failed"));
+}
+
+#[test]
+fn quiet_leaves_out_status_messages() {
+    let file = source("quiet", "pub fn main() {}");
+
+    assert_eq!(stdout(&rotor(&["check", &file])), format!("Checked rotor-test-cli-quiet-{}: 0 errors, 0 warnings\n", std::process::id()));
+    let output = rotor(&["-q", "check", &file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn parse_prints_the_tree() {
+    let file = source("parse", "fn f(x: i32) bool {\n    return !(x < 2);\n}");
+
+    let output = rotor(&["parse", &file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        "FnDecl f(x: i32) bool @1:1\n  Return @2:5\n    UnaryOp ! @2:12\n      BinaryOp < @2:14\n        Variable x @2:14\n        Integer 2 @2:18\n"
+    );
+}
+
+#[test]
+fn lex_and_parse_agree() {
+    let file = source("agree", "fn f() {}");
+
+    // Without the `serde` feature both fail the same way
+    let lexed = rotor(&["lex", "--json", &file]).status.code();
+    assert_eq!(lexed, rotor(&["parse", "--json", &file]).status.code());
+    assert_eq!(lexed, Some(if cfg!(feature = "serde") { 0 } else { 2 }));
+
+    let file = source("broken", "fn f( {}");
+    let output = rotor(&["parse", &file]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), format!("error: {}:1:7: Expected identifier, found {{\n", file));
+}
+
+#[test]
+fn test_command_runs_test_functions() {
+    let file = source(
+        "tests",
+        "fn test_passes() bool {\n    return 2 * 2 == 4;\n}\nfn test_fails() bool {\n    return false;\n}\nfn helper() bool {\n    return false;\n}\nfn test_returns_int() i32 {\n    return 0;\n}",
+    );
+
+    let output = rotor(&["test", &file]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("test test_passes ... ok\ntest test_fails ... FAILED\n"));
+    assert!(stdout(&output).contains("test result: FAILED. 1 passed; 1 failed"));
+
+    assert!(stderr(&output).contains("---- test_fails exited with code 1 ----"));
+    assert!(stderr(&output).contains("warning: `test_returns_int` is not run"));
+
+    let output = rotor(&["test", &file, "--message-format", "json"]);
+    assert!(stdout(&output).lines().all(|line| line.starts_with('{')));
+    assert!(stdout(&output).contains("{\"level\":\"error\",\"message\":\"test_fails exited with code 1\",\"output\":\"\"}"));
+
+    let output = rotor(&["test", &file, "--filter", "passes"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn grammar_covers_the_lexer_tables() {
+    use rotor::lexer::{KEYWORDS, SYMBOLS};
+    use rotor::lsp::Json;
+
+    assert_eq!(rotor(&["grammar"]).status.code(), Some(2));
+    let output = rotor(&["grammar", "--textmate"]);
+    assert_eq!(output.status.code(), Some(0));
+    let grammar = Json::parse(&stdout(&output)).unwrap();
+    assert_eq!(grammar["scopeName"].as_str(), Some("source.rotor"));
+
+    let patterns = |name: &str| -> String {
+        let patterns = grammar["repository"][name]["patterns"].as_array();
+        patterns.iter().map(|pattern| pattern["match"].as_str().unwrap().to_string()).collect::<Vec<_>>().join("\n")
+    };
+    let keywords = patterns("keywords");
+    for (word, _) in KEYWORDS {
+        assert!(keywords.contains(word), "`{}` is missing from the grammar", word);
+    }
+    let symbols = patterns("symbols");
+    for (symbol, _) in SYMBOLS {
+        let escaped: String = symbol.chars().flat_map(|ch| if "\\^$.|?*+()[]{}".contains(ch) { vec!['\\', ch] } else { vec![ch] }).collect();
+        assert!(symbols.contains(&escaped), "`{}` is missing from the grammar", symbol);
+    }
+}
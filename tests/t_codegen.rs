@@ -0,0 +1,252 @@
+// These tests need a C compiler on the PATH (or in $CC)
+
+fn compile_c(src: &str, name: &str) -> std::path::PathBuf {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::{lower_program, verify};
+    use rotor::codegen::{emit_c, build_c};
+
+    let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let module = lower_program(&program, &checked);
+    verify(&module).unwrap();
+    let c_source = emit_c(&module).unwrap();
+
+    let output = std::env::temp_dir().join(format!("rotor-test-{}-{}", name, std::process::id()));
+    build_c(&c_source, &output).unwrap();
+    output
+}
+
+fn run(path: &std::path::Path) -> std::process::Output {
+    let output = std::process::Command::new(path).output().unwrap();
+    let _ = std::fs::remove_file(path);
+    output
+}
+
+// `main`'s result on every backend that can run here. WebAssembly runs
+// in node, when it is installed.
+fn main_everywhere(src: &str, name: &str) -> Vec<(&'static str, i32)> {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::lower_program;
+    use rotor::codegen::emit_wasm;
+
+    let mut results = vec![("c", run(&compile_c(src, name)).status.code().unwrap())];
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    results.push(("x86_64", run(&compile_asm(src, name)).status.code().unwrap()));
+
+    let node = std::process::Command::new("node").arg("--version").output();
+    if node.is_ok_and(|output| output.status.success()) {
+        let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+        let checked = check_program(&mut program).unwrap();
+        let module = emit_wasm(&lower_program(&program, &checked)).unwrap();
+        let path = std::env::temp_dir().join(format!("rotor-test-{}-{}.wasm", name, std::process::id()));
+        std::fs::write(&path, module.to_bytes()).unwrap();
+        let script = "
+const bytes = require('fs').readFileSync(process.argv[1]);
+WebAssembly.instantiate(bytes, {}).then(({ instance }) => console.log(instance.exports.main()));";
+        let output = std::process::Command::new("node").arg("-e").arg(script).arg(&path).output().unwrap();
+        let _ = std::fs::remove_file(&path);
+        results.push(("wasm", String::from_utf8_lossy(&output.stdout).trim().parse().unwrap()));
+    }
+    results
+}
+
+#[test]
+fn c_main_return_is_exit_code() {
+    let exe = compile_c("pub fn main() i32 {\n    return 42;\n}", "exit_code");
+    assert_eq!(run(&exe).status.code(), Some(42));
+}
+
+#[test]
+fn c_control_flow_and_calls() {
+    let src = "
+const LIMIT: i32 = 10;
+
+fn fib(n: i32) i32 {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+fn main() i32 {
+    let total = 0;
+    let done = false;
+    for i in 0..LIMIT {
+        total = total + fib(i);
+    }
+    while !done {
+        total = total - 8;
+        done = total % 10 == 0 && total < 100;
+    }
+    return total;
+}";
+    let exe = compile_c(src, "control_flow");
+    // The fibonacci numbers below 10 add up to 88, then 8 is taken away once
+    assert_eq!(run(&exe).status.code(), Some(80));
+}
+
+#[test]
+fn c_division_by_zero_panics() {
+    let exe = compile_c("fn div(a: i32, b: i32) i32 { return a / b; }\nfn main() i32 { return div(1, 0); }", "div_zero");
+    let output = run(&exe);
+
+    assert_eq!(output.status.code(), Some(101));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("divide by zero"));
+}
+
+#[test]
+fn c_requires_main() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::typeck::check_program;
+    use rotor::ir::lower_program;
+    use rotor::codegen::emit_c;
+
+    let lexed = lex("fn helper() {}");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let mut program = parse_program(&mut stream).unwrap();
+    let checked = check_program(&mut program).unwrap();
+
+    assert!(emit_c(&lower_program(&program, &checked)).is_err());
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+fn compile_asm(src: &str, name: &str) -> std::path::PathBuf {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::{lower_program, verify};
+    use rotor::codegen::{emit_asm, build_asm};
+
+    let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let module = lower_program(&program, &checked);
+    verify(&module).unwrap();
+    let asm = emit_asm(&module).unwrap();
+
+    let output = std::env::temp_dir().join(format!("rotor-test-asm-{}-{}", name, std::process::id()));
+    build_asm(&asm, &output).unwrap();
+    output
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_recursion_and_loops() {
+    let src = "
+const LIMIT: i32 = 10;
+
+fn fib(n: i32) i32 {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+fn main() i32 {
+    let total = 0;
+    for i in 0..LIMIT {
+        total = total + fib(i);
+    }
+    return total;
+}";
+    let exe = compile_asm(src, "fib");
+    assert_eq!(run(&exe).status.code(), Some(88));
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_stack_arguments() {
+    // Arguments past the sixth are passed on the stack
+    let src = "
+fn weigh(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, g: i32, h: i32) i32 {
+    return a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f + 7 * g + 8 * h;
+}
+
+fn main() i32 {
+    let one = 1;
+    return weigh(one, 1, 1, 1, 1, 1, 1, 1 + weigh(0, 0, 0, 0, 0, 0, 0, 0)) - 10;
+}";
+    let exe = compile_asm(src, "stack_args");
+    assert_eq!(run(&exe).status.code(), Some(26));
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_logic_and_arithmetic() {
+    let src = "
+fn check(x: i32) bool {
+    return x > 0 && x % 2 == 0 || x == -7;
+}
+
+fn main() i32 {
+    let result = 0;
+    if check(4) { result = result + 1; }
+    if !check(3) { result = result + 2; }
+    if check(-7) { result = result + 4; }
+    let min = -2147483647 - 1;
+    if min / -1 == min && min + min == 0 { result = result + 8; }
+    return result;
+}";
+    let exe = compile_asm(src, "logic");
+    assert_eq!(run(&exe).status.code(), Some(15));
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_division_by_zero_panics() {
+    let exe = compile_asm("fn main() i32 { let zero = 0; return 1 % zero; }", "rem_zero");
+    let output = run(&exe);
+
+    assert_eq!(output.status.code(), Some(101));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("divisor of zero"));
+}
+
+#[test]
+fn c_console_output() {
+    let exe = compile_c("use std.io [console]\npub fn main() i32 {\n    console.out(\"Rotor is spinning!\");\n    return 0;\n}", "console");
+    let output = run(&exe);
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Rotor is spinning!\n");
+}
+
+#[test]
+fn c_non_ascii_strings() {
+    let src = "use std.io [console]\nuse std.string [len]\npub fn main() i32 {\n    console.out(\"héllo\");\n    return len(\"é€\");\n}";
+    let output = run(&compile_c(src, "non_ascii"));
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "héllo\n");
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_non_ascii_strings() {
+    let src = "use std.io [console]\nuse std.string [len]\npub fn main() i32 {\n    console.out(\"héllo\");\n    return len(\"é€\");\n}";
+    let output = run(&compile_asm(src, "non_ascii"));
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "héllo\n");
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[test]
+fn shadowing_works_on_every_backend() {
+    // The inner `x` is initialized from the outer one
+    let src = "fn main() i32 { let x = 1; if x == 1 { let x = x + 41; return x; } return 0; }";
+    for (backend, result) in main_everywhere(src, "shadow") {
+        assert_eq!(result, 42, "with the {} backend", backend);
+    }
+
+    let src = "fn f(a: i32) i32 { let a = a + 1; return a; }\nfn main() i32 { return f(41); }";
+    for (backend, result) in main_everywhere(src, "shadow_param") {
+        assert_eq!(result, 42, "with the {} backend", backend);
+    }
+}
+
+#[test]
+fn globals_and_functions_can_share_a_name() {
+    let src = "let foo = 3;\nfn foo() i32 { return foo; }\nfn main() i32 { return foo() + foo; }";
+    for (backend, result) in main_everywhere(src, "same_name") {
+        assert_eq!(result, 6, "with the {} backend", backend);
+    }
+}
@@ -0,0 +1,81 @@
+const SOURCES: &[&str] = &[
+    "",
+    "   \n\n",
+    "// just a comment",
+    "use std.io [console]\r\n\r\npub fn main() i32 {\r\n\treturn 0; // done\r\n}\r\n",
+    "fn f(x: i32,   y: i32) i32 { /* sum */ return x+y ; }\n\n\n",
+    "let a = 1 & 2; @ let b = \"é\";",
+    "fn broken( { let = ; }\nfn fine() {}\n",
+    "let s = \"unterminated",
+    "/* unterminated",
+    "if x { } else if y { } else { z(1, [2, 3]); }",
+];
+
+#[test]
+fn lossless_tokens_round_trip() {
+    use rotor::lexer::lex_lossless;
+
+    for source in SOURCES {
+        assert_eq!(lex_lossless(source).text(), *source);
+    }
+}
+
+#[test]
+fn cst_round_trips() {
+    use rotor::parser::parse_cst;
+
+    for source in SOURCES {
+        assert_eq!(parse_cst(source).text(), *source);
+    }
+    for stator in ["std.io", "std.io.console", "std.math", "std.process", "std.string"] {
+        let source = rotor::stdlib::source(stator).unwrap();
+        assert_eq!(parse_cst(source).text(), source);
+    }
+}
+
+#[test]
+fn trivia_attachment() {
+    use rotor::lexer::{lex_lossless, TriviaKind};
+
+    let lexed = lex_lossless("// head\nlet x = 1; // tail\n\n  y\n");
+    let first = &lexed.tokens[0];
+    let kinds: Vec<TriviaKind> = first.leading.iter().map(|trivia| trivia.kind).collect();
+    assert_eq!(kinds, vec![TriviaKind::LineComment, TriviaKind::Newline]);
+    assert_eq!(first.text, "let");
+
+    // A comment on the same line belongs to the token before it
+    let semicolon = &lexed.tokens[4];
+    assert_eq!(semicolon.text, ";");
+    let trailing: Vec<&str> = semicolon.trailing.iter().map(|trivia| trivia.text.as_str()).collect();
+    assert_eq!(trailing, vec![" ", "// tail"]);
+
+    let last = &lexed.tokens[5];
+    let leading: Vec<&str> = last.leading.iter().map(|trivia| trivia.text.as_str()).collect();
+    assert_eq!(leading, vec!["\n", "\n", "  "]);
+    assert_eq!(lexed.eof.len(), 1);
+    assert_eq!(lexed.eof[0].kind, TriviaKind::Newline);
+}
+
+#[test]
+fn cst_nodes_follow_the_ast() {
+    use rotor::parser::{parse_cst, NodeKind};
+
+    let cst = parse_cst("fn f(x: i32) i32 {\n    return x * 2;\n}\nlet = 4;\n");
+    assert_eq!(cst.errors.len(), 1);
+
+    let function = cst.root.nodes().next().unwrap();
+    assert_eq!(function.kind, NodeKind::FnDecl);
+    let kinds: Vec<NodeKind> = function.nodes().map(|node| node.kind).collect();
+    assert_eq!(kinds, vec![NodeKind::Param, NodeKind::ReturnStmt]);
+    // The line break after `}` starts the next token's trivia
+    assert_eq!(function.text(), "fn f(x: i32) i32 {\n    return x * 2;\n}");
+
+    let ret = function.nodes().nth(1).unwrap();
+    let product = ret.nodes().next().unwrap();
+    assert_eq!(product.kind, NodeKind::BinaryOp);
+    assert_eq!(product.tokens().iter().map(|token| token.text.as_str()).collect::<Vec<_>>(), vec!["x", "*", "2"]);
+
+    // The statement that failed to parse is left as loose tokens
+    assert_eq!(cst.root.nodes().count(), 1);
+    assert_eq!(cst.root.tokens().len(), 19);
+}
@@ -0,0 +1,55 @@
+fn format(src: &str) -> String {
+    rotor::format::format_source(src).unwrap_or_else(|errors| panic!("{:?}", errors))
+}
+
+#[test]
+fn format_canonical_style() {
+    let src = "use std.math [min,abs]\nconst LIMIT=10;\nfn f(x:i32,y:i32)i32{let z=(x+y)*2;if z>LIMIT{return z;}else if z<0 {return -(z+1);} else{return x-(y-z);}}\nfn g(n: i32);";
+    let expected = "\
+use std.math [abs, min]
+const LIMIT = 10;
+
+fn f(x: i32, y: i32) i32 {
+    let z = (x + y) * 2;
+    if z > LIMIT {
+        return z;
+    } else if z < 0 {
+        return -(z + 1);
+    } else {
+        return x - (y - z);
+    }
+}
+
+fn g(n: i32);
+";
+    assert_eq!(format(src), expected);
+}
+
+#[test]
+fn format_keeps_comments() {
+    let src = "// Header\n\nfn main() {   // opens\n  // inside\n  for i in 0..3 { f(i); } // trailing\n  /* before\n     the end */\n}\n";
+    let expected = "// Header\n\nfn main() { // opens\n    // inside\n    for i in 0..3 {\n        f(i);\n    } // trailing\n    /* before\n     the end */\n}\n";
+    assert_eq!(format(src), expected);
+}
+
+#[test]
+fn format_is_idempotent() {
+    let src = "fn a() {}\n\n\n\nfn b() bool { let x = !(1 < 2); return x || true && false; }\n// last\n";
+    let once = format(src);
+    assert_eq!(format(&once), once);
+    assert_eq!(once, "fn a() {}\n\nfn b() bool {\n    let x = !(1 < 2);\n    return x || true && false;\n}\n// last\n");
+}
+
+#[test]
+fn format_leaves_standard_library_alone() {
+    for stator in ["std.io", "std.io.console", "std.math", "std.process", "std.string"] {
+        let source = rotor::stdlib::source(stator).unwrap();
+        assert_eq!(format(source), source, "{}", stator);
+    }
+}
+
+#[test]
+fn format_refuses_broken_source() {
+    let errors = rotor::format::format_source("fn main() { let = 1; }").err().unwrap();
+    assert_eq!((errors[0].line(), errors[0].column()), (1, 17));
+}
@@ -0,0 +1,122 @@
+// Incremental relexing and reparsing: random edits to real sources, each
+// checked against lexing and parsing the edited text from scratch
+
+use rotor::lexer::Lexed;
+use rotor::parser::Parsed;
+
+// A small xorshift generator, so runs are repeatable without a dependency
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Pieces that change how the text around them lexes and parses
+const FRAGMENTS: &[&str] = &[
+    "", " ", "\n", "\t", ";", "{", "}", "(", ")", "\"", "/*", "*/", "//", "&", "=", "==", ".", "..", "1.5",
+    "fn", "pub fn f() {\n", "use std.io [console]\n", "let x = 1;\n", "const K: i32 = 2;", "if a { b(); } else ",
+    "return 0;", "x = x + 1;\n", "é", "while true {\n}\n",
+];
+
+fn sources() -> Vec<String> {
+    let mut sources: Vec<String> = rotor::stdlib::stators().filter_map(rotor::stdlib::source).map(str::to_string).collect();
+    sources.push("pub fn main() i32 {\n    let total = 0;\n    for i in 0..10 {\n        total = total + i;\n    }\n    return total;\n}\n".to_string());
+    sources.push("/* a comment\n spanning lines */\nfn f() {\n    g(\"text\n with a newline\");\n}\n&\nfn g(s: str) {}\n".to_string());
+    sources
+}
+
+fn random_edit(rng: &mut Rng, text: &str) -> rotor::lexer::Edit {
+    use rotor::lexer::Edit;
+
+    let boundary = |mut offset: usize| {
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    };
+    let start = boundary(rng.below(text.len() + 1));
+    let end = boundary((start + rng.below(12)).min(text.len())).max(start);
+    Edit::new(start..end, FRAGMENTS[rng.below(FRAGMENTS.len())])
+}
+
+// A run of random edits, each with the text it leaves. Tokens borrow the
+// text they were lexed from, so every version has to outlive the run.
+fn edits(rng: &mut Rng, source: String) -> Vec<(rotor::lexer::Edit, String)> {
+    let mut text = source;
+    (0..300)
+        .map(|_| {
+            let edit = random_edit(rng, &text);
+            edit.apply(&mut text);
+            (edit, text.clone())
+        })
+        .collect()
+}
+
+fn tokens_of(lexed: &Lexed) -> Vec<String> {
+    lexed.tokens.iter().map(|token| format!("{} {:?}", token.get_debug_info(), token.span())).collect()
+}
+
+fn errors_of(errors: &[rotor::Error]) -> Vec<String> {
+    errors.iter().map(|error| format!("{:?} {}", error.kind(), error)).collect()
+}
+
+#[test]
+fn relex_matches_lex() {
+    use rotor::lexer::{lex, relex};
+
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for source in sources() {
+        let mut lexed = lex(&source);
+        let edits = edits(&mut rng, source.clone());
+        for (edit, text) in &edits {
+            lexed = relex(lexed, text, edit);
+            let full = lex(text);
+            assert_eq!(tokens_of(&lexed), tokens_of(&full), "after {:?} in {:?}", edit, text);
+            assert_eq!(lexed.errors, full.errors, "after {:?} in {:?}", edit, text);
+            assert_eq!(lexed.comments, full.comments, "after {:?} in {:?}", edit, text);
+            assert_eq!(lexed.eof, full.eof, "after {:?} in {:?}", edit, text);
+        }
+    }
+}
+
+#[test]
+fn reparse_matches_a_full_parse() {
+    use rotor::lexer::lex;
+    use rotor::parser::{parse_program_recovering, reparse, TokenStream};
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for source in sources() {
+        let mut lexed = lex(&source);
+        let mut parsed = Parsed::new(&lexed);
+        let edits = edits(&mut rng, source.clone());
+        for (edit, text) in &edits {
+            (lexed, parsed) = reparse(parsed, lexed, text, edit);
+
+            let full = lex(text);
+            let (program, errors) = parse_program_recovering(&mut TokenStream::with_eof(full.tokens, full.eof));
+            assert_eq!(parsed.program, program, "after {:?} in {:?}", edit, text);
+            assert_eq!(errors_of(&parsed.errors), errors_of(&errors), "after {:?} in {:?}", edit, text);
+        }
+    }
+}
+
+#[test]
+fn edits_apply_to_text() {
+    use rotor::lexer::Edit;
+
+    let mut text = "let x = 1;".to_string();
+    let edit = Edit::new(8..9, "42");
+    edit.apply(&mut text);
+    assert_eq!(text, "let x = 42;");
+    assert_eq!(edit.delta(), 1);
+    assert_eq!(Edit::new(0..4, "").delta(), -4);
+}
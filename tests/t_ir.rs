@@ -0,0 +1,146 @@
+fn lower(src: &str) -> rotor::ir::Module {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::typeck::check_program;
+    use rotor::ir::{lower_program, verify};
+
+    let lexed = lex(src);
+    let mut stream = TokenStream::new(lexed.tokens);
+    let mut program = parse_program(&mut stream).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let module = lower_program(&program, &checked);
+    verify(&module).unwrap();
+    module
+}
+
+#[test]
+fn lower_if_merges_with_phi() {
+    let module = lower("fn pick(c: bool) i32 {\n    let x = 1;\n    if c {\n        x = 2;\n    }\n    return x;\n}");
+
+    assert_eq!(
+        module.to_string(),
+        "fn @pick(bool) -> i32 {
+bb0:
+    %0: bool = param 0
+    %1: i32 = const 1
+    br %0, bb1, bb2
+bb1:
+    %2: i32 = const 2
+    jmp bb2
+bb2:
+    %3: i32 = phi [bb0: %1], [bb1: %2]
+    ret %3
+bb3:
+    unreachable
+}
+"
+    );
+}
+
+#[test]
+fn lower_while_loop_header_phi() {
+    use rotor::ir::InstKind;
+
+    let module = lower("fn count(n: i32) i32 {\n    let i = 0;\n    while i < n {\n        i = i + 1;\n    }\n    return i;\n}");
+    let function = module.function("count").unwrap();
+
+    // The loop header reads `i` from both the entry and the back edge
+    let header = function.block(rotor::ir::BlockId(1));
+    let InstKind::Phi(incoming) = &header.insts[0].kind else {
+        panic!("expected a phi at the start of the loop header");
+    };
+    assert_eq!(incoming.len(), 2);
+    assert_eq!(function.predecessors()[1].len(), 2);
+}
+
+#[test]
+fn lower_for_and_early_return() {
+    let module = lower(
+        "let total: i32 = 0;
+fn find(limit: i32) i32 {
+    for i in 0..limit {
+        if i * i > 50 {
+            return i;
+        }
+        total = total + i;
+    }
+    return -1;
+}",
+    );
+    let text = module.to_string();
+
+    assert!(text.starts_with("global @total: i32 = 0\n"));
+    assert!(text.contains("lt "));
+    assert!(text.contains("store @total"));
+    assert_eq!(text.matches("ret ").count(), 2);
+}
+
+#[test]
+fn lower_short_circuit_uses_branches() {
+    let module = lower("fn both(a: bool, b: bool) bool {\n    return a && b;\n}");
+    let function = module.function("both").unwrap();
+
+    assert_eq!(function.reverse_postorder().len(), 3);
+    assert!(module.to_string().contains("phi [bb0: %0], [bb1: %1]"));
+}
+
+#[test]
+fn verify_rejects_type_mismatch() {
+    use rotor::ir::{BinOp, Const, FunctionBuilder, Module, verify};
+    use rotor::typeck::Type;
+
+    let mut builder = FunctionBuilder::new("bad", vec![], Type::I32);
+    let flag = builder.constant(Const::Bool(true));
+    let one = builder.constant(Const::Int(1));
+    let sum = builder.binary(BinOp::Add, flag, one);
+    builder.ret(Some(sum));
+
+    let module = Module { globals: vec![], functions: vec![builder.finish()] };
+    let errors = verify(&module).unwrap_err();
+
+    assert!(errors[0].message().contains("operand %0 must be i32, found bool"));
+}
+
+#[test]
+fn verify_rejects_use_not_dominated() {
+    use rotor::ir::{Const, FunctionBuilder, Module, verify};
+    use rotor::typeck::Type;
+
+    let mut builder = FunctionBuilder::new("bad", vec![Type::Bool], Type::I32);
+    let condition = builder.param(0);
+    let (then_block, merge) = (builder.new_block(), builder.new_block());
+    builder.branch(condition, then_block, merge);
+    builder.switch_to(then_block);
+    let value = builder.constant(Const::Int(7));
+    builder.jump(merge);
+    builder.switch_to(merge);
+    // Only defined when the branch was taken
+    builder.ret(Some(value));
+
+    let module = Module { globals: vec![], functions: vec![builder.finish()] };
+    let errors = verify(&module).unwrap_err();
+
+    assert!(errors[0].message().contains("does not dominate"));
+}
+
+#[test]
+fn verify_rejects_phi_missing_predecessor() {
+    use rotor::ir::{Const, FunctionBuilder, Module, verify};
+    use rotor::typeck::Type;
+
+    let mut builder = FunctionBuilder::new("bad", vec![Type::Bool], Type::I32);
+    let condition = builder.param(0);
+    let zero = builder.constant(Const::Int(0));
+    let (then_block, merge) = (builder.new_block(), builder.new_block());
+    builder.branch(condition, then_block, merge);
+    builder.switch_to(then_block);
+    builder.jump(merge);
+    let phi = builder.phi(merge, Type::I32, vec![(then_block, zero)]);
+    builder.switch_to(merge);
+    builder.ret(Some(phi));
+
+    let module = Module { globals: vec![], functions: vec![builder.finish()] };
+    let errors = verify(&module).unwrap_err();
+
+    assert!(errors[0].message().contains("one entry per predecessor"));
+}
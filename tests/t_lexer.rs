@@ -56,4 +56,126 @@ fn lex_operators_and_brackets() {
     assert_eq!(lexed.tokens[16].kind, TokenKind::RCurly);
     assert_eq!(lexed.tokens[17].kind, TokenKind::Semicolon);
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn lex_multi_char_symbols() {
+    use rotor::lexer::{lex, TokenKind};
+
+    let input = "a <= b && c != d || !e == f.g >= 0..10";
+    let lexed = lex(input);
+
+    let kinds: Vec<TokenKind> = lexed.tokens.iter().map(|token| token.kind).collect();
+    assert_eq!(kinds, vec![
+        TokenKind::Identifier, TokenKind::LessThanOrEqual, TokenKind::Identifier,
+        TokenKind::And,
+        TokenKind::Identifier, TokenKind::NotEqual, TokenKind::Identifier,
+        TokenKind::Or,
+        TokenKind::Not, TokenKind::Identifier, TokenKind::EqualEqual,
+        TokenKind::Identifier, TokenKind::Dot, TokenKind::Identifier,
+        TokenKind::GreaterThanOrEqual,
+        TokenKind::Integer, TokenKind::Range, TokenKind::Integer,
+    ]);
+    assert!(lexed.errors.is_empty());
+}
+
+#[test]
+fn lex_fn_keywords_and_floats() {
+    use rotor::lexer::{lex, TokenKind};
+
+    let lexed = lex("pub fn f() { return 3.14; }");
+
+    assert_eq!(lexed.tokens[0].kind, TokenKind::Pub);
+    assert_eq!(lexed.tokens[1].kind, TokenKind::Fn);
+    assert_eq!(lexed.tokens[6].kind, TokenKind::Return);
+    assert_eq!(lexed.tokens[7].kind, TokenKind::Float);
+    assert_eq!(lexed.tokens[7].value(), "3.14");
+}
+
+#[test]
+fn lex_keeps_comments() {
+    use rotor::lexer::lex;
+
+    let lexed = lex("let x = 1; // one\n/* two\n */ let y = 2;");
+    let comments: Vec<(&str, usize, usize)> = lexed.comments.iter().map(|comment| (comment.text.as_str(), comment.span.line, comment.span.start)).collect();
+    assert_eq!(comments, vec![("// one", 1, 11), ("/* two\n */", 2, 18)]);
+    assert!(lexed.tokens.iter().all(|token| token.value() != "one"));
+}
+
+#[test]
+fn lex_follows_the_tables() {
+    use rotor::lexer::{lex, TokenKind, KEYWORDS, SYMBOLS};
+
+    for (word, kind) in KEYWORDS {
+        let lexed = lex(word);
+        assert_eq!(lexed.tokens.len(), 1);
+        assert_eq!(lexed.tokens[0].kind, *kind, "{}", word);
+    }
+    for (symbol, kind) in SYMBOLS {
+        let lexed = lex(symbol);
+        assert_eq!(lexed.tokens.len(), 1);
+        assert_eq!(lexed.tokens[0].kind, *kind, "{}", symbol);
+        assert_eq!(lexed.tokens[0].value(), *symbol);
+    }
+    assert_eq!(lex("lets").tokens[0].kind, TokenKind::Identifier);
+    assert_eq!(lex("&").errors.len(), 1);
+}
+
+#[test]
+fn tokens_borrow_the_source() {
+    use rotor::lexer::{lex, TokenKind};
+
+    let source = "let é = \"naïve\"; \"open";
+    let lexed = lex(source);
+    let texts: Vec<&str> = lexed.tokens.iter().map(|token| token.text).collect();
+    assert_eq!(texts, vec!["let", "é", "=", "\"naïve\"", ";", "\"open"]);
+    assert_eq!(lexed.tokens[1].kind, TokenKind::Identifier);
+    // Strings are decoded on demand, and spans cover exactly the text
+    assert_eq!(lexed.tokens[3].value(), "naïve");
+    assert_eq!(lexed.tokens[5].value(), "open");
+    for token in &lexed.tokens {
+        assert_eq!(&source[token.span().start..token.span().end], token.text);
+    }
+}
+
+#[test]
+fn symbols_are_interned() {
+    use rotor::lexer::{lex, Symbol};
+
+    let a = Symbol::intern("counter");
+    assert_eq!(a, Symbol::intern(&String::from("counter")));
+    assert_ne!(a, Symbol::intern("count"));
+    assert_eq!(a.as_str(), "counter");
+    assert_eq!(format!("`{}` {:?}", a, a), "`counter` \"counter\"");
+    // Ordered by text, not by when they were interned
+    assert!(Symbol::intern("zz_late") > Symbol::intern("aa_later"));
+
+    let lexed = lex("counter + 1");
+    assert_eq!(lexed.tokens[0].symbol(), a);
+}
+
+#[test]
+fn the_lexer_streams_what_lex_collects() {
+    use rotor::lexer::{lex, Lexer, TokenKind};
+
+    let source = "let s = \"a\"; // note\nx & y\n\"open";
+    let lexed = lex(source);
+    let mut lexer = Lexer::new(source);
+    // Looking ahead lexes on demand and doesn't consume anything
+    assert_eq!(lexer.peek_nth(3).unwrap().as_ref().unwrap().kind, TokenKind::String);
+    assert_eq!(lexer.peek().unwrap().as_ref().unwrap().kind, TokenKind::Let);
+
+    let (mut tokens, mut errors) = (vec![], vec![]);
+    for item in &mut lexer {
+        match item {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push((error.line(), error.column(), error.to_string())),
+        }
+    }
+    assert_eq!(tokens, lexed.tokens);
+    assert_eq!(errors.iter().map(|(_, _, message)| message.clone()).collect::<Vec<_>>(), lexed.errors);
+    assert_eq!((errors[0].0, errors[0].1), (2, 3));
+    // The position is kept out of the message, so it is printed once
+    assert_eq!(errors[0].2, "2:3: Invalid token: &");
+    assert_eq!(lexer.comments(), &lexed.comments[..]);
+}
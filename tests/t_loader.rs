@@ -0,0 +1,193 @@
+// Writes a throwaway project and returns the path of its `src/`
+fn project(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let src = std::env::temp_dir().join(format!("rotor-test-loader-{}-{}", name, std::process::id())).join("src");
+    let _ = std::fs::remove_dir_all(&src);
+    for (path, source) in files {
+        let file = src.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, source).unwrap();
+    }
+    src
+}
+
+fn messages(errors: &[rotor::Error]) -> Vec<String> {
+    errors.iter().map(|error| error.message().to_string()).collect()
+}
+
+#[test]
+fn parse_dotted_use_path() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, p_use_stmt};
+    use rotor::parser::nodes::{Stmt, UseImports};
+
+    let lexed = lex("use std.io [console]");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let Stmt::UseStmt { stator, imports: UseImports::List(imports), .. } = p_use_stmt(&mut stream).unwrap() else {
+        panic!("expected a use statement");
+    };
+
+    assert_eq!(stator, "std.io");
+    assert_eq!(imports, vec!["console"]);
+}
+
+#[test]
+fn load_links_imported_functions() {
+    use rotor::loader::load_program;
+    use rotor::typeck::check_program;
+    use rotor::parser::nodes::Stmt;
+
+    let src = project(
+        "link",
+        &[
+            ("main.rt", "use math.ops [*]\nconst LIMIT: i32 = 4;\npub fn main() i32 {\n    return double(LIMIT);\n}"),
+            ("math/ops.rt", "let LIMIT: i32 = 100;\npub fn double(x: i32) i32 {\n    let LIMIT = 2;\n    return x * LIMIT;\n}\nfn hidden() {}"),
+        ],
+    );
+    let mut program = load_program(&src.join("main.rt")).unwrap();
+    check_program(&mut program).unwrap();
+
+    let names: Vec<&str> = program
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::FnDecl { name, .. } | Stmt::LetStmt { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    // Dependencies first, qualified by their stator path
+    assert_eq!(names, vec!["math.ops.LIMIT", "math.ops.double", "math.ops.hidden", "LIMIT", "main"]);
+}
+
+#[test]
+fn load_parses_each_stator_once() {
+    use rotor::loader::Loader;
+
+    let src = project(
+        "diamond",
+        &[
+            ("main.rt", "use left [l]\nuse right [r]\npub fn main() i32 {\n    return l() + r();\n}"),
+            ("left.rt", "use shared [one]\npub fn l() i32 {\n    return one();\n}"),
+            ("right.rt", "use shared [one]\npub fn r() i32 {\n    return one();\n}"),
+            ("shared.rt", "pub fn one() i32 {\n    return 1;\n}"),
+        ],
+    );
+    let mut loader = Loader::new(&src);
+    loader.load_entry(&src.join("main.rt")).unwrap();
+
+    let names: Vec<&str> = loader.stators().iter().map(|stator| stator.name.as_str()).collect();
+    assert_eq!(names, vec!["shared", "left", "right", "main"]);
+}
+
+#[test]
+fn broken_stators_are_parsed_once() {
+    use rotor::loader::Loader;
+
+    let src = project(
+        "broken",
+        &[
+            ("main.rt", "use util [helper]\nuse util.deep [inner]\npub fn main() {}"),
+            ("util.rt", "pub fn helper() {\n    helper()\n}"),
+            ("util/deep.rt", "use util [helper]\npub fn inner() {}"),
+        ],
+    );
+    let mut loader = Loader::new(&src);
+    let errors = loader.load_entry(&src.join("main.rt")).err().unwrap();
+
+    assert_eq!(messages(&errors), vec!["Expected ;, found }"]);
+    assert_eq!(loader.sources().files().filter(|(_, file)| file.path == src.join("util.rt")).count(), 1);
+}
+
+#[test]
+fn load_reports_import_cycles() {
+    use rotor::loader::load_program;
+    use rotor::ErrorKind;
+
+    let src = project(
+        "cycle",
+        &[
+            ("main.rt", "use a [f]\npub fn main() {\n    f();\n}"),
+            ("a.rt", "use b [g]\npub fn f() {\n    g();\n}"),
+            ("b.rt", "use a [f]\npub fn g() {}"),
+        ],
+    );
+    let errors = load_program(&src.join("main.rt")).err().unwrap();
+
+    assert_eq!(errors[0].kind(), &ErrorKind::ImportCycle);
+    assert!(errors[0].message().starts_with("Import cycle: a -> b -> a"));
+}
+
+#[test]
+fn load_honours_visibility() {
+    use rotor::loader::load_program;
+    use rotor::ErrorKind;
+
+    let src = project(
+        "private",
+        &[
+            ("main.rt", "use util [helper, secret, missing]\npub fn main() {}"),
+            ("util.rt", "pub fn helper() {}\nfn secret() {}"),
+        ],
+    );
+    let errors = load_program(&src.join("main.rt")).err().unwrap();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind(), &ErrorKind::PrivateItem);
+    assert!(messages(&errors)[0].starts_with("`secret` is private to stator `util`"));
+    assert_eq!(errors[1].kind(), &ErrorKind::UnknownImport);
+    assert!(messages(&errors)[1].starts_with("Stator `util` has no item `missing`"));
+}
+
+#[test]
+fn load_reports_unknown_stators() {
+    use rotor::loader::load_program;
+    use rotor::ErrorKind;
+
+    let src = project("unknown", &[("main.rt", "pub fn main() {}\nuse nowhere.near [x]")]);
+    let errors = load_program(&src.join("main.rt")).err().unwrap();
+
+    assert_eq!(errors[0].kind(), &ErrorKind::UnknownStator);
+    assert_eq!((errors[0].line(), errors[0].column()), (2, 1));
+}
+
+#[test]
+fn errors_point_at_the_file_they_are_about() {
+    use rotor::loader::Loader;
+    use rotor::typeck::check_program;
+
+    let src = project(
+        "files",
+        &[
+            ("main.rt", "use util [helper, missing]\npub fn main() {\n    helper();\n}"),
+            ("util.rt", "pub fn helper() {\n    let x: i32 = true;\n}"),
+        ],
+    );
+    let mut loader = Loader::new(&src);
+    loader.load_entry(&src.join("main.rt")).unwrap();
+    let errors = loader.link().err().unwrap();
+    let sources = loader.sources();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources.file_of(&errors[0]).unwrap().path, src.join("main.rt"));
+    assert_eq!(sources.describe(&errors[0]), format!("{}:1:1: Stator `util` has no item `missing`", src.join("main.rt").display()));
+
+    let mut loader = Loader::new(&src);
+    loader.load_entry_source(&src.join("main.rt"), "use util [helper]\npub fn main() {\n    helper();\n}").unwrap();
+    let errors = check_program(&mut loader.link().unwrap()).err().unwrap();
+    assert_eq!(loader.sources().file_of(&errors[0]).unwrap().path, src.join("util.rt"));
+    assert_eq!((errors[0].line(), errors[0].column()), (2, 18));
+}
+
+#[test]
+fn unimported_functions_stay_invisible() {
+    use rotor::loader::load_program;
+    use rotor::typeck::check_program;
+
+    let src = project(
+        "invisible",
+        &[
+            ("main.rt", "use util [helper]\npub fn main() {\n    helper();\n    other();\n}"),
+            ("util.rt", "pub fn helper() {}\npub fn other() {}"),
+        ],
+    );
+    let mut program = load_program(&src.join("main.rt")).unwrap();
+
+    assert!(check_program(&mut program).is_err());
+}
@@ -0,0 +1,338 @@
+// Talks to `rotor lsp` like an editor would: a script of messages goes in,
+// and the replies and notifications that come out are checked
+
+use rotor::lsp::Json;
+
+// Runs the server on `messages` (followed by `shutdown` and `exit`) and
+// returns everything it sent back, with its exit code
+fn session(messages: &[Json]) -> (Vec<Json>, Option<i32>) {
+    use rotor::lsp::write_message;
+
+    let mut input = vec![];
+    for message in messages {
+        write_message(&mut input, message).unwrap();
+    }
+    session_raw(input)
+}
+
+// Like `session`, for input that may not be well formed
+fn session_raw(mut input: Vec<u8>) -> (Vec<Json>, Option<i32>) {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use rotor::lsp::{read_message, write_message};
+
+    write_message(&mut input, &request(999, "shutdown", Json::Null)).unwrap();
+    write_message(&mut input, &notification("exit", Json::Null)).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rotor"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let mut replies = vec![];
+    let mut reader = &output.stdout[..];
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        replies.push(message.unwrap());
+    }
+    (replies, output.status.code())
+}
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+    Json::object([("jsonrpc", "2.0".into()), ("id", id.into()), ("method", method.into()), ("params", params)])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([("jsonrpc", "2.0".into()), ("method", method.into()), ("params", params)])
+}
+
+fn open(uri: &str, text: &str) -> Json {
+    let document = Json::object([("uri", uri.into()), ("languageId", "rotor".into()), ("version", 1usize.into()), ("text", text.into())]);
+    notification("textDocument/didOpen", Json::object([("textDocument", document)]))
+}
+
+fn at(uri: &str, line: usize, character: usize) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", uri.into())])),
+        ("position", Json::object([("line", line.into()), ("character", character.into())])),
+    ])
+}
+
+fn reply(replies: &[Json], id: usize) -> &Json {
+    let id = Json::from(id);
+    &replies.iter().find(|message| message["id"] == id).expect("no reply")["result"]
+}
+
+fn diagnostics(replies: &[Json]) -> Vec<&Json> {
+    replies.iter().filter(|message| message["method"].as_str() == Some("textDocument/publishDiagnostics")).collect()
+}
+
+// A position as `(line, character)`
+fn start(range: &Json) -> (u64, u64) {
+    (range["start"]["line"].as_u64().unwrap(), range["start"]["character"].as_u64().unwrap())
+}
+
+fn uri(name: &str) -> String {
+    format!("file://{}/rotor-test-lsp-{}-{}.rt", std::env::temp_dir().display(), name, std::process::id())
+}
+
+const PROGRAM: &str = "use std.io [console]
+
+fn add(a: i32, b: i32) i32 {
+    return a + b;
+}
+
+pub fn main() i32 {
+    let total = add(1, 2);
+    console.out(\"hi\");
+    return total;
+}
+";
+
+#[test]
+fn json_round_trips() {
+    let text = r#"{"a":[1,2.5,-3],"b":"q\"\né😀","c":null,"d":true}"#;
+    let json = Json::parse(text).unwrap();
+    assert_eq!(json["b"].as_str(), Some("q\"\né😀"));
+    assert_eq!(json["a"].as_array().len(), 3);
+    assert!(json["missing"].is_null());
+    assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+    assert!(Json::parse("{\"a\":}").is_err());
+}
+
+#[test]
+fn initialize_and_shut_down() {
+    let (replies, code) = session(&[request(1, "initialize", Json::object([])), notification("initialized", Json::object([]))]);
+    let capabilities = &reply(&replies, 1)["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], Json::Bool(true));
+    assert_eq!(capabilities["definitionProvider"], Json::Bool(true));
+    assert_eq!(capabilities["referencesProvider"], Json::Bool(true));
+    assert_eq!(capabilities["documentSymbolProvider"], Json::Bool(true));
+    assert!(reply(&replies, 999).is_null());
+    assert_eq!(code, Some(0));
+
+    let (replies, _) = session(&[request(2, "textDocument/frobnicate", Json::Null)]);
+    let error = &replies.iter().find(|message| message["id"] == Json::from(2usize)).unwrap()["error"];
+    assert_eq!(error["code"], Json::Number(-32601.0));
+}
+
+#[test]
+fn diagnostics_follow_every_change() {
+    let uri = uri("diagnostics");
+    let change = |text: &str| {
+        let changes = vec![Json::object([("text", text.into())])];
+        let document = Json::object([("uri", uri.as_str().into()), ("version", 2usize.into())]);
+        notification("textDocument/didChange", Json::object([("textDocument", document), ("contentChanges", changes.into())]))
+    };
+    let (replies, _) = session(&[
+        open(&uri, "fn main() {\n    let x = ;\n}\n"),
+        change("fn main() {\n    let unused = 1;\n}\n\nfn other() {\n    let y = 2 $ 3;\n}\n"),
+        change("fn main() {\n}\n"),
+    ]);
+    let published = diagnostics(&replies);
+    assert_eq!(published.len(), 3);
+
+    let first = published[0]["params"]["diagnostics"].as_array();
+    assert!(!first.is_empty());
+    assert_eq!(first[0]["severity"].as_u64(), Some(1));
+    assert_eq!(start(&first[0]["range"]).0, 1);
+
+    // A lexer error and a lint warning
+    let second = published[1]["params"]["diagnostics"].as_array();
+    let invalid = second.iter().find(|diagnostic| diagnostic["message"].as_str().unwrap().contains('$')).unwrap();
+    assert_eq!(start(&invalid["range"]), (5, 14));
+    let warning = second.iter().find(|diagnostic| diagnostic["severity"].as_u64() == Some(2)).unwrap();
+    assert!(warning["message"].as_str().unwrap().contains("`unused`"));
+    assert_eq!(start(&warning["range"]), (1, 4));
+
+    assert!(published[2]["params"]["diagnostics"].as_array().is_empty());
+}
+
+#[test]
+fn symbols_and_hover() {
+    let uri = uri("hover");
+    let symbols = Json::object([("textDocument", Json::object([("uri", uri.as_str().into())]))]);
+    let (replies, _) = session(&[
+        open(&uri, PROGRAM),
+        request(1, "textDocument/documentSymbol", symbols),
+        // `total` where it is declared, and `add` where it is called
+        request(2, "textDocument/hover", at(&uri, 7, 9)),
+        request(3, "textDocument/hover", at(&uri, 7, 17)),
+        request(4, "textDocument/hover", at(&uri, 8, 13)),
+        request(5, "textDocument/hover", at(&uri, 0, 1)),
+    ]);
+    let names: Vec<&str> = reply(&replies, 1).as_array().iter().map(|symbol| symbol["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["add", "main"]);
+    assert_eq!(start(&reply(&replies, 1).as_array()[1]["selectionRange"]), (6, 7));
+
+    let hover = |id| reply(&replies, id)["contents"]["value"].as_str().unwrap().to_string();
+    assert!(hover(2).contains("let total: i32"));
+    assert!(hover(3).contains("fn add(a: i32, b: i32) i32"));
+    assert!(hover(4).contains("pub fn out(message: str)"));
+    assert!(hover(4).contains("std.io.console"));
+    assert!(reply(&replies, 5).is_null());
+}
+
+#[test]
+fn definitions_and_references() {
+    let uri = uri("references");
+    let references = |id, include| {
+        let mut params = at(&uri, 3, 11);
+        if let Json::Object(pairs) = &mut params {
+            pairs.push(("context".to_string(), Json::object([("includeDeclaration", Json::Bool(include))])));
+        }
+        request(id, "textDocument/references", params)
+    };
+    let (replies, _) = session(&[
+        open(&uri, PROGRAM),
+        request(1, "textDocument/definition", at(&uri, 7, 17)),
+        request(2, "textDocument/definition", at(&uri, 9, 12)),
+        references(3, true),
+        references(4, false),
+        // Standard library items have no file to go to
+        request(5, "textDocument/definition", at(&uri, 8, 13)),
+    ]);
+    let definition = reply(&replies, 1);
+    assert_eq!(definition["uri"].as_str(), Some(uri.as_str()));
+    assert_eq!(start(&definition["range"]), (2, 3));
+    assert_eq!(start(&reply(&replies, 2)["range"]), (7, 8));
+
+    // `a` in `return a + b`, declared as a parameter
+    let found: Vec<(u64, u64)> = reply(&replies, 3).as_array().iter().map(|location| start(&location["range"])).collect();
+    assert_eq!(found, [(2, 7), (3, 11)]);
+    assert_eq!(reply(&replies, 4).as_array().len(), 1);
+    assert!(reply(&replies, 5).is_null());
+}
+
+#[test]
+fn definitions_in_other_files() {
+    let dir = std::env::temp_dir().join(format!("rotor-test-lsp-project-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("util.rt"), "// Helpers\npub fn twice(x: i32) i32 {\n    return x * 2;\n}\n").unwrap();
+    let uri = format!("file://{}/main.rt", dir.display());
+    let text = "use util [twice]\n\npub fn main() i32 {\n    return twice(4);\n}\n";
+
+    let (replies, _) = session(&[
+        open(&uri, text),
+        request(1, "textDocument/definition", at(&uri, 3, 12)),
+        request(2, "textDocument/hover", at(&uri, 0, 12)),
+    ]);
+    assert!(diagnostics(&replies)[0]["params"]["diagnostics"].as_array().is_empty());
+    let definition = reply(&replies, 1);
+    assert!(definition["uri"].as_str().unwrap().ends_with("/util.rt"));
+    assert_eq!(start(&definition["range"]), (1, 7));
+    assert!(reply(&replies, 2)["contents"]["value"].as_str().unwrap().contains("pub fn twice(x: i32) i32"));
+}
+
+#[test]
+fn semantic_tokens_classify_names() {
+    let uri = uri("semantic");
+    let document = Json::object([("textDocument", Json::object([("uri", uri.as_str().into())]))]);
+    let (replies, _) = session(&[
+        request(1, "initialize", Json::object([])),
+        open(&uri, PROGRAM),
+        request(2, "textDocument/semanticTokens/full", document),
+    ]);
+    let legend = &reply(&replies, 1)["capabilities"]["semanticTokensProvider"]["legend"];
+    let types: Vec<&str> = legend["tokenTypes"].as_array().iter().map(|name| name.as_str().unwrap()).collect();
+    let modifiers: Vec<&str> = legend["tokenModifiers"].as_array().iter().map(|name| name.as_str().unwrap()).collect();
+
+    // Decode the relative positions back to (line, column, text, type, modifiers)
+    let lines: Vec<&str> = PROGRAM.lines().collect();
+    let data: Vec<usize> = reply(&replies, 2)["data"].as_array().iter().map(|n| n.as_u64().unwrap() as usize).collect();
+    let (mut line, mut column) = (0, 0);
+    let mut tokens = vec![];
+    for chunk in data.chunks(5) {
+        column = if chunk[0] == 0 { column + chunk[1] } else { chunk[1] };
+        line += chunk[0];
+        let mods: Vec<&str> = modifiers.iter().enumerate().filter(|(bit, _)| chunk[4] & (1 << bit) != 0).map(|(_, name)| *name).collect();
+        tokens.push((line, &lines[line][column..column + chunk[2]], types[chunk[3]], mods));
+    }
+    let find = |line: usize, text: &str| tokens.iter().find(|token| token.0 == line && token.1 == text).map(|token| (token.2, token.3.clone()));
+
+    assert_eq!(find(0, "use"), Some(("keyword", vec![])));
+    assert_eq!(find(0, "console"), Some(("namespace", vec!["declaration"])));
+    assert_eq!(find(2, "add"), Some(("function", vec!["declaration"])));
+    assert_eq!(find(2, "a"), Some(("parameter", vec!["declaration"])));
+    assert_eq!(find(2, "i32"), Some(("type", vec![])));
+    assert_eq!(find(3, "b"), Some(("parameter", vec![])));
+    assert_eq!(find(3, "+"), Some(("operator", vec![])));
+    assert_eq!(find(7, "total"), Some(("variable", vec!["declaration"])));
+    assert_eq!(find(7, "1"), Some(("number", vec![])));
+    assert_eq!(find(8, "console"), Some(("namespace", vec![])));
+    assert_eq!(find(8, "out"), Some(("function", vec!["defaultLibrary"])));
+    assert_eq!(find(8, "\"hi\""), Some(("string", vec![])));
+}
+
+#[test]
+fn completion_in_broken_code() {
+    // Each case is a document, and where the cursor is in it
+    let cases = [
+        ("fn main() {\n    let count = 1;\n    \n}\n", 2, 4),
+        ("fn main() {\n    let count = 1;\n    re\n", 2, 6),
+        ("fn add(a: i32, b: i32) i32 {\n    let total = a + \n", 1, 20),
+        ("use std.io [con", 0, 15),
+        ("use std.io.console [out, ", 0, 25),
+        ("use std.", 0, 8),
+        ("use std.io [console]\n\npub fn main() i32 {\n    console.\n}\n", 3, 12),
+        ("fn f(x: ", 0, 8),
+        ("\n", 0, 0),
+    ];
+    let uris: Vec<String> = (0..cases.len()).map(|index| uri(&format!("completion{}", index))).collect();
+    let mut messages = vec![];
+    for (index, (text, line, character)) in cases.iter().enumerate() {
+        messages.push(open(&uris[index], text));
+        messages.push(request(index + 1, "textDocument/completion", at(&uris[index], *line, *character)));
+    }
+    let (replies, _) = session(&messages);
+    let labels = |id: usize| -> Vec<String> {
+        reply(&replies, id).as_array().iter().map(|item| item["label"].as_str().unwrap().to_string()).collect()
+    };
+    let has = |id: usize, expected: &[&str]| {
+        let labels = labels(id);
+        for label in expected {
+            assert!(labels.iter().any(|other| other == label), "{} missing from case {}: {:?}", label, id, labels);
+        }
+    };
+
+    // Statement position in a block: block keywords and the names in scope
+    has(1, &["let", "if", "while", "return", "count", "main"]);
+    assert!(!labels(1).contains(&"fn".to_string()));
+    assert_eq!(labels(2), ["return"]);
+    // Expression position: no statement keywords
+    has(3, &["a", "b", "add", "true"]);
+    assert!(!labels(3).contains(&"let".to_string()));
+
+    assert_eq!(labels(4), ["console"]);
+    assert_eq!(labels(5), ["err", "read_line"]);
+    assert_eq!(labels(6), ["io", "math", "process", "string"]);
+    assert_eq!(labels(7), ["out", "err", "read_line"]);
+    let out = &reply(&replies, 7).as_array()[0];
+    assert_eq!(out["detail"].as_str(), Some("pub fn out(message: str)"));
+    assert_eq!(labels(8), ["i32", "bool", "str"]);
+    has(9, &["fn", "use", "pub"]);
+}
+
+#[test]
+fn malformed_messages_get_a_parse_error() {
+    let mut input = b"Content-Length: 5\r\n\r\n{oops".to_vec();
+    rotor::lsp::write_message(&mut input, &request(1, "initialize", Json::object([]))).unwrap();
+    let (replies, code) = session_raw(input);
+
+    assert_eq!(replies[0]["id"], Json::Null);
+    assert_eq!(replies[0]["error"]["code"], Json::Number(-32700.0));
+    assert!(replies[1]["result"]["capabilities"].get("hoverProvider").is_some());
+    assert_eq!(replies[2]["id"], Json::from(999usize));
+    assert_eq!(code, Some(0));
+}
+
+#[test]
+fn huge_content_lengths_are_refused() {
+    use rotor::lsp::read_message;
+
+    let mut input = &b"Content-Length: 99999999999999\r\n\r\n{}"[..];
+    assert!(read_message(&mut input).is_err());
+}
@@ -0,0 +1,147 @@
+fn optimize(src: &str, level: rotor::opt::OptLevel) -> rotor::ir::Module {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::lower_program;
+    use rotor::opt::PassManager;
+
+    let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let mut module = lower_program(&program, &checked);
+    PassManager::for_level(level).run(&mut module).unwrap();
+    module
+}
+
+#[test]
+fn o0_leaves_ir_alone() {
+    use rotor::opt::OptLevel;
+
+    let module = optimize("fn f() i32 {\n    return 6 * 7;\n}", OptLevel::O0);
+
+    assert!(module.to_string().contains("mul"));
+}
+
+#[test]
+fn fold_constants_and_const_globals() {
+    use rotor::opt::OptLevel;
+
+    let module = optimize("const SIX: i32 = 6;\nfn f() i32 {\n    let seven = 7;\n    return SIX * seven;\n}", OptLevel::O1);
+
+    assert_eq!(
+        module.to_string(),
+        "const @SIX: i32 = 6
+
+fn @f() -> i32 {
+bb0:
+    %0: i32 = const 42
+    ret %0
+}
+"
+    );
+}
+
+#[test]
+fn fold_branches_and_remove_unreachable_blocks() {
+    use rotor::opt::OptLevel;
+
+    let module = optimize(
+        "use std.io [console]
+const DEBUG: bool = false;
+fn f(x: i32) i32 {
+    if DEBUG {
+        console.out(\"debug\");
+        x = x + 1;
+    }
+    return x;
+}",
+        OptLevel::O1,
+    );
+    let function = module.function("f").unwrap();
+
+    assert_eq!(function.blocks.len(), 1);
+    assert!(!module.to_string().contains("call"));
+}
+
+#[test]
+fn division_by_zero_is_kept() {
+    use rotor::opt::OptLevel;
+
+    let module = optimize("fn f() {\n    let x = 1 / 0;\n}", OptLevel::O2);
+
+    assert!(module.to_string().contains("div"));
+}
+
+#[test]
+fn inline_small_functions() {
+    use rotor::opt::OptLevel;
+
+    let src = "fn square(x: i32) i32 {\n    return x * x;\n}\nfn f(y: i32) i32 {\n    return square(y) + square(3);\n}";
+    let o1 = optimize(src, OptLevel::O1);
+    let o2 = optimize(src, OptLevel::O2);
+
+    assert!(o1.to_string().contains("call @square"));
+    let f = o2.function("f").unwrap().to_string();
+    assert!(!f.contains("call"));
+    assert!(f.contains("const 9"));
+}
+
+#[test]
+fn recursive_functions_are_not_inlined() {
+    use rotor::opt::OptLevel;
+
+    let module = optimize("fn down(n: i32) i32 {\n    if n == 0 {\n        return 0;\n    }\n    return down(n - 1);\n}", OptLevel::O2);
+
+    assert!(module.to_string().contains("call @down"));
+}
+
+#[test]
+fn dump_after_each_changing_pass() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::typeck::check_program;
+    use rotor::ir::lower_program;
+    use rotor::opt::{OptLevel, PassManager};
+
+    let lexed = lex("fn f() i32 {\n    return 1 + 2;\n}");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let mut program = parse_program(&mut stream).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let mut module = lower_program(&program, &checked);
+
+    let mut passes = vec![];
+    PassManager::for_level(OptLevel::O1)
+        .run_with_dump(&mut module, &mut |pass, _| passes.push(pass.to_string()))
+        .unwrap();
+
+    assert_eq!(passes, vec!["const-fold", "simplify-cfg", "dce"]);
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn optimized_programs_behave_the_same() {
+    use rotor::codegen::{emit_asm, build_asm};
+    use rotor::opt::OptLevel;
+
+    let src = "
+const LIMIT: i32 = 10;
+fn square(x: i32) i32 { return x * x; }
+fn pick(c: bool, a: i32, b: i32) i32 { if c { return a; } return b; }
+fn fib(n: i32) i32 { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); }
+fn main() i32 {
+    let total = 0;
+    for i in 0..LIMIT { total = total + square(i) % 7; }
+    let done = false;
+    while !done { total = total - 3; done = total % 5 == 0 || total < 0; }
+    return pick(total > 50, total, fib(LIMIT) - total) + square(LIMIT / 5);
+}";
+    let mut codes = vec![];
+    for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+        let module = optimize(src, level);
+        let asm = emit_asm(&module).unwrap();
+        let output = std::env::temp_dir().join(format!("rotor-test-opt-{:?}-{}", level, std::process::id()));
+        build_asm(&asm, &output).unwrap();
+        codes.push(std::process::Command::new(&output).status().unwrap().code());
+        let _ = std::fs::remove_file(&output);
+    }
+
+    assert_eq!(codes, vec![Some(49); 3]);
+}
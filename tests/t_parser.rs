@@ -1,5 +1,213 @@
 #[test]
 pub fn parse_let_stmt() {
-    assert_eq!(5, 5);
-    // Empty for now.
-}
\ No newline at end of file
+    use rotor::lexer::{lex, TokenKind};
+    use rotor::parser::{TokenStream, p_let_stmt, Expr, Stmt};
+
+    let lexed = lex("const x: i32 = 1 + 2 * 3;");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let stmt = p_let_stmt(&mut stream).unwrap();
+
+    let Stmt::LetStmt { name, ty, value, constant, .. } = stmt else { panic!("expected a let statement") };
+    assert_eq!(name, "x");
+    assert_eq!(ty, Some(TokenKind::I32));
+    assert!(constant);
+    // `*` binds tighter than `+`
+    let Expr::BinaryOp { op, right, .. } = *value else { panic!("expected a binary operation") };
+    assert_eq!(op, TokenKind::Plus);
+    assert!(matches!(*right, Expr::BinaryOp { op: TokenKind::Star, .. }));
+}
+
+#[test]
+pub fn parse_fn_decl() {
+    use rotor::lexer::{lex, TokenKind};
+    use rotor::parser::{TokenStream, parse_program, Stmt};
+
+    let lexed = lex("pub fn add(a: i32, b: i32) i32 {\n    return a + b;\n}\n");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let program = parse_program(&mut stream).unwrap();
+
+    assert_eq!(program.len(), 1);
+    let Stmt::FnDecl { name, params, return_ty, body, public, .. } = &program[0] else { panic!("expected a function") };
+    assert_eq!(name, "add");
+    assert_eq!(params.len(), 2);
+    assert_eq!(params[1].name, "b");
+    assert_eq!(*return_ty, Some(TokenKind::I32));
+    assert!(*public);
+    assert!(matches!(body.as_deref(), Some([Stmt::ReturnStmt { .. }])));
+}
+
+#[test]
+pub fn parse_control_flow() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program, Stmt};
+
+    let src = "fn main() {\n    for i in 0..10 {\n        if i == 3 { x = 1; } else if i > 5 { foo(i); } else { }\n    }\n    while !done { done = true; }\n}";
+    let lexed = lex(src);
+    let mut stream = TokenStream::new(lexed.tokens);
+    let program = parse_program(&mut stream).unwrap();
+
+    let Stmt::FnDecl { body: Some(body), .. } = &program[0] else { panic!("expected a function") };
+    assert!(matches!(body[0], Stmt::ForStmt { .. }));
+    assert!(matches!(body[1], Stmt::WhileStmt { .. }));
+    let Stmt::ForStmt { body: for_body, .. } = &body[0] else { unreachable!() };
+    let Stmt::IfStmt { else_branch: Some(else_branch), .. } = &for_body[0] else { panic!("expected an if statement") };
+    assert!(matches!(else_branch.as_slice(), [Stmt::IfStmt { else_branch: Some(_), .. }]));
+}
+
+#[test]
+pub fn parse_error_reports_position() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+
+    let lexed = lex("let x = 5;\nlet = 6;");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let error = parse_program(&mut stream).err().unwrap();
+
+    assert_eq!((error.line(), error.column()), (2, 5));
+}
+
+#[test]
+fn parse_recovers_after_errors() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program_recovering};
+
+    let lexed = lex("fn one() {\n    let = 3;\n}\nfn two() {\n    return 1 +;\n}\npub fn main() i32 { return 0; }");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let (program, errors) = parse_program_recovering(&mut stream);
+
+    let positions: Vec<(usize, usize)> = errors.iter().map(|error| (error.line(), error.column())).collect();
+    assert_eq!(positions, vec![(2, 9), (5, 15)]);
+    // `main` still parses after the two broken functions
+    assert_eq!(program.len(), 1);
+}
+
+#[test]
+fn visitors_reach_every_node() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::parser::nodes::{walk_expr, walk_expr_mut, Expr, FnParam, Visitor, VisitorMut};
+
+    // Collects the names of variables and parameters
+    struct Names(Vec<String>);
+    impl Visitor for Names {
+        fn visit_param(&mut self, param: &FnParam) {
+            self.0.push(param.name.to_string());
+        }
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let Expr::Variable { name, .. } = expr {
+                self.0.push(name.to_string());
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    // Renames a variable everywhere it is read
+    struct Rename;
+    impl VisitorMut for Rename {
+        fn visit_expr_mut(&mut self, expr: &mut Expr) {
+            if let Expr::Variable { name, .. } = expr {
+                if name == "a" {
+                    *name = "b".into();
+                }
+            }
+            walk_expr_mut(self, expr);
+        }
+    }
+
+    let src = "fn f(a: i32) i32 {\n    while a > 0 {\n        if !(a == 2) { g([a, -a]); } else { return a; }\n    }\n    for i in 0..a { x = i; }\n    return a;\n}";
+    let lexed = lex(src);
+    let mut stream = TokenStream::new(lexed.tokens);
+    let mut program = parse_program(&mut stream).unwrap();
+
+    let mut names = Names(vec![]);
+    names.visit_program(&program);
+    assert_eq!(names.0, vec!["a", "a", "a", "a", "a", "a", "a", "i", "a"]);
+
+    Rename.visit_program_mut(&mut program);
+    let mut names = Names(vec![]);
+    names.visit_program(&program);
+    assert_eq!(names.0, vec!["a", "b", "b", "b", "b", "b", "b", "i", "b"]);
+}
+
+#[test]
+fn dump_shows_nesting() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, dump_program, parse_program};
+
+    let lexed = lex("use std.io [*]\nfn f() {\n    if true { g(\"a\"); } else { x = [1]; }\n}");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let program = parse_program(&mut stream).unwrap();
+
+    let expected = "\
+Use std.io [*] @1:1
+FnDecl f() @2:1
+  If @3:5
+    Boolean true @3:8
+    then
+      CallStmt @3:15
+        FnCall g @3:15
+          String \"a\" @3:17
+    else
+      Assign x @3:32
+        Array @3:36
+          Integer 1 @3:37
+";
+    assert_eq!(dump_program(&program), expected);
+}
+
+#[test]
+fn parse_pulls_tokens_from_a_lexer() {
+    use rotor::lexer::{lex, Lexer};
+    use rotor::parser::{parse_program_recovering, TokenStream};
+
+    let src = "fn f(a: i32) i32 {\n    return a & 1;\n}\nfn g() { f(\"x\"); }\n";
+    let (eager, eager_errors) = parse_program_recovering(&mut TokenStream::new(lex(src).tokens));
+    let mut stream = TokenStream::from_lexer(Lexer::new(src));
+    let (streamed, errors) = parse_program_recovering(&mut stream);
+    assert_eq!(streamed, eager);
+    assert_eq!(errors.len(), eager_errors.len());
+    let lex_errors = stream.take_lex_errors();
+    assert_eq!(lex_errors.len(), 1);
+    assert_eq!((lex_errors[0].line(), lex_errors[0].column()), (2, 14));
+}
+
+#[test]
+fn token_stream_looks_ahead_and_rewinds() {
+    use rotor::lexer::{Lexer, TokenKind};
+    use rotor::parser::TokenStream;
+
+    let mut stream = TokenStream::from_lexer(Lexer::new("x = f(1)\n"));
+    assert_eq!(stream.peek_nth(1).kind, TokenKind::Equal);
+    assert_eq!(stream.peek_nth(3).kind, TokenKind::LParen);
+    let start = stream.checkpoint();
+    assert!(stream.eat(TokenKind::Identifier));
+    assert!(!stream.eat(TokenKind::Semicolon));
+    assert!(stream.eat(TokenKind::Equal));
+    stream.rewind(start);
+    assert_eq!(stream.next().map(|token| token.kind), Some(TokenKind::Identifier));
+
+    // Past the last token there is only the end of the input
+    let eof = *stream.peek_nth(10);
+    assert_eq!(eof.kind, TokenKind::Eof);
+    assert_eq!((eof.line, eof.column, eof.span().start), (2, 1, 9));
+    assert_eq!(stream.peek_nth(0).kind, TokenKind::Equal);
+}
+
+#[test]
+fn running_out_of_tokens_is_reported_at_the_end() {
+    use rotor::lexer::{lex, Lexer};
+    use rotor::parser::{parse_program_recovering, TokenStream};
+
+    let (_, errors) = parse_program_recovering(&mut TokenStream::new(lex("fn main() {\n    let x = 1;").tokens));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "Expected }, found end of file");
+    assert_eq!((errors[0].line(), errors[0].column()), (2, 15));
+
+    // Past trailing comments and blank lines, where the file really ends
+    let src = "fn main() {\n// done\n\n/* c */";
+    let lexed = lex(src);
+    let (_, errors) = parse_program_recovering(&mut TokenStream::with_eof(lexed.tokens, lexed.eof));
+    assert_eq!((errors[0].line(), errors[0].column()), (4, 8));
+    let (_, errors) = parse_program_recovering(&mut TokenStream::from_lexer(Lexer::new(src)));
+    assert_eq!((errors[0].line(), errors[0].column()), (4, 8));
+}
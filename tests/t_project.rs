@@ -0,0 +1,181 @@
+// A fresh directory for one test to create projects in
+fn scratch(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rotor-test-project-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn new_creates_a_runnable_project() {
+    use rotor::project::{new_project, Template};
+    use rotor::loader::load_program;
+    use rotor::typeck::check_program;
+
+    let dir = scratch("new").join("my_first_project");
+    let files = new_project(&dir, "my_first_project", Template::Bin).unwrap();
+
+    assert_eq!(files, vec![dir.join("rotor.conf"), dir.join("src/main.rt")]);
+    let manifest = std::fs::read_to_string(dir.join("rotor.conf")).unwrap();
+    assert!(manifest.contains("name = \"my_first_project\""));
+    assert!(manifest.contains("entry = \"src/main.rt\""));
+    let mut program = load_program(&dir.join("src/main.rt")).unwrap();
+    check_program(&mut program).unwrap();
+}
+
+#[test]
+fn new_lib_template() {
+    use rotor::project::{new_project, Template};
+
+    let dir = scratch("lib").join("shapes");
+    new_project(&dir, "shapes", Template::Lib).unwrap();
+
+    assert!(dir.join("src/lib.rt").is_file());
+    assert!(!dir.join("src/main.rt").exists());
+    assert!(std::fs::read_to_string(dir.join("rotor.conf")).unwrap().contains("kind = \"lib\""));
+}
+
+#[test]
+fn new_and_init_refuse_to_overwrite() {
+    use rotor::project::{init_project, new_project, Template};
+    use rotor::ErrorKind;
+
+    let dir = scratch("overwrite");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rt"), "pub fn main() {}").unwrap();
+
+    let error = new_project(&dir, "overwrite", Template::Bin).err().unwrap();
+    assert_eq!(error.kind(), &ErrorKind::Project);
+    let error = init_project(&dir, Template::Bin).err().unwrap();
+    assert!(error.message().contains("main.rt` already exists"));
+    // Nothing was written, not even the manifest
+    assert!(!dir.join("rotor.conf").exists());
+    assert_eq!(std::fs::read_to_string(dir.join("src/main.rt")).unwrap(), "pub fn main() {}");
+
+    // A library only needs `src/lib.rt`, which is free
+    init_project(&dir, Template::Lib).unwrap();
+    assert!(dir.join("src/lib.rt").is_file());
+}
+
+#[test]
+fn project_names_are_checked() {
+    use rotor::project::{is_valid_name, new_project, Template};
+
+    assert!(is_valid_name("my_first_project"));
+    assert!(is_valid_name("rotor-web"));
+    assert!(!is_valid_name("9lives"));
+    assert!(!is_valid_name("has space"));
+    assert!(!is_valid_name(""));
+
+    let dir = scratch("names").join("9lives");
+    assert!(new_project(&dir, "9lives", Template::Bin).is_err());
+    assert!(!dir.exists());
+}
+
+#[test]
+fn parse_full_manifest() {
+    use rotor::project::{parse_manifest, Kind};
+    use rotor::opt::OptLevel;
+
+    let manifest = parse_manifest(
+        "# An example
+name = \"shapes\"   # the stator name
+version = \"1.2.3\"
+edition = \"2025\"
+kind = \"lib\"
+
+[dependencies]
+geometry = \"../geometry\"
+
+[profile.release]
+backend = \"x86_64\"
+
+[profile.bench]
+opt-level = 1
+",
+    )
+    .unwrap();
+
+    assert_eq!(manifest.name, "shapes");
+    assert_eq!(manifest.version, "1.2.3");
+    assert_eq!(manifest.kind, Kind::Lib);
+    assert_eq!(manifest.entry, std::path::PathBuf::from("src/lib.rt"));
+    assert_eq!(manifest.dependencies[0].name, "geometry");
+    assert_eq!(manifest.dependencies[0].path, std::path::PathBuf::from("../geometry"));
+    let release = manifest.profile("release").unwrap();
+    assert_eq!((release.opt_level, release.backend.as_str()), (OptLevel::O2, "x86_64"));
+    assert_eq!(manifest.profile("bench").unwrap().opt_level, OptLevel::O1);
+    assert_eq!(manifest.profile("dev").unwrap().opt_level, OptLevel::O0);
+}
+
+#[test]
+fn manifest_errors_have_positions() {
+    use rotor::project::parse_manifest;
+    use rotor::ErrorKind;
+
+    let errors = parse_manifest(
+        "name = \"app\"
+version = 1.0
+name = \"again\"
+  edition = \"1999\"
+entry = \"src/main.rt
+[profile.dev]
+opt-level = \"fast\"
+[dependencies
+",
+    )
+    .err()
+    .unwrap();
+    let found: Vec<(usize, usize, &str)> = errors.iter().map(|error| (error.line(), error.column(), error.message())).collect();
+
+    assert!(errors.iter().all(|error| error.kind() == &ErrorKind::InvalidManifest));
+    assert_eq!(
+        found,
+        vec![
+            (2, 11, "Invalid value `1.0` (strings need double quotes)"),
+            (3, 1, "`name` is set more than once"),
+            (4, 13, "Unknown edition `1999` (the only edition is 2025)"),
+            (5, 9, "Unterminated string"),
+            (7, 13, "`opt-level` must be an integer, found a string"),
+            (8, 14, "Expected `]` at the end of the section header"),
+        ]
+    );
+
+    let errors = parse_manifest("version = \"0.1.0\"").err().unwrap();
+    assert_eq!(errors[0].message(), "The manifest has no `name`");
+}
+
+#[test]
+fn discover_walks_up_to_the_manifest() {
+    use rotor::project::{find_manifest, new_project, Project, Template};
+
+    let dir = scratch("discover").join("app");
+    new_project(&dir, "app", Template::Bin).unwrap();
+    let nested = dir.join("src/a/b");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    assert_eq!(find_manifest(&nested).unwrap(), dir.join("rotor.conf").canonicalize().unwrap());
+    let project = Project::discover(&nested).unwrap();
+    assert_eq!(project.manifest.name, "app");
+    assert!(project.entry().ends_with("src/main.rt"));
+    assert!(Project::discover(&std::env::temp_dir().join("rotor-no-such-dir")).is_err());
+}
+
+#[test]
+fn project_loads_its_dependencies() {
+    use rotor::project::{new_project, Project, Template};
+    use rotor::typeck::check_program;
+
+    let dir = scratch("deps");
+    new_project(&dir.join("shapes"), "shapes", Template::Lib).unwrap();
+    std::fs::write(dir.join("shapes/src/lib.rt"), "use shapes.inner [three]\npub fn add(a: i32, b: i32) i32 {\n    return a + b + three();\n}").unwrap();
+    std::fs::write(dir.join("shapes/src/inner.rt"), "pub fn three() i32 {\n    return 3;\n}").unwrap();
+    new_project(&dir.join("app"), "app", Template::Bin).unwrap();
+    let manifest = std::fs::read_to_string(dir.join("app/rotor.conf")).unwrap();
+    std::fs::write(dir.join("app/rotor.conf"), manifest + "shapes = \"../shapes\"\n").unwrap();
+    std::fs::write(dir.join("app/src/main.rt"), "use shapes [add]\npub fn main() i32 {\n    return add(1, 2);\n}").unwrap();
+
+    let project = Project::open(&dir.join("app/rotor.conf")).unwrap();
+    let mut program = project.load_program().unwrap();
+    check_program(&mut program).unwrap();
+}
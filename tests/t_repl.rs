@@ -0,0 +1,115 @@
+// Drives `rotor repl` through stdin, and the session and evaluator behind
+// it directly
+
+// Runs the REPL on `input` with its own history file, and returns what it
+// printed and the history it saved
+fn repl(name: &str, input: &str) -> (String, String) {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let history = std::env::temp_dir().join(format!("rotor-test-repl-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&history);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rotor"))
+        .arg("repl")
+        .env("ROTOR_HISTORY", &history)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let saved = std::fs::read_to_string(&history).unwrap_or_default();
+    let _ = std::fs::remove_file(&history);
+    (String::from_utf8(output.stdout).unwrap(), saved)
+}
+
+#[test]
+fn values_print_with_their_types() {
+    let (out, _) = repl("values", "1 + 2 * 3\n\"hi\"\n4 > 3 && false\n-2147483647 - 2\n");
+    assert_eq!(out, "7: i32\n\"hi\": str\nfalse: bool\n2147483647: i32\n");
+}
+
+#[test]
+fn the_environment_persists() {
+    let input = "\
+let x = 5
+fn square(n: i32) i32 {
+    return n * n;
+}
+square(x)
+x = x + 1;
+square(x)
+use std.io [console]
+console.out(\"hello\")
+fn square(n: i32) i32 { return n + n; }
+square(x)
+";
+    let (out, _) = repl("environment", input);
+    assert_eq!(out, "x: i32 = 5\n25: i32\n36: i32\nhello\n12: i32\n");
+}
+
+#[test]
+fn meta_commands_and_history() {
+    let input = "let flag = true\n:type flag || 1 > 2\n:ast -a * f(2)\n:tokens x+1\n:quit\n1\n";
+    let (out, history) = repl("meta", input);
+    let expected = "\
+flag: bool = true
+bool
+BinaryOp * @1:1
+  UnaryOp - @1:1
+    Variable a @1:2
+  FnCall f @1:6
+    Integer 2 @1:8
+Token: Identifier, Value: 'x', Line: 1, Column: 1
+Token: Plus, Value: '+', Line: 1, Column: 2
+Token: Integer, Value: '1', Line: 1, Column: 3
+";
+    assert_eq!(out, expected);
+    assert_eq!(history, "let flag = true\n:type flag || 1 > 2\n:ast -a * f(2)\n:tokens x+1\n:quit\n");
+}
+
+#[test]
+fn errors_leave_the_session_usable() {
+    let input = "let n = 1\nn + true\n10 / (n - 1)\nconst K = 3\nK = 4;\nmissing\nn\nfn forever(n: i32) i32 { return forever(n); }\nforever(n)\nn\n";
+    let (out, _) = repl("errors", input);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "n: i32 = 1");
+    assert!(lines[1].starts_with("error: "), "{}", lines[1]);
+    assert_eq!(lines[2], "error: panic: attempt to divide by zero (line 1, column 1)");
+    assert_eq!(lines[3], "K: i32 = 3");
+    assert!(lines[4].contains("constant `K`"), "{}", lines[4]);
+    assert!(lines[5].contains("`missing`"), "{}", lines[5]);
+    assert_eq!(lines[6], "1: i32");
+    assert_eq!(lines[7], "error: Stack overflow: more than 1000 nested calls");
+    assert_eq!(lines[8], "1: i32");
+}
+
+#[test]
+fn input_continues_while_braces_are_open() {
+    use rotor::repl::is_complete;
+
+    assert!(is_complete("1 + 2"));
+    assert!(!is_complete("fn f() {"));
+    assert!(!is_complete("if true {\n    if false {\n    }"));
+    assert!(is_complete("if true {\n}"));
+    // Braces inside strings don't count
+    assert!(is_complete("\"{\""));
+
+    let (out, _) = repl("multiline", "let total = 0\nfor i in 0..5 {\n    total = total + i;\n}\ntotal\n");
+    assert_eq!(out, "total: i32 = 0\n10: i32\n");
+}
+
+#[test]
+fn the_evaluator_matches_the_runtime() {
+    use rotor::repl::{Session, Value};
+
+    let mut session = Session::new(std::env::temp_dir());
+    let eval = |session: &mut Session, input: &str| session.eval(input).map_err(|errors| errors[0].message().to_string());
+    assert_eq!(eval(&mut session, "2147483647 + 1"), Ok("-2147483648: i32".to_string()));
+    assert_eq!(eval(&mut session, "(-2147483647 - 1) / -1"), Ok("-2147483648: i32".to_string()));
+    assert_eq!(eval(&mut session, "(-2147483647 - 1) % -1"), Ok("0: i32".to_string()));
+    assert_eq!(eval(&mut session, "7 % 0"), Err("panic: attempt to calculate the remainder with a divisor of zero".to_string()));
+    assert_eq!(eval(&mut session, "false && 1 / 0 == 0"), Ok("false: bool".to_string()));
+    assert_eq!(Value::Str("a".to_string()).to_string(), "a");
+}
@@ -0,0 +1,29 @@
+// Only built with `--features serde`
+#![cfg(feature = "serde")]
+
+#[test]
+fn ast_round_trips_through_json() {
+    use rotor::lexer::lex;
+    use rotor::parser::nodes::Program;
+    use rotor::parser::{TokenStream, parse_program};
+
+    let lexed = lex("use std.io [console]\npub fn main() i32 {\n    for i in 0..3 { console.out(\"hi\"); }\n    return -1;\n}");
+    let mut stream = TokenStream::new(lexed.tokens);
+    let program = parse_program(&mut stream).unwrap();
+
+    let json = serde_json::to_string(&program).unwrap();
+    assert!(json.starts_with("[{\"UseStmt\":{\"stator\":\"std.io\",\"imports\":{\"List\":[\"console\"]},"));
+    let back: Program = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, program);
+}
+
+#[test]
+fn tokens_serialize() {
+    use rotor::lexer::lex;
+
+    let lexed = lex("let x");
+    let json = serde_json::to_value(&lexed.tokens).unwrap();
+    assert_eq!(json[0]["kind"], "Let");
+    assert_eq!(json[1]["value"], "x");
+    assert_eq!(json[1]["column"], 5);
+}
@@ -0,0 +1,35 @@
+#[test]
+fn offsets_become_lines_and_columns_per_file() {
+    use rotor::lexer::lex;
+    use rotor::SourceMap;
+
+    let mut sources = SourceMap::new();
+    let main = sources.add("main.rt", "pub fn main() {\n\tf();\n}\n");
+    let util = sources.add("util.rt", "fn f() {}");
+    assert_ne!(main, util);
+    assert_eq!(sources.find(std::path::Path::new("util.rt")), Some(util));
+    assert_eq!(sources.text(util), "fn f() {}");
+
+    // Columns count as the lexer does, a tab taking four
+    for token in &lex(sources.text(main)).tokens {
+        assert_eq!(sources.location(main, token.span().start), (token.line, token.column));
+    }
+    assert_eq!(sources.location(util, 3), (1, 4));
+    assert_eq!(sources.location(util, 100), (1, 10));
+}
+
+#[test]
+fn errors_show_a_position_only_when_they_have_one() {
+    use rotor::lexer::Lexer;
+    use rotor::{Error, ErrorKind, SourceMap};
+
+    let mut sources = SourceMap::new();
+    let main = sources.add("main.rt", "x & y");
+    let error = Lexer::new(sources.text(main)).find_map(Result::err).unwrap();
+    assert_eq!(error.to_string(), "1:3: Invalid token: &");
+    assert_eq!(sources.describe(&error.in_file(main)), "main.rt:1:3: Invalid token: &");
+
+    let error = Error::new(ErrorKind::Backend, "`cc` failed", 0, 0);
+    assert_eq!(error.to_string(), "`cc` failed");
+    assert_eq!(sources.describe(&error), "`cc` failed");
+}
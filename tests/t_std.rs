@@ -0,0 +1,175 @@
+// Programs using the standard library. These need a C compiler on the
+// PATH (or in $CC); the x86-64 ones also need `as` and `ld`.
+
+fn compile(src: &str, name: &str, backend: &str) -> std::path::PathBuf {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::{lower_program, verify};
+    use rotor::codegen::{emit_c, build_c, emit_asm, build_asm};
+
+    let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let module = lower_program(&program, &checked);
+    verify(&module).unwrap();
+    let output = std::env::temp_dir().join(format!("rotor-test-std-{}-{}-{}", backend, name, std::process::id()));
+    if backend == "x86_64" {
+        build_asm(&emit_asm(&module).unwrap(), &output).unwrap();
+    } else {
+        build_c(&emit_c(&module).unwrap(), &output).unwrap();
+    }
+    output
+}
+
+fn run(path: &std::path::Path, args: &[&str], stdin: &str) -> std::process::Output {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_file(path);
+    output
+}
+
+const MATH: &str = "
+use std.math [abs, clamp, gcd, max, min, pow]
+
+pub fn main() i32 {
+    if abs(-7) != 7 || min(3, -2) != -2 || max(3, -2) != 3 {
+        return 1;
+    }
+    if clamp(15, 0, 10) != 10 || clamp(-5, 0, 10) != 0 {
+        return 2;
+    }
+    if pow(2, 10) != 1024 || pow(5, -1) != 0 {
+        return 3;
+    }
+    return gcd(-84, 36);
+}";
+
+const STRINGS: &str = "
+use std.io [console]
+use std.string [*]
+
+pub fn main() i32 {
+    let line = console.read_line();
+    if !starts_with(line, \"rotor\") || !ends_with(line, \"spinning\") {
+        return 1;
+    }
+    if index_of(line, \"is\") != 6 || contains(line, \"stopped\") || !equals(line, \"rotor is spinning\") {
+        return 2;
+    }
+    if !is_empty(console.read_line()) {
+        return 3;
+    }
+    return parse_int(\"-12\") + parse_int(\"54abc\") + byte_at(line, 0) - len(line);
+}";
+
+const PROCESS: &str = "
+use std.io [console]
+use std.process [arg, arg_count, exit]
+
+pub fn main() i32 {
+    for i in 1..arg_count() {
+        console.out(arg(i));
+    }
+    console.err(\"leaving\");
+    exit(arg_count());
+    return 100;
+}";
+
+#[test]
+fn c_std_math() {
+    let exe = compile(MATH, "math", "c");
+    assert_eq!(run(&exe, &[], "").status.code(), Some(12));
+}
+
+#[test]
+fn c_std_string_and_console_input() {
+    let exe = compile(STRINGS, "strings", "c");
+    // -12 + 54 + 'r' (114) - 17
+    assert_eq!(run(&exe, &[], "rotor is spinning\n").status.code(), Some(139));
+}
+
+#[test]
+fn c_std_process() {
+    let exe = compile(PROCESS, "process", "c");
+    let output = run(&exe, &["one", "two"], "");
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "one\ntwo\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "leaving\n");
+}
+
+#[test]
+fn c_std_byte_index_panics() {
+    let exe = compile("use std.string [byte_at]\npub fn main() i32 {\n    return byte_at(\"abc\", 3);\n}", "byte_range", "c");
+    let output = run(&exe, &[], "");
+
+    assert_eq!(output.status.code(), Some(101));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("byte index out of range"));
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[test]
+fn x86_64_std_library() {
+    let exe = compile(MATH, "math", "x86_64");
+    assert_eq!(run(&exe, &[], "").status.code(), Some(12));
+
+    let exe = compile(STRINGS, "strings", "x86_64");
+    assert_eq!(run(&exe, &[], "rotor is spinning\n").status.code(), Some(139));
+
+    let exe = compile(PROCESS, "process", "x86_64");
+    let output = run(&exe, &["one", "two"], "");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "one\ntwo\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "leaving\n");
+}
+
+#[test]
+fn std_calls_go_through_the_imported_stator() {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::parser::nodes::Stmt;
+
+    let mut program = load_source(
+        std::path::Path::new("main.rt"),
+        "use std.io [console]\npub fn main() {\n    console.out(\"hi\");\n}",
+    )
+    .unwrap();
+    check_program(&mut program).unwrap();
+
+    let names: Vec<&str> = program
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::FnDecl { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, vec!["std.io.console.out", "std.io.console.err", "std.io.console.read_line", "main"]);
+}
+
+#[test]
+fn std_private_items_stay_hidden() {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ErrorKind;
+
+    let errors = load_source(std::path::Path::new("main.rt"), "use std.string [matches_at]\npub fn main() {}")
+        .err()
+        .unwrap();
+    assert_eq!(errors[0].kind(), &ErrorKind::PrivateItem);
+
+    let errors = load_source(std::path::Path::new("main.rt"), "use std.nothing [x]\npub fn main() {}").err().unwrap();
+    assert_eq!(errors[0].kind(), &ErrorKind::UnknownStator);
+
+    // Only public functions can be called through an imported stator
+    let mut program = load_source(std::path::Path::new("main.rt"), "use std.io [console]\npub fn main() {\n    console.write();\n}").unwrap();
+    assert!(check_program(&mut program).is_err());
+}
@@ -0,0 +1,71 @@
+fn check(src: &str) -> Result<rotor::typeck::Checked, Vec<rotor::Error>> {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::typeck::check_program;
+
+    let lexed = lex(src);
+    let mut stream = TokenStream::new(lexed.tokens);
+    let mut program = parse_program(&mut stream).unwrap();
+    check_program(&mut program)
+}
+
+#[test]
+fn check_valid_program() {
+    use rotor::lexer::Symbol;
+    use rotor::typeck::Type;
+
+    let checked = check("const LIMIT = 3;\nfn twice(x: i32) i32 { return x * 2; }\nfn main() i32 { let y = twice(LIMIT); return y; }");
+    let checked = checked.unwrap_or_else(|errors| panic!("{:?}", errors));
+
+    assert_eq!(checked.functions[&Symbol::intern("twice")].params, vec![Type::I32]);
+    assert_eq!(checked.functions[&Symbol::intern("main")].ret, Type::I32);
+    assert_eq!(checked.globals[&Symbol::intern("LIMIT")], Type::I32);
+}
+
+#[test]
+fn check_reports_every_error() {
+    use rotor::ErrorKind;
+
+    let errors = check("fn main() i32 {\n    const x = 1;\n    x = 2;\n    let b: bool = 3;\n    foo();\n}").err().unwrap();
+    let kinds: Vec<&ErrorKind> = errors.iter().map(|error| error.kind()).collect();
+
+    assert_eq!(kinds, vec![
+        &ErrorKind::ConstAssignment,
+        &ErrorKind::TypeMismatch,
+        &ErrorKind::UnknownIdentifier,
+        &ErrorKind::MissingReturn,
+    ]);
+    assert_eq!(errors[1].line(), 4);
+}
+
+#[test]
+fn check_call_arguments() {
+    use rotor::ErrorKind;
+
+    let errors = check("fn f(a: i32, b: bool) {}\nfn main() { f(1); f(true, 1); }").err().unwrap();
+
+    assert_eq!(errors[0].kind(), &ErrorKind::WrongArgumentCount);
+    assert_eq!(errors[1].kind(), &ErrorKind::TypeMismatch);
+    assert_eq!(errors[2].kind(), &ErrorKind::TypeMismatch);
+}
+
+#[test]
+fn lint_unused_variables_and_unreachable_code() {
+    use rotor::lexer::lex;
+    use rotor::parser::{TokenStream, parse_program};
+    use rotor::typeck::lint_program;
+    use rotor::ErrorKind;
+
+    let src = "const UNUSED = 1;\nfn f(x: i32, _y: i32) i32 {\n    let a = 1;\n    let b = 2;\n    b = 3;\n    for i in 0..a {\n        return i;\n        a = 4;\n    }\n    return 0;\n}";
+    let lexed = lex(src);
+    let mut stream = TokenStream::new(lexed.tokens);
+    let program = parse_program(&mut stream).unwrap();
+    let warnings = lint_program(&program);
+
+    let found: Vec<(&ErrorKind, usize)> = warnings.iter().map(|warning| (warning.kind(), warning.line())).collect();
+    assert_eq!(
+        found,
+        vec![(&ErrorKind::UnusedVariable, 2), (&ErrorKind::UnusedVariable, 4), (&ErrorKind::UnreachableCode, 8)]
+    );
+    assert!(warnings[0].message().contains("`x`"));
+}
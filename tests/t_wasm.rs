@@ -0,0 +1,281 @@
+// Decodes modules produced by the wasm backend to check that the binary
+// is well formed and matches the module it was written from.
+
+fn compile_wasm(src: &str) -> rotor::codegen::WasmModule {
+    use rotor::loader::load_source;
+    use rotor::typeck::check_program;
+    use rotor::ir::{lower_program, verify};
+    use rotor::codegen::emit_wasm;
+
+    let mut program = load_source(std::path::Path::new("main.rt"), src).unwrap();
+    let checked = check_program(&mut program).unwrap();
+    let module = lower_program(&program, &checked);
+    verify(&module).unwrap();
+    emit_wasm(&module).unwrap()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> u8 {
+        let byte = self.bytes[self.pos];
+        self.pos += 1;
+        byte
+    }
+
+    fn u32(&mut self) -> u32 {
+        let (mut result, mut shift) = (0u32, 0);
+        loop {
+            let byte = self.byte();
+            result |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return result;
+            }
+            shift += 7;
+        }
+    }
+
+    fn i32(&mut self) -> i32 {
+        let (mut result, mut shift) = (0i64, 0);
+        loop {
+            let byte = self.byte();
+            result |= ((byte & 0x7F) as i64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return result as i32;
+            }
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let len = self.u32() as usize;
+        let name = String::from_utf8(self.bytes[self.pos..self.pos + len].to_vec()).unwrap();
+        self.pos += len;
+        name
+    }
+
+    fn done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+// (opcode, immediate)
+type DecodedInstr = (u8, Option<i32>);
+
+#[derive(Default, Debug)]
+struct Decoded {
+    types: Vec<(u32, u32)>,
+    imports: Vec<(String, String, u32)>,
+    functions: Vec<u32>,
+    exports: Vec<(String, u8, u32)>,
+    globals: Vec<(bool, i32)>,
+    bodies: Vec<(u32, Vec<DecodedInstr>)>,
+    data: Vec<u8>,
+}
+
+fn decode(bytes: &[u8]) -> Decoded {
+    assert_eq!(&bytes[..8], b"\0asm\x01\0\0\0");
+    let mut reader = Reader { bytes: &bytes[8..], pos: 0 };
+    let mut decoded = Decoded::default();
+    let mut last_id = 0;
+
+    while !reader.done() {
+        let id = reader.byte();
+        assert!(id > last_id, "sections must be in order");
+        last_id = id;
+        let size = reader.u32() as usize;
+        let mut section = Reader { bytes: &reader.bytes[reader.pos..reader.pos + size], pos: 0 };
+        reader.pos += size;
+
+        let count = section.u32();
+        for _ in 0..count {
+            match id {
+                1 => {
+                    assert_eq!(section.byte(), 0x60);
+                    let params = section.u32();
+                    (0..params).for_each(|_| assert_eq!(section.byte(), 0x7F));
+                    let results = section.u32();
+                    (0..results).for_each(|_| assert_eq!(section.byte(), 0x7F));
+                    decoded.types.push((params, results));
+                }
+                2 => {
+                    let (module, name) = (section.name(), section.name());
+                    assert_eq!(section.byte(), 0x00);
+                    decoded.imports.push((module, name, section.u32()));
+                }
+                3 => decoded.functions.push(section.u32()),
+                5 => {
+                    assert_eq!(section.byte(), 0x00);
+                    assert!(section.u32() >= 1);
+                }
+                6 => {
+                    assert_eq!(section.byte(), 0x7F);
+                    let mutable = section.byte() == 1;
+                    assert_eq!(section.byte(), 0x41);
+                    let init = section.i32();
+                    assert_eq!(section.byte(), 0x0B);
+                    decoded.globals.push((mutable, init));
+                }
+                7 => {
+                    let name = section.name();
+                    let kind = section.byte();
+                    decoded.exports.push((name, kind, section.u32()));
+                }
+                10 => {
+                    let size = section.u32() as usize;
+                    let mut body = Reader { bytes: &section.bytes[section.pos..section.pos + size], pos: 0 };
+                    section.pos += size;
+                    let mut locals = 0;
+                    for _ in 0..body.u32() {
+                        locals += body.u32();
+                        assert_eq!(body.byte(), 0x7F);
+                    }
+                    let mut instrs = vec![];
+                    let mut depth = 1;
+                    while depth > 0 {
+                        let opcode = body.byte();
+                        let immediate = match opcode {
+                            0x02..=0x04 => {
+                                depth += 1;
+                                Some(body.byte() as i32)
+                            }
+                            0x0B => {
+                                depth -= 1;
+                                None
+                            }
+                            0x0C | 0x0D | 0x10 | 0x20 | 0x21 | 0x23 | 0x24 => Some(body.u32() as i32),
+                            0x0E => {
+                                // Only the default target is kept
+                                (0..body.u32()).for_each(|_| { body.u32(); });
+                                Some(body.u32() as i32)
+                            }
+                            0x41 => Some(body.i32()),
+                            0x00 | 0x05 | 0x0F | 0x1A | 0x45..=0x4E | 0x6A..=0x6F => None,
+                            other => panic!("unexpected opcode {:#x}", other),
+                        };
+                        instrs.push((opcode, immediate));
+                    }
+                    assert!(body.done(), "code after the final end");
+                    decoded.bodies.push((locals, instrs));
+                }
+                11 => {
+                    assert_eq!(section.u32(), 0);
+                    assert_eq!(section.byte(), 0x41);
+                    section.i32();
+                    assert_eq!(section.byte(), 0x0B);
+                    let len = section.u32() as usize;
+                    decoded.data = section.bytes[section.pos..section.pos + len].to_vec();
+                    section.pos += len;
+                }
+                other => panic!("unexpected section {}", other),
+            }
+        }
+        assert!(section.done(), "section {} has trailing bytes", id);
+    }
+    decoded
+}
+
+#[test]
+fn wasm_round_trip() {
+    let module = compile_wasm("
+use std.io [console]
+let counter = 5;
+fn square(x: i32) i32 { return x * x; }
+pub fn main() i32 {
+    console.out(\"spinning\");
+    for i in 0..3 { counter = counter + square(i); }
+    return counter;
+}");
+    let decoded = decode(&module.to_bytes());
+
+    assert_eq!(decoded.imports, vec![("std.io.console".to_string(), "out".to_string(), 0)]);
+    assert_eq!(decoded.types[0], (1, 0));
+    assert_eq!(decoded.functions.len(), 2);
+    assert_eq!(decoded.bodies.len(), 2);
+    assert_eq!(decoded.globals, vec![(true, 5)]);
+    // Imports take the first function index, so main is the third function
+    assert!(decoded.exports.contains(&("main".to_string(), 0x00, 2)));
+    assert!(decoded.exports.contains(&("memory".to_string(), 0x02, 0)));
+    assert_eq!(&decoded.data[..4], &8u32.to_le_bytes());
+    assert_eq!(&decoded.data[4..], b"spinning");
+
+    for (function, (locals, instrs)) in module.functions.iter().zip(&decoded.bodies) {
+        assert_eq!(function.locals, *locals);
+        assert_eq!(function.body.len(), instrs.len());
+    }
+    // square is one block: local.get 0, local.get 0, i32.mul, local.set 1, local.get 1, return, end
+    let opcodes: Vec<u8> = decoded.bodies[0].1.iter().map(|(op, _)| *op).collect();
+    assert_eq!(opcodes, vec![0x20, 0x20, 0x6C, 0x21, 0x20, 0x0F, 0x0B]);
+    // main has a loop, so it dispatches between its blocks
+    assert!(decoded.bodies[1].1.iter().any(|(op, _)| *op == 0x0E));
+}
+
+#[test]
+fn wasm_text_output() {
+    let module = compile_wasm("fn main() { let big = -100000; if big < 0 && !false { return; } }");
+    let wat = module.to_wat();
+
+    assert!(wat.starts_with(";; Generated by rotor"));
+    assert!(wat.contains("(func $main (;0;) (type 0)"));
+    assert!(wat.contains("i32.const 100000"));
+    assert!(wat.contains("    loop\n"));
+    assert!(wat.contains("br_table 0 1 2 3 4 4"));
+    assert!(wat.contains("(export \"main\" (func 0))"));
+    // Without console calls nothing is imported
+    assert!(!wat.contains("(import"));
+}
+
+#[test]
+fn wasm_non_ascii_strings() {
+    let module = compile_wasm("use std.io [console]\npub fn main() {\n    console.out(\"héllo\");\n}");
+    let decoded = decode(&module.to_bytes());
+
+    assert_eq!(&decoded.data[..4], &6u32.to_le_bytes());
+    assert_eq!(&decoded.data[4..], "héllo".as_bytes());
+}
+
+#[test]
+fn wasm_runs_in_node() {
+    // Only runs when node is installed
+    let node = std::process::Command::new("node").arg("--version").output();
+    if !node.is_ok_and(|output| output.status.success()) {
+        return;
+    }
+
+    let module = compile_wasm("
+use std.io [console]
+fn fib(n: i32) i32 {
+    if n < 2 { return n; }
+    return fib(n - 1) + fib(n - 2);
+}
+pub fn main() i32 {
+    console.out(\"Rotor is spinning!\");
+    let min = -2147483647 - 1;
+    if min / -1 != min { return 1; }
+    return fib(10);
+}");
+    let path = std::env::temp_dir().join(format!("rotor-test-{}.wasm", std::process::id()));
+    std::fs::write(&path, module.to_bytes()).unwrap();
+    let script = "
+const bytes = require('fs').readFileSync(process.argv[1]);
+let memory;
+const text = (ptr) => {
+    const len = new DataView(memory.buffer).getUint32(ptr, true);
+    return Buffer.from(memory.buffer, ptr + 4, len).toString();
+};
+WebAssembly.instantiate(bytes, { 'std.io.console': { out: (ptr) => console.log(text(ptr)) } })
+    .then(({ instance }) => {
+        memory = instance.exports.memory;
+        console.log(instance.exports.main());
+    });";
+    let output = std::process::Command::new("node").arg("-e").arg(script).arg(&path).output().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Rotor is spinning!\n55\n");
+}
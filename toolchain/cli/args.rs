@@ -0,0 +1,207 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Command line parsing. Global options may appear anywhere before a
+// `--`; everything else is matched against the chosen command's option
+// table, so each command documents exactly what it accepts and help is
+// generated from the same table.
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+pub struct Global {
+    pub color: ColorChoice,
+    pub quiet: bool,
+    pub message_format: MessageFormat,
+    pub help: bool,
+    pub version: bool,
+}
+
+/// An option of a command. Options with a `value` take an argument,
+/// written `--name value` or `--name=value` (`-O2` for short ones).
+#[derive(Clone, Copy)]
+pub struct Opt {
+    pub name: &'static str,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub struct Spec {
+    pub name: &'static str,
+    pub summary: &'static str,
+    /// The positional part of the usage line, e.g. `<file>`.
+    pub args: &'static str,
+    pub min_args: usize,
+    pub max_args: usize,
+    pub options: &'static [Opt],
+    /// Whether arguments after `--` are passed on.
+    pub trailing: bool,
+}
+
+#[derive(Default)]
+pub struct Matches {
+    options: Vec<(&'static str, Option<String>)>,
+    pub args: Vec<String>,
+    /// The arguments after `--`.
+    pub trailing: Vec<String>,
+}
+
+impl Matches {
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| *option == name)
+    }
+
+    /// The value of the last occurrence of an option.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| *option == name).and_then(|(_, value)| value.as_deref())
+    }
+}
+
+pub const GLOBAL_OPTIONS: &[Opt] = &[
+    Opt { name: "--color", value: Some("<auto|always|never>"), help: "When to color diagnostics (default: auto)" },
+    Opt { name: "--quiet", value: None, help: "Only print errors (also -q)" },
+    Opt { name: "--message-format", value: Some("<human|json>"), help: "How to print diagnostics (default: human)" },
+    Opt { name: "--help", value: None, help: "Show help, for a command if one is given (also -h)" },
+    Opt { name: "--version", value: None, help: "Show the version (also -V)" },
+];
+
+/// Takes the global options out of `args`, leaving the command and its arguments.
+pub fn parse_global(args: &[String]) -> Result<(Global, Vec<String>), String> {
+    let mut global = Global {
+        color: ColorChoice::Auto,
+        quiet: false,
+        message_format: MessageFormat::Human,
+        help: false,
+        version: false,
+    };
+    let mut rest = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            rest.push(arg.clone());
+            rest.extend(iter.by_ref().cloned());
+            break;
+        }
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |option: &str| match inline.clone().or_else(|| iter.next().cloned()) {
+            Some(value) => Ok(value),
+            None => Err(format!("`{}` needs a value", option)),
+        };
+        match name {
+            "--color" => {
+                global.color = match value(name)?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => return Err(format!("`--color` must be auto, always or never, not `{}`", other)),
+                }
+            }
+            "--message-format" => {
+                global.message_format = match value(name)?.as_str() {
+                    "human" => MessageFormat::Human,
+                    "json" => MessageFormat::Json,
+                    other => return Err(format!("`--message-format` must be human or json, not `{}`", other)),
+                }
+            }
+            "--quiet" | "-q" => global.quiet = true,
+            "--help" | "-h" => global.help = true,
+            "--version" | "-V" => global.version = true,
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok((global, rest))
+}
+
+/// Matches a command's arguments against its spec.
+pub fn parse_command(spec: &Spec, args: &[String]) -> Result<Matches, String> {
+    let mut matches = Matches::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            if !spec.trailing {
+                return Err(format!("`rotor {}` takes no arguments after `--`", spec.name));
+            }
+            matches.trailing.extend(iter.by_ref().cloned());
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            matches.args.push(arg.clone());
+            continue;
+        }
+        // `--name=value`, or a short option with its value attached (`-O2`)
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ if !arg.starts_with("--") && arg.len() > 2 => (&arg[..2], Some(arg[2..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let Some(option) = spec.options.iter().find(|option| option.name == name) else {
+            return Err(format!("Unknown option `{}` for `rotor {}`", arg, spec.name));
+        };
+        let value = match (option.value, inline) {
+            (Some(_), Some(value)) => Some(value),
+            (Some(_), None) => match iter.next() {
+                Some(value) => Some(value.clone()),
+                None => return Err(format!("`{}` needs a value", option.name)),
+            },
+            (None, Some(_)) => return Err(format!("`{}` does not take a value", option.name)),
+            (None, None) => None,
+        };
+        matches.options.push((option.name, value));
+    }
+    if matches.args.len() < spec.min_args || matches.args.len() > spec.max_args {
+        return Err(format!("Wrong number of arguments for `rotor {}`", spec.name));
+    }
+    Ok(matches)
+}
+
+fn option_lines(out: &mut String, options: &[Opt]) {
+    for option in options {
+        let name = match option.value {
+            Some(value) => format!("{} {}", option.name, value),
+            None => option.name.to_string(),
+        };
+        let _ = writeln!(out, "  {:<32} {}", name, option.help);
+    }
+}
+
+pub fn usage(spec: &Spec) -> String {
+    let options = if spec.options.is_empty() { "" } else { " [options]" };
+    let args = if spec.args.is_empty() { String::new() } else { format!(" {}", spec.args) };
+    format!("Usage: rotor {}{}{}", spec.name, options, args)
+}
+
+/// The help text of one command.
+pub fn command_help(spec: &Spec) -> String {
+    let mut out = format!("{}\n\n{}\n", spec.summary, usage(spec));
+    if !spec.options.is_empty() {
+        out.push_str("\nOptions:\n");
+        option_lines(&mut out, spec.options);
+    }
+    out
+}
+
+/// The help text listing every command.
+pub fn general_help(specs: &[Spec]) -> String {
+    let mut out = String::from("The Rotor language toolchain\n\nUsage: rotor [global options] <command> [options]\n\nCommands:\n");
+    for spec in specs {
+        let _ = writeln!(out, "  {:<8} {}", spec.name, spec.summary);
+    }
+    out.push_str("\nGlobal options:\n");
+    option_lines(&mut out, GLOBAL_OPTIONS);
+    out.push_str("\nRun `rotor help <command>` for the options of a command.\n");
+    out
+}
@@ -0,0 +1,398 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// `rotor build`, `rotor run` and `rotor test`. Each works on a single
+// file when one is given, and otherwise on the project around the
+// current directory. Project builds land in `target/<profile>/`; a
+// profile picks the backend and optimization level, and `--backend` or
+// `-O` override it for one build.
+use std::path::{Path, PathBuf};
+use rotor::codegen::{emit_c, build_c, emit_asm, build_asm, emit_wasm};
+use rotor::ir::{lower_program, verify};
+use rotor::lexer::lex;
+use rotor::loader::Loader;
+use rotor::opt::{OptLevel, PassManager};
+use rotor::parser::nodes::{Program, Stmt};
+use rotor::parser::{parse_program, TokenStream};
+use rotor::project::{builtin_profiles, Kind, Project};
+use rotor::typeck::check_program;
+use rotor::lexer::TokenKind;
+use rotor::{Error, SourceMap};
+use crate::args::{Matches, Opt};
+use crate::report::Reporter;
+
+/// A failure that has already been reported to the user.
+pub struct Failed;
+
+/// What a command works on.
+pub enum Target {
+    File(PathBuf),
+    Project(Project),
+}
+
+impl Target {
+    /// The file given on the command line, or else the project around
+    /// the current directory.
+    pub fn find(file: Option<&str>, reporter: &Reporter) -> Result<Target, Failed> {
+        if let Some(file) = file {
+            return Ok(Target::File(PathBuf::from(file)));
+        }
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Project::discover(&cwd).map(Target::Project).map_err(|errors| {
+            reporter.errors(&errors);
+            Failed
+        })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Target::File(file) => file.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            Target::Project(project) => project.manifest.name.clone(),
+        }
+    }
+
+    /// The file the target starts from.
+    pub fn entry(&self) -> PathBuf {
+        match self {
+            Target::File(file) => file.clone(),
+            Target::Project(project) => project.entry(),
+        }
+    }
+
+    /// A loader for the target, with nothing loaded yet.
+    pub fn new_loader(&self) -> Result<Loader, Vec<Error>> {
+        match self {
+            Target::File(file) => Ok(Loader::new(file.parent().unwrap_or(Path::new(".")))),
+            Target::Project(project) => project.loader(),
+        }
+    }
+
+    /// A loader that has loaded every stator of the target.
+    pub fn loader(&self, reporter: &Reporter) -> Result<Loader, Failed> {
+        let mut loader = self.new_loader().map_err(|errors| {
+            reporter.errors(&errors);
+            Failed
+        })?;
+        if let Err(errors) = loader.load_entry(&self.entry()) {
+            reporter.errors_in(loader.sources(), &errors);
+            return Err(Failed);
+        }
+        Ok(loader)
+    }
+
+    /// The target's linked program, with the files it was loaded from.
+    pub fn load(&self, reporter: &Reporter) -> Result<(Program, SourceMap), Failed> {
+        let mut loader = self.loader(reporter)?;
+        match loader.link() {
+            Ok(program) => Ok((program, loader.into_sources())),
+            Err(errors) => {
+                reporter.errors_in(loader.sources(), &errors);
+                Err(Failed)
+            }
+        }
+    }
+}
+
+/// Where and how to build a program.
+pub struct Build {
+    pub output: PathBuf,
+    pub backend: String,
+    pub emit: Option<String>,
+    pub level: OptLevel,
+    pub dump_passes: bool,
+}
+
+pub const PROFILE_OPTIONS: [Opt; 4] = [
+    Opt { name: "--release", value: None, help: "Use the release profile" },
+    Opt { name: "--profile", value: Some("<name>"), help: "Use a profile from rotor.conf (default: dev)" },
+    Opt { name: "--backend", value: Some("<c|x86_64|wasm>"), help: "Override the profile's backend" },
+    Opt { name: "-O", value: Some("<0|1|2>"), help: "Override the profile's optimization level" },
+];
+
+/// Works out a build of `target` from its profile and the options given.
+pub fn configure(target: &Target, matches: &Matches, reporter: &Reporter) -> Result<Build, Failed> {
+    let name = match matches.value("--profile") {
+        Some(name) => name,
+        None if matches.flag("--release") => "release",
+        None => "dev",
+    };
+    let profile = match target {
+        Target::Project(project) => project.manifest.profile(name).cloned(),
+        Target::File(_) => builtin_profiles().into_iter().find(|profile| profile.name == name),
+    };
+    let Some(mut profile) = profile else {
+        reporter.error(format!("`{}` has no profile `{}`", target.name(), name));
+        return Err(Failed);
+    };
+    if let Some(backend) = matches.value("--backend") {
+        if !matches!(backend, "c" | "x86_64" | "wasm") {
+            reporter.error(format!("Unknown backend `{}` (expected c, x86_64 or wasm)", backend));
+            return Err(Failed);
+        }
+        profile.backend = backend.to_string();
+    }
+    if let Some(level) = matches.value("-O") {
+        let Some(level) = OptLevel::from_flag(level) else {
+            reporter.error(format!("Unknown optimization level `{}` (expected 0, 1 or 2)", level));
+            return Err(Failed);
+        };
+        profile.opt_level = level;
+    }
+
+    let ext = if profile.backend == "wasm" { "wasm" } else { std::env::consts::EXE_EXTENSION };
+    let output = match (matches.value("-o"), target) {
+        (Some(output), _) => PathBuf::from(output),
+        (None, Target::File(_)) => PathBuf::from(target.name()).with_extension(ext),
+        (None, Target::Project(project)) => {
+            let dir = project.root.join("target").join(&profile.name);
+            if let Err(error) = std::fs::create_dir_all(&dir) {
+                reporter.error(format!("Unable to create {}: {}", dir.display(), error));
+                return Err(Failed);
+            }
+            dir.join(&project.manifest.name).with_extension(ext)
+        }
+    };
+    Ok(Build {
+        output,
+        backend: profile.backend,
+        emit: matches.value("--emit").map(str::to_string),
+        level: profile.opt_level,
+        dump_passes: matches.flag("--dump-passes"),
+    })
+}
+
+fn write_kept(path: PathBuf, contents: impl AsRef<[u8]>, reporter: &Reporter) {
+    if let Err(error) = std::fs::write(&path, contents) {
+        reporter.warning(format!("Unable to write {}: {}", path.display(), error));
+    }
+}
+
+/// Type checks `program`, loaded from `sources`, and builds it with the
+/// chosen backend.
+pub fn build_program(mut program: Program, sources: &SourceMap, build: &Build, reporter: &Reporter) -> Result<(), Failed> {
+    let Build { output, level, dump_passes, .. } = build;
+    let (backend, emit) = (build.backend.as_str(), build.emit.as_deref());
+    let emit_ext = match (backend, emit) {
+        (_, None) => None,
+        // The IR does not depend on the backend
+        (_, Some("ir")) => Some("ir"),
+        ("c", Some("c")) => Some("c"),
+        ("x86_64", Some("asm")) => Some("s"),
+        ("wasm", Some("wat")) => Some("wat"),
+        (_, Some(emit)) => {
+            reporter.error(format!("--emit={} is not available with the {} backend", emit, backend));
+            return Err(Failed);
+        }
+    };
+    let fail = |error: Error| {
+        reporter.errors_in(sources, &[error]);
+        Failed
+    };
+
+    let checked = check_program(&mut program).map_err(|errors| {
+        reporter.errors_in(sources, &errors);
+        Failed
+    })?;
+
+    let mut module = lower_program(&program, &checked);
+    let mut dump = |pass: &str, module: &rotor::ir::Module| {
+        if *dump_passes {
+            eprintln!("; IR after {}\n{}", pass, module);
+        }
+    };
+    dump("lowering", &module);
+    let optimized = verify(&module).and_then(|_| PassManager::for_level(*level).run_with_dump(&mut module, &mut dump));
+    if let Err(errors) = optimized {
+        for error in &errors {
+            reporter.error(format!("internal compiler error: {}", error));
+        }
+        return Err(Failed);
+    }
+    if emit_ext == Some("ir") {
+        write_kept(output.with_extension("ir"), module.to_string(), reporter);
+    }
+
+    match backend {
+        "x86_64" => {
+            let asm = emit_asm(&module).map_err(fail)?;
+            if emit_ext == Some("s") {
+                write_kept(output.with_extension("s"), &asm, reporter);
+            }
+            build_asm(&asm, output).map_err(fail)
+        }
+        "wasm" => {
+            let wasm = emit_wasm(&module).map_err(fail)?;
+            if emit_ext == Some("wat") {
+                write_kept(output.with_extension("wat"), wasm.to_wat(), reporter);
+            }
+            std::fs::write(output, wasm.to_bytes()).map_err(|error| {
+                reporter.error(format!("Unable to write {}: {}", output.display(), error));
+                Failed
+            })
+        }
+        _ => {
+            let generated = emit_c(&module).map_err(fail)?;
+            if emit_ext == Some("c") {
+                write_kept(output.with_extension("c"), &generated, reporter);
+            }
+            build_c(&generated, output).map_err(fail)
+        }
+    }
+}
+
+fn only_programs(target: &Target, reporter: &Reporter) -> Result<(), Failed> {
+    match target {
+        Target::Project(project) if project.manifest.kind == Kind::Lib => {
+            reporter.error(format!("`{}` is a library; only programs can be built and run", project.manifest.name));
+            Err(Failed)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn build(matches: &Matches, reporter: &Reporter) -> Result<(), Failed> {
+    let target = Target::find(matches.args.first().map(String::as_str), reporter)?;
+    only_programs(&target, reporter)?;
+    let build = configure(&target, matches, reporter)?;
+    let (program, sources) = target.load(reporter)?;
+    build_program(program, &sources, &build, reporter)?;
+    reporter.status(format!("Built {}", build.output.display()));
+    Ok(())
+}
+
+pub fn cmd_build(matches: &Matches, reporter: &Reporter) -> i32 {
+    match build(matches, reporter) {
+        Ok(()) => 0,
+        Err(Failed) => 1,
+    }
+}
+
+/// A path in the temporary directory for a throwaway executable.
+fn scratch_exe(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rotor-{}-{}", name, std::process::id()))
+}
+
+fn runnable(build: &Build, reporter: &Reporter) -> Result<(), Failed> {
+    if build.backend == "wasm" {
+        reporter.error("WebAssembly modules need a WebAssembly host to run; use `rotor build` instead");
+        return Err(Failed);
+    }
+    Ok(())
+}
+
+pub fn cmd_run(matches: &Matches, reporter: &Reporter) -> i32 {
+    let built = Target::find(matches.args.first().map(String::as_str), reporter).and_then(|target| {
+        only_programs(&target, reporter)?;
+        let mut build = configure(&target, matches, reporter)?;
+        runnable(&build, reporter)?;
+        // A single file is built out of the way, since nothing would clean it up
+        let temporary = matches!(target, Target::File(_));
+        if temporary {
+            build.output = scratch_exe(&target.name());
+        }
+        let (program, sources) = target.load(reporter)?;
+        build_program(program, &sources, &build, reporter)?;
+        Ok((build.output, temporary))
+    });
+    let Ok((exe, temporary)) = built else { return 1 };
+
+    let status = std::process::Command::new(&exe).args(&matches.trailing).status();
+    if temporary {
+        let _ = std::fs::remove_file(&exe);
+    }
+    match status {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(error) => {
+            reporter.error(format!("Unable to run {}: {}", exe.display(), error));
+            1
+        }
+    }
+}
+
+/// The tests in `program`: functions named `test_*` that take nothing
+/// and return nothing or a `bool`. Other `test_*` functions are warned
+/// about, since they look like tests but can't be run as one.
+fn find_tests(program: &Program, reporter: &Reporter) -> Vec<(String, bool)> {
+    let mut tests = vec![];
+    for stmt in program {
+        let Stmt::FnDecl { name, params, return_ty, body: Some(_), .. } = stmt else { continue };
+        if !name.rsplit('.').next().unwrap_or_default().starts_with("test_") {
+            continue;
+        }
+        match return_ty {
+            None if params.is_empty() => tests.push((name.to_string(), false)),
+            Some(TokenKind::BOOL) if params.is_empty() => tests.push((name.to_string(), true)),
+            _ => reporter.warning(format!("`{}` is not run: a test takes no parameters and returns nothing or a bool", name)),
+        }
+    }
+    tests
+}
+
+/// Replaces the program's `main` with one that runs a single test and
+/// exits with 0 if it passed.
+fn harness(program: &mut Program, test: &str, returns_bool: bool) {
+    program.retain(|stmt| !matches!(stmt, Stmt::FnDecl { name, .. } if name == "main"));
+    let source = if returns_bool {
+        format!("pub fn main() i32 {{\n    if {}() {{\n        return 0;\n    }}\n    return 1;\n}}", test)
+    } else {
+        format!("pub fn main() i32 {{\n    {}();\n    return 0;\n}}", test)
+    };
+    let mut stream = TokenStream::new(lex(&source).tokens);
+    let main = parse_program(&mut stream).expect("the test harness is valid Rotor");
+    program.extend(main);
+}
+
+fn test(matches: &Matches, reporter: &Reporter) -> Result<(), Failed> {
+    let target = Target::find(matches.args.first().map(String::as_str), reporter)?;
+    let mut build = configure(&target, matches, reporter)?;
+    runnable(&build, reporter)?;
+    build.output = scratch_exe(&format!("test-{}", target.name()));
+
+    let tests = find_tests(&target.load(reporter)?.0, reporter);
+    let filter = matches.value("--filter").unwrap_or_default();
+    let tests: Vec<_> = tests.into_iter().filter(|(name, _)| name.contains(filter)).collect();
+    reporter.status(format!("running {} test{}", tests.len(), if tests.len() == 1 { "" } else { "s" }));
+
+    let mut failures = vec![];
+    for (name, returns_bool) in &tests {
+        // Every test gets its own `main`, and the program is loaded afresh for each
+        let (mut program, sources) = target.load(reporter)?;
+        harness(&mut program, name, *returns_bool);
+        build_program(program, &sources, &build, reporter)?;
+        let output = std::process::Command::new(&build.output).output();
+        let _ = std::fs::remove_file(&build.output);
+        let passed = match &output {
+            Ok(output) => output.status.success(),
+            Err(_) => false,
+        };
+        reporter.status(format!("test {} ... {}", name, if passed { "ok" } else { "FAILED" }));
+        if !passed {
+            failures.push((name, output));
+        }
+    }
+
+    for (name, output) in &failures {
+        match output {
+            Ok(output) => {
+                let code = output.status.code().map_or("a signal".to_string(), |code| format!("code {}", code));
+                let printed = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+                reporter.failure(&format!("{} exited with {}", name, code), &printed);
+            }
+            Err(error) => reporter.failure(&format!("{} could not be run: {}", name, error), ""),
+        }
+    }
+    let result = if failures.is_empty() { "ok" } else { "FAILED" };
+    reporter.status(format!("\ntest result: {}. {} passed; {} failed", result, tests.len() - failures.len(), failures.len()));
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Failed)
+    }
+}
+
+pub fn cmd_test(matches: &Matches, reporter: &Reporter) -> i32 {
+    match test(matches, reporter) {
+        Ok(()) => 0,
+        Err(Failed) => 1,
+    }
+}
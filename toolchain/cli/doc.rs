@@ -0,0 +1,76 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// `rotor doc` writes a Markdown page listing the public functions of
+// every stator in a project, each with its signature and the `//`
+// comment lines written directly above it. The standard library is left
+// out.
+use std::fmt::Write;
+use std::path::PathBuf;
+use rotor::parser::nodes::Stmt;
+use rotor::typeck::Type;
+use crate::args::Matches;
+use crate::build::{Failed, Target};
+use crate::report::Reporter;
+
+/// The comment lines directly above line `line` (1-based) of `source`.
+fn doc_comment(source: &str, line: usize) -> Vec<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut comment = vec![];
+    for text in lines[..line.saturating_sub(1).min(lines.len())].iter().rev() {
+        let Some(text) = text.trim().strip_prefix("//") else { break };
+        comment.push(text.strip_prefix(' ').unwrap_or(text).to_string());
+    }
+    comment.reverse();
+    comment
+}
+
+fn type_name(kind: Option<rotor::TokenKind>) -> String {
+    kind.and_then(Type::from_kind).map_or(String::new(), |ty| format!(" {}", ty))
+}
+
+fn doc(matches: &Matches, reporter: &Reporter) -> Result<PathBuf, Failed> {
+    let target = Target::find(matches.args.first().map(String::as_str), reporter)?;
+    let loader = target.loader(reporter)?;
+
+    let mut page = format!("# {}\n", target.name());
+    // The entry stator first, then its dependencies
+    for stator in loader.stators().iter().rev() {
+        if stator.name == "std" || stator.name.starts_with("std.") {
+            continue;
+        }
+        let source = loader.sources().text(stator.id);
+        let _ = write!(page, "\n## `{}`\n", stator.name);
+        for stmt in &stator.program {
+            let Stmt::FnDecl { name, params, return_ty, public: true, span, .. } = stmt else { continue };
+            let params: Vec<String> = params.iter().map(|param| format!("{}:{}", param.name, type_name(Some(param.ty)))).collect();
+            let _ = write!(page, "\n### `{}`\n\n```rotor\npub fn {}({}){}\n```\n", name, name, params.join(", "), type_name(*return_ty));
+            let comment = doc_comment(source, span.line);
+            if !comment.is_empty() {
+                let _ = write!(page, "\n{}\n", comment.join("\n"));
+            }
+        }
+    }
+
+    let dir = match (matches.value("-o"), &target) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Target::Project(project)) => project.root.join("target").join("doc"),
+        (None, Target::File(_)) => PathBuf::from("doc"),
+    };
+    let file = dir.join(format!("{}.md", target.name()));
+    if let Err(error) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&file, page)) {
+        reporter.error(format!("Unable to write {}: {}", file.display(), error));
+        return Err(Failed);
+    }
+    Ok(file)
+}
+
+pub fn cmd_doc(matches: &Matches, reporter: &Reporter) -> i32 {
+    match doc(matches, reporter) {
+        Ok(file) => {
+            reporter.status(format!("Documented {}", file.display()));
+            0
+        }
+        Err(Failed) => 1,
+    }
+}
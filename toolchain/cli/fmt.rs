@@ -0,0 +1,95 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// `rotor fmt` rewrites files in the canonical style. Without arguments
+// it formats every `.rt` file under the project's `src/`; directories
+// given on the command line are searched the same way. With `--check`
+// nothing is written, and the command fails if any file would change,
+// which is what CI wants.
+use std::path::{Path, PathBuf};
+use rotor::format::format_source;
+use rotor::project::Project;
+use crate::args::Matches;
+use crate::build::Failed;
+use crate::report::Reporter;
+
+fn rotor_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            rotor_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rt") {
+            files.push(path);
+        }
+    }
+}
+
+fn files(matches: &Matches, reporter: &Reporter) -> Result<Vec<PathBuf>, Failed> {
+    let mut files = vec![];
+    if matches.args.is_empty() {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let project = Project::discover(&cwd).map_err(|errors| {
+            reporter.errors(&errors);
+            Failed
+        })?;
+        rotor_files(&project.root.join("src"), &mut files);
+    }
+    for arg in &matches.args {
+        let path = PathBuf::from(arg);
+        if path.is_dir() { rotor_files(&path, &mut files) } else { files.push(path) }
+    }
+    Ok(files)
+}
+
+/// The first line (1-based) where `a` and `b` differ.
+fn first_difference(a: &str, b: &str) -> usize {
+    let mut lines = a.lines().zip(b.lines());
+    lines.position(|(a, b)| a != b).unwrap_or_else(|| a.lines().count().min(b.lines().count())) + 1
+}
+
+pub fn cmd_fmt(matches: &Matches, reporter: &Reporter) -> i32 {
+    let Ok(files) = files(matches, reporter) else { return 1 };
+    let check = matches.flag("--check");
+    let mut failed = false;
+    let mut changed = 0;
+    for file in &files {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(error) => {
+                reporter.error(format!("Unable to read {}: {}", file.display(), error));
+                failed = true;
+                continue;
+            }
+        };
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                reporter.error(format!("Unable to format {}, it has errors:", file.display()));
+                reporter.errors(&errors);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+        if check {
+            reporter.error(format!("{} is not formatted (from line {})", file.display(), first_difference(&source, &formatted)));
+        } else if let Err(error) = std::fs::write(file, formatted) {
+            reporter.error(format!("Unable to write {}: {}", file.display(), error));
+            failed = true;
+        } else {
+            reporter.status(format!("Formatted {}", file.display()));
+        }
+    }
+    if failed || (check && changed > 0) {
+        return 1;
+    }
+    if check {
+        reporter.status(format!("{} file(s) already formatted", files.len()));
+    }
+    0
+}
@@ -1,60 +1,403 @@
-use rotor::lexer::{lex};
-// use rotor::handle_error::handle_error::{ErrorKind, Error};
-// use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue};
-// use tokio;
-// use dotenv::dotenv;
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The `rotor` command. Every subcommand is described by a `Spec` in
+// `COMMANDS`, which drives both argument parsing and help; `run_command`
+// maps the chosen one to its implementation. Exit codes: 0 on success,
+// 1 when the command failed (errors in the program, a failed build or
+// test) and 2 when the command line itself was wrong. `rotor run`
+// exits with the program's own exit code.
+mod args;
+mod build;
+mod doc;
+mod fmt;
+mod report;
 
-// const CURRENT_VERSION: &str = "v0.1.0-unrelease1";
+use std::path::Path;
+use rotor::lexer::lex;
+use rotor::grammar::textmate_grammar;
+use rotor::lsp::serve_stdio;
+use rotor::parser::{dump_program, parse_program_recovering, TokenStream};
+use rotor::repl;
+use rotor::project::{Template, new_project, init_project};
+use rotor::typeck::{check_program, lint_program};
+use rotor::SourceMap;
+use args::{Matches, Opt, Spec, command_help, general_help, parse_command, parse_global, usage};
+use build::{PROFILE_OPTIONS, Target};
+use report::{Level, Reporter};
+
+const VERSION: &str = "v0.1.0-unrelease1.1";
+
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const LIB_OPTION: Opt = Opt { name: "--lib", value: None, help: "Create a library instead of a program" };
+
+const JSON_OPTION: Opt = Opt { name: "--json", value: None, help: "Print JSON instead (needs the `serde` feature)" };
+
+const BUILD_OPTIONS: &[Opt] = &[
+    PROFILE_OPTIONS[0],
+    PROFILE_OPTIONS[1],
+    PROFILE_OPTIONS[2],
+    PROFILE_OPTIONS[3],
+    Opt { name: "-o", value: Some("<path>"), help: "Where to write the output" },
+    Opt { name: "--emit", value: Some("<c|asm|wat|ir>"), help: "Keep the generated source next to the output" },
+    Opt { name: "--dump-passes", value: None, help: "Print the IR after every pass that changes it" },
+];
+
+const TEST_OPTIONS: &[Opt] = &[
+    PROFILE_OPTIONS[0],
+    PROFILE_OPTIONS[1],
+    PROFILE_OPTIONS[2],
+    PROFILE_OPTIONS[3],
+    Opt { name: "--filter", value: Some("<text>"), help: "Only run tests whose name contains <text>" },
+];
+
+const COMMANDS: &[Spec] = &[
+    Spec {
+        name: "new",
+        summary: "Create a project in a new directory",
+        args: "<path>",
+        min_args: 1,
+        max_args: 1,
+        options: &[LIB_OPTION],
+        trailing: false,
+    },
+    Spec {
+        name: "init",
+        summary: "Make the current directory a project",
+        args: "",
+        min_args: 0,
+        max_args: 0,
+        options: &[LIB_OPTION],
+        trailing: false,
+    },
+    Spec {
+        name: "build",
+        summary: "Build the current project, or a single file",
+        args: "[file]",
+        min_args: 0,
+        max_args: 1,
+        options: BUILD_OPTIONS,
+        trailing: false,
+    },
+    Spec {
+        name: "run",
+        summary: "Build and run the current project, or a single file",
+        args: "[file] [-- <program arguments>]",
+        min_args: 0,
+        max_args: 1,
+        options: &PROFILE_OPTIONS,
+        trailing: true,
+    },
+    Spec {
+        name: "check",
+        summary: "Check the current project, or a single file, for errors without building it",
+        args: "[file]",
+        min_args: 0,
+        max_args: 1,
+        options: &[],
+        trailing: false,
+    },
+    Spec {
+        name: "test",
+        summary: "Run the `test_*` functions of the current project, or a single file",
+        args: "[file]",
+        min_args: 0,
+        max_args: 1,
+        options: TEST_OPTIONS,
+        trailing: false,
+    },
+    Spec {
+        name: "fmt",
+        summary: "Format the current project's sources, or the given files and directories",
+        args: "[paths...]",
+        min_args: 0,
+        max_args: usize::MAX,
+        options: &[Opt { name: "--check", value: None, help: "Don't write anything; fail if a file isn't formatted" }],
+        trailing: false,
+    },
+    Spec {
+        name: "lex",
+        summary: "Print the tokens of a file",
+        args: "<file>",
+        min_args: 1,
+        max_args: 1,
+        options: &[JSON_OPTION],
+        trailing: false,
+    },
+    Spec {
+        name: "parse",
+        summary: "Print the syntax tree of a file",
+        args: "<file>",
+        min_args: 1,
+        max_args: 1,
+        options: &[JSON_OPTION],
+        trailing: false,
+    },
+    Spec {
+        name: "doc",
+        summary: "Document the public functions of the current project, or a single file",
+        args: "[file]",
+        min_args: 0,
+        max_args: 1,
+        options: &[Opt { name: "-o", value: Some("<dir>"), help: "The directory to write to (default: target/doc)" }],
+        trailing: false,
+    },
+    Spec {
+        name: "grammar",
+        summary: "Print an editor grammar for Rotor",
+        args: "",
+        min_args: 0,
+        max_args: 0,
+        options: &[Opt { name: "--textmate", value: None, help: "A TextMate grammar, as VS Code uses" }],
+        trailing: false,
+    },
+    Spec {
+        name: "lsp",
+        summary: "Run the language server on stdin and stdout",
+        args: "",
+        min_args: 0,
+        max_args: 0,
+        options: &[],
+        trailing: false,
+    },
+    Spec {
+        name: "repl",
+        summary: "Evaluate expressions, statements and items interactively",
+        args: "",
+        min_args: 0,
+        max_args: 0,
+        options: &[],
+        trailing: false,
+    },
+    Spec {
+        name: "help",
+        summary: "Show help for a command",
+        args: "[command]",
+        min_args: 0,
+        max_args: 1,
+        options: &[],
+        trailing: false,
+    },
+];
 
-//#[tokio::main]
 fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
-    
-    if args.len() < 2  {
-        println!("Use --help for information on commands.");
-        std::process::exit(1);
-    }
-
-    if args[1] == "--help" {
-        println!("Usage: rotor [OPTIONS] [FILE]");
-        println!("------------------------------------------");
-        println!("WARNING: This is an early version of the program, and it is not yet fully functional.");
-        println!("Only the --lex option has been implemented so far.");
-        println!("------------------------------------------");
-        println!("Options:");
-        println!("  --help       Show this help message");
-        println!("  --version    Show the version of the program");
-        println!("  --run        Run the specified file");
-        println!("  --compile    Compile the specified file");
-        println!("  --debug      Debug the specified file");
-        println!("  --lex        Lex the specified file");
-        println!("---------------------------------------");
-    } else if args[1] == "--version" {
-        println!("Version: {}", "v0.1.0-unrelease1.1");
-    } else if args[1] == "--run" {
-        if args.len() < 3 {
-            println!("Running is not supported yet.");
-            return;
-        }
-        let source = std::fs::read_to_string(&args[2]).expect("Unable to read file");
-        let lexed = lex(&source);
-        lexed.get_debug_info();
-    } else if args[1] == "--compile" {
-        println!("Compilation is not yet implemented.")
-    } else if args[1] == "--debug" {
-        println!("Debugging is not yet implemented.")
-    } else if args[1] == "--lex" {
-        let source = std::fs::read_to_string(&args[2]).expect("Unable to read file");
-        let lexed = lex(&source);
-        lexed.get_debug_info();
-    } else if args[1] == "--test" {
-        println!("Testing is not yet implemented.")
-    } else if args[1] == "--build" {
-        println!("Building is not yet implemented.")
-    } else if args[1] == "--install" {
-        println!("Installing is not yet implemented.")
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    std::process::exit(run(&args));
+}
+
+fn run(args: &[String]) -> i32 {
+    let (global, rest) = match parse_global(args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let reporter = Reporter::new(&global);
+    if global.version {
+        println!("Version: {}", VERSION);
+        return 0;
+    }
+    let Some(name) = rest.first() else {
+        if global.help {
+            print!("{}", general_help(COMMANDS));
+            return 0;
+        }
+        eprint!("{}", general_help(COMMANDS));
+        return EXIT_USAGE;
+    };
+    let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) else {
+        reporter.error(format!("Unknown command `{}`", name));
+        eprintln!("Run `rotor --help` for the list of commands.");
+        return EXIT_USAGE;
+    };
+    if global.help {
+        print!("{}", command_help(spec));
+        return 0;
+    }
+    match parse_command(spec, &rest[1..]) {
+        Ok(matches) => run_command(spec, &matches, &reporter),
+        Err(message) => {
+            reporter.error(message);
+            eprintln!("\n{}", usage(spec));
+            EXIT_USAGE
+        }
+    }
+}
+
+fn run_command(spec: &Spec, matches: &Matches, reporter: &Reporter) -> i32 {
+    match spec.name {
+        "new" | "init" => scaffold(spec.name, matches, reporter),
+        "build" => build::cmd_build(matches, reporter),
+        "run" => build::cmd_run(matches, reporter),
+        "check" => check(matches, reporter),
+        "test" => build::cmd_test(matches, reporter),
+        "fmt" => fmt::cmd_fmt(matches, reporter),
+        "lex" => lex_file(&matches.args[0], matches.flag("--json"), reporter),
+        "parse" => parse_file(&matches.args[0], matches.flag("--json"), reporter),
+        "doc" => doc::cmd_doc(matches, reporter),
+        "grammar" => grammar(matches, reporter),
+        "lsp" => serve_stdio(),
+        "repl" => repl::run_stdio(),
+        "help" => help(matches, reporter),
+        _ => unreachable!("every command has an implementation"),
+    }
+}
+
+fn help(matches: &Matches, reporter: &Reporter) -> i32 {
+    match matches.args.first() {
+        None => print!("{}", general_help(COMMANDS)),
+        Some(name) => match COMMANDS.iter().find(|spec| spec.name == name) {
+            Some(spec) => print!("{}", command_help(spec)),
+            None => {
+                reporter.error(format!("Unknown command `{}`", name));
+                return EXIT_USAGE;
+            }
+        },
+    }
+    0
+}
+
+fn scaffold(command: &str, matches: &Matches, reporter: &Reporter) -> i32 {
+    let template = if matches.flag("--lib") { Template::Lib } else { Template::Bin };
+    let created = if command == "new" {
+        let dir = Path::new(&matches.args[0]);
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        new_project(dir, &name, template)
+    } else {
+        init_project(Path::new("."), template)
+    };
+    match created {
+        Ok(files) => {
+            for file in files {
+                reporter.status(format!("Created {}", file.display()));
+            }
+            0
+        }
+        Err(error) => {
+            reporter.error(error.message());
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+}
+
+/// Everything short of code generation: every stator is lexed and parsed,
+/// linted, linked and type checked, and all diagnostics found are reported.
+fn check(matches: &Matches, reporter: &Reporter) -> i32 {
+    let Ok(target) = Target::find(matches.args.first().map(String::as_str), reporter) else { return EXIT_FAILURE };
+    let mut warnings = vec![];
+    let (sources, errors) = match target.new_loader() {
+        Ok(mut loader) => {
+            let checked = loader.load_entry(&target.entry()).and_then(|_| {
+                for stator in loader.stators() {
+                    if stator.name != "std" && !stator.name.starts_with("std.") {
+                        warnings.extend(lint_program(&stator.program));
+                    }
+                }
+                let mut program = loader.link()?;
+                check_program(&mut program).map(|_| ())
+            });
+            (loader.into_sources(), checked.err().unwrap_or_default())
+        }
+        Err(errors) => (SourceMap::new(), errors),
+    };
+    for warning in &warnings {
+        reporter.diagnostic_in(Level::Warning, warning, &sources);
+    }
+    reporter.errors_in(&sources, &errors);
+
+    let counts = format!("{}, {}", plural(errors.len(), "error"), plural(warnings.len(), "warning"));
+    if errors.is_empty() {
+        reporter.status(format!("Checked {}: {}", target.name(), counts));
+        0
     } else {
-        println!("Unknown command: {}", args[1]);
+        reporter.error(format!("Could not check {}: {}", target.name(), counts));
+        EXIT_FAILURE
+    }
+}
+
+fn grammar(matches: &Matches, reporter: &Reporter) -> i32 {
+    if !matches.flag("--textmate") {
+        reporter.error("Choose a grammar format, e.g. `--textmate`");
+        return EXIT_USAGE;
+    }
+    print!("{}", textmate_grammar().pretty());
+    0
+}
+
+fn read_source(file: &str, reporter: &Reporter) -> Option<String> {
+    match std::fs::read_to_string(file) {
+        Ok(source) => Some(source),
+        Err(error) => {
+            reporter.error(format!("Unable to read {}: {}", file, error));
+            None
+        }
+    }
+}
+
+/// Prints `value` as JSON, if this build can.
+#[cfg(feature = "serde")]
+fn print_json<T: serde::Serialize + ?Sized>(value: &T, reporter: &Reporter) -> i32 {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(error) => {
+            reporter.error(format!("Unable to write JSON: {}", error));
+            EXIT_FAILURE
+        }
     }
 }
 
+#[cfg(not(feature = "serde"))]
+fn print_json<T: ?Sized>(_value: &T, reporter: &Reporter) -> i32 {
+    reporter.error("`--json` needs rotor to be built with the `serde` feature");
+    EXIT_USAGE
+}
+
+fn lex_file(file: &str, json: bool, reporter: &Reporter) -> i32 {
+    let Some(source) = read_source(file, reporter) else { return EXIT_FAILURE };
+    let lexed = lex(&source);
+    for error in &lexed.errors {
+        reporter.error(error);
+    }
+    let code = if json {
+        print_json(&lexed.tokens, reporter)
+    } else {
+        lexed.get_debug_info();
+        0
+    };
+    if lexed.errors.is_empty() { code } else { EXIT_FAILURE }
+}
+
+fn parse_file(file: &str, json: bool, reporter: &Reporter) -> i32 {
+    let Some(source) = read_source(file, reporter) else { return EXIT_FAILURE };
+    let mut sources = SourceMap::new();
+    let id = sources.add(file, source);
+    let lexed = lex(sources.text(id));
+    if !lexed.errors.is_empty() {
+        for error in &lexed.errors {
+            reporter.error(error);
+        }
+        return EXIT_FAILURE;
+    }
+    let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+    let (program, errors) = parse_program_recovering(&mut stream);
+    if !errors.is_empty() {
+        let errors: Vec<_> = errors.into_iter().map(|error| error.in_file(id)).collect();
+        reporter.errors_in(&sources, &errors);
+        return EXIT_FAILURE;
+    }
+    if json {
+        return print_json(&program, reporter);
+    }
+    print!("{}", dump_program(&program));
+    0
+}
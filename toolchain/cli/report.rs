@@ -0,0 +1,166 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Everything the CLI tells the user goes through a `Reporter`, which
+// applies the global `--color`, `--quiet` and `--message-format` options.
+// Human readable diagnostics go to stderr; JSON diagnostics go to stdout,
+// one object per line, for tools to read.
+use std::io::IsTerminal;
+use rotor::{Error, SourceMap};
+use crate::args::{ColorChoice, Global, MessageFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Level::Error => "\x1b[1;31m",
+            Level::Warning => "\x1b[1;33m",
+        }
+    }
+}
+
+pub struct Reporter {
+    color: bool,
+    quiet: bool,
+    format: MessageFormat,
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Reporter {
+    pub fn new(global: &Global) -> Self {
+        let color = match global.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        };
+        Reporter { color, quiet: global.quiet, format: global.message_format }
+    }
+
+    /// Reports a diagnostic, with the position of `error` if there is one,
+    /// and its file if `sources` has it.
+    fn emit(&self, level: Level, message: &str, error: Option<&Error>, sources: Option<&SourceMap>) {
+        // `--quiet` leaves only errors
+        if self.quiet && level == Level::Warning {
+            return;
+        }
+        let file = error.zip(sources).and_then(|(error, sources)| sources.file_of(error)).map(|file| file.path.display());
+        match self.format {
+            MessageFormat::Json => {
+                let mut object = format!("{{\"level\":\"{}\",\"message\":{}", level.name(), json_string(message));
+                if let Some(file) = &file {
+                    object.push_str(&format!(",\"file\":{}", json_string(&file.to_string())));
+                }
+                if let Some(error) = error {
+                    object.push_str(&format!(
+                        ",\"kind\":\"{:?}\",\"line\":{},\"column\":{}",
+                        error.kind(),
+                        error.line(),
+                        error.column()
+                    ));
+                }
+                object.push('}');
+                println!("{}", object);
+            }
+            MessageFormat::Human => {
+                let label = if self.color {
+                    format!("{}{}\x1b[0m", level.color(), level.name())
+                } else {
+                    level.name().to_string()
+                };
+                let file = file.map_or(String::new(), |file| format!("{}:", file));
+                match error {
+                    Some(error) if error.line() > 0 => eprintln!("{}: {}{}:{}: {}", label, file, error.line(), error.column(), message),
+                    Some(_) if !file.is_empty() => eprintln!("{}: {} {}", label, file, message),
+                    _ => eprintln!("{}: {}", label, message),
+                }
+            }
+        }
+    }
+
+    pub fn error(&self, message: impl AsRef<str>) {
+        self.emit(Level::Error, message.as_ref(), None, None);
+    }
+
+    pub fn warning(&self, message: impl AsRef<str>) {
+        self.emit(Level::Warning, message.as_ref(), None, None);
+    }
+
+    pub fn diagnostic(&self, level: Level, error: &Error) {
+        self.emit(level, error.message(), Some(error), None);
+    }
+
+    /// Reports `error` with the file it is about, from `sources`.
+    pub fn diagnostic_in(&self, level: Level, error: &Error, sources: &SourceMap) {
+        self.emit(level, error.message(), Some(error), Some(sources));
+    }
+
+    pub fn errors(&self, errors: &[Error]) {
+        for error in errors {
+            self.diagnostic(Level::Error, error);
+        }
+    }
+
+    pub fn errors_in(&self, sources: &SourceMap, errors: &[Error]) {
+        for error in errors {
+            self.diagnostic_in(Level::Error, error, sources);
+        }
+    }
+
+    /// A program the CLI ran failed, such as a test: `heading` says how,
+    /// and `output` is what it printed.
+    pub fn failure(&self, heading: &str, output: &str) {
+        match self.format {
+            MessageFormat::Json => {
+                println!("{{\"level\":\"error\",\"message\":{},\"output\":{}}}", json_string(heading), json_string(output));
+            }
+            MessageFormat::Human => {
+                let heading = format!("---- {} ----", heading);
+                if self.color {
+                    eprintln!("\n{}{}\x1b[0m", Level::Error.color(), heading);
+                } else {
+                    eprintln!("\n{}", heading);
+                }
+                eprint!("{}", output);
+            }
+        }
+    }
+
+    /// Progress and results, left out with `--quiet`. With JSON messages
+    /// they are objects of their own, so stdout stays one object per line.
+    pub fn status(&self, message: impl AsRef<str>) {
+        if self.quiet {
+            return;
+        }
+        match self.format {
+            MessageFormat::Json => println!("{{\"level\":\"status\",\"message\":{}}}", json_string(message.as_ref().trim_start())),
+            MessageFormat::Human => println!("{}", message.as_ref()),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The C backend lowers an IR module to portable C99 and hands it to the
+// system C compiler. Functions are prefixed with `r_` and globals with
+// `rg_`, since typeck keeps them in separate namespaces, so no Rotor
+// name can clash with C keywords or the runtime's `rt_` helpers.
+//
+// Every IR value becomes a local `vN`, assigned once, and every block a
+// label that is reached with `goto`. A phi also gets a `vN_in` that each
+// edge into its block writes; the phi reads it at the top of the block,
+// so all the phis of a block take their values at once.
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use crate::codegen::common::{check_bodies, check_main, run_tool, BuildDir};
+use crate::handle_error::Error;
+use crate::ir::{BinOp, BlockId, Const, Function, InstKind, Module, Terminator, UnOp};
+use crate::stdlib::is_intrinsic;
+use crate::typeck::Type;
+
+/// The runtime header every generated file includes.
+pub const RUNTIME_HEADER: &str = include_str!("runtime/rotor.h");
+
+fn c_type(ty: Type) -> &'static str {
+    match ty {
+        Type::I32 => "rt_i32",
+        Type::Bool => "rt_bool",
+        Type::Str => "rt_str",
+        Type::Void => "void",
+    }
+}
+
+fn c_name(name: &str) -> String {
+    format!("r_{}", name.replace('.', "__"))
+}
+
+fn c_global(name: &str) -> String {
+    format!("rg_{}", name.replace('.', "__"))
+}
+
+fn c_string(value: &str) -> String {
+    // Strings are UTF-8, so anything outside printable ASCII is written
+    // byte by byte
+    let mut out = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' '..=b'~' => out.push(byte as char),
+            _ => { let _ = write!(out, "\\{:03o}", byte); }
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn c_const(value: &Const) -> String {
+    match value {
+        // The literal 2147483648 doesn't fit in an int
+        Const::Int(i32::MIN) => "INT32_MIN".to_string(),
+        Const::Int(n) => n.to_string(),
+        Const::Bool(b) => b.to_string(),
+        Const::Str(s) => c_string(s),
+    }
+}
+
+fn signature(function: &Function) -> String {
+    let params = if function.params.is_empty() {
+        "void".to_string()
+    } else {
+        function.params.iter().enumerate().map(|(i, ty)| format!("{} a{}", c_type(*ty), i)).collect::<Vec<_>>().join(", ")
+    };
+    format!("{} {}({})", c_type(function.ret), c_name(&function.name), params)
+}
+
+struct CEmitter<'a> {
+    function: &'a Function,
+    out: String,
+    indent: usize,
+}
+
+impl CEmitter<'_> {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn expr(&self, kind: &InstKind) -> String {
+        match kind {
+            InstKind::Const(value) => c_const(value),
+            // Only read in unreachable code
+            InstKind::Undef => "0".to_string(),
+            InstKind::Param(index) => format!("a{}", index),
+            InstKind::Copy(value) => format!("v{}", value.0),
+            InstKind::Binary(op, left, right) => {
+                let (l, r) = (format!("v{}", left.0), format!("v{}", right.0));
+                let op = match op {
+                    BinOp::Add => return format!("rt_add({}, {})", l, r),
+                    BinOp::Sub => return format!("rt_sub({}, {})", l, r),
+                    BinOp::Mul => return format!("rt_mul({}, {})", l, r),
+                    BinOp::Div => return format!("rt_div({}, {})", l, r),
+                    BinOp::Rem => return format!("rt_rem({}, {})", l, r),
+                    BinOp::Eq => "==",
+                    BinOp::Ne => "!=",
+                    BinOp::Lt => "<",
+                    BinOp::Gt => ">",
+                    BinOp::Le => "<=",
+                    BinOp::Ge => ">=",
+                };
+                format!("({} {} {})", l, op, r)
+            }
+            InstKind::Unary(UnOp::Neg, value) => format!("rt_neg(v{})", value.0),
+            InstKind::Unary(UnOp::Not, value) => format!("(!v{})", value.0),
+            InstKind::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|arg| format!("v{}", arg.0)).collect();
+                format!("{}({})", c_name(name), args.join(", "))
+            }
+            InstKind::Phi(_) => unreachable!("phis are read from their `_in` variable"),
+            InstKind::LoadGlobal(name) => c_global(name),
+            InstKind::StoreGlobal(..) => unreachable!("stores produce no value"),
+        }
+    }
+
+    /// Writes the values flowing from `from` into the phis of `to`.
+    fn edge_copies(&mut self, from: BlockId, to: BlockId) {
+        for inst in &self.function.block(to).insts {
+            if let (Some(result), InstKind::Phi(incoming)) = (inst.result, &inst.kind) {
+                if let Some((_, value)) = incoming.iter().find(|(pred, _)| *pred == from) {
+                    let text = format!("v{}_in = v{};", result.0, value.0);
+                    self.line(&text);
+                }
+            }
+        }
+    }
+
+    fn jump(&mut self, from: BlockId, to: BlockId, next: Option<BlockId>) {
+        self.edge_copies(from, to);
+        if next != Some(to) {
+            let text = format!("goto {};", to);
+            self.line(&text);
+        }
+    }
+
+    fn emit(&mut self) {
+        let function = self.function;
+        self.out.push('\n');
+        let _ = writeln!(self.out, "{} {{", signature(function));
+        for (i, ty) in function.values.iter().enumerate() {
+            let text = format!("{} v{};", c_type(*ty), i);
+            self.line(&text);
+        }
+        for block in &function.blocks {
+            for result in block.insts.iter().filter(|inst| inst.is_phi()).filter_map(|inst| inst.result) {
+                let text = format!("{} v{}_in;", c_type(function.ty(result)), result.0);
+                self.line(&text);
+            }
+        }
+
+        let order = function.reverse_postorder();
+        for (i, block) in order.iter().enumerate() {
+            let next = order.get(i + 1).copied();
+            let _ = writeln!(self.out, "{}:", block);
+            let data = function.block(*block);
+            for inst in &data.insts {
+                let text = match (inst.result, &inst.kind) {
+                    (Some(result), InstKind::Phi(_)) => format!("v{} = v{}_in;", result.0, result.0),
+                    (None, InstKind::StoreGlobal(name, value)) => format!("{} = v{};", c_global(name), value.0),
+                    (Some(result), kind) => format!("v{} = {};", result.0, self.expr(kind)),
+                    (None, kind) => format!("{};", self.expr(kind)),
+                };
+                self.line(&text);
+            }
+            match &data.term {
+                Terminator::Jump(target) => self.jump(*block, *target, next),
+                Terminator::Branch(condition, then_block, else_block) => {
+                    let text = format!("if (v{}) {{", condition.0);
+                    self.line(&text);
+                    self.indent += 1;
+                    self.jump(*block, *then_block, None);
+                    self.indent -= 1;
+                    self.line("}");
+                    self.jump(*block, *else_block, next);
+                }
+                Terminator::Return(Some(value)) => {
+                    let text = format!("return v{};", value.0);
+                    self.line(&text);
+                }
+                Terminator::Return(None) => self.line("return;"),
+                Terminator::Unreachable => self.line("abort();"),
+            }
+        }
+        self.out.push_str("}\n");
+    }
+}
+
+/// Lowers an IR module to a C99 translation unit.
+pub fn emit_c(module: &Module) -> Result<String, Error> {
+    let main_ret = check_main(module)?;
+    check_bodies(module)?;
+
+    let mut out = String::from("/* Generated by rotor. Do not edit. */\n#include \"rotor.h\"\n\n");
+
+    // Globals, then prototypes so functions can call each other in any order
+    for global in &module.globals {
+        let qualifier = if global.mutable { "" } else { "const " };
+        let _ = writeln!(out, "static {}{} {} = {};", qualifier, c_type(global.ty), c_global(&global.name), c_const(&global.init));
+    }
+    if !module.globals.is_empty() {
+        out.push('\n');
+    }
+
+    // Intrinsics are defined by the runtime header
+    let functions: Vec<&Function> = module.functions.iter().filter(|function| !is_intrinsic(&function.name)).collect();
+    for function in &functions {
+        let _ = writeln!(out, "{};", signature(function));
+    }
+
+    for function in functions {
+        let mut emitter = CEmitter { function, out, indent: 1 };
+        emitter.emit();
+        out = emitter.out;
+    }
+
+    out.push_str("\nint main(int argc, char **argv) {\n");
+    out.push_str("    rt_argc = argc;\n");
+    out.push_str("    rt_argv = argv;\n");
+    match main_ret {
+        Type::I32 => out.push_str("    return (int)r_main();\n"),
+        _ => out.push_str("    r_main();\n    return 0;\n"),
+    }
+    out.push_str("}\n");
+
+    Ok(out)
+}
+
+/// Compiles C produced by `emit_c` into a native executable at `output`.
+/// The compiler can be overridden with the `CC` environment variable.
+pub fn build_c(c_source: &str, output: &Path) -> Result<(), Error> {
+    let dir = BuildDir::new()?;
+    dir.write("rotor.h", RUNTIME_HEADER)?;
+    let source = dir.write("main.c", c_source)?;
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    run_tool(
+        Command::new(cc)
+            .arg("-std=c99")
+            .arg("-O2")
+            .arg("-o")
+            .arg(output)
+            .arg(source)
+            .arg(format!("-I{}", dir.path().display())),
+    )
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::handle_error::{ErrorKind, Error};
+use crate::ir::Module;
+use crate::stdlib::is_intrinsic;
+use crate::typeck::Type;
+
+/// Checks that the module has a `main` every backend can start from:
+/// no parameters, returning either `i32` (the exit code) or nothing.
+pub fn check_main(module: &Module) -> Result<Type, Error> {
+    let Some(main) = module.function("main") else {
+        return Err(Error::new(ErrorKind::Backend, "No `main` function found", 0, 0));
+    };
+    if !main.params.is_empty() || !matches!(main.ret, Type::I32 | Type::Void) {
+        return Err(Error::new(
+            ErrorKind::Backend,
+            "`main` must take no parameters and return i32 or nothing",
+            0,
+            0,
+        ));
+    }
+    Ok(main.ret)
+}
+
+/// Rejects functions declared without a body, for backends that have
+/// nothing to link them against. Intrinsics are the exception.
+pub fn check_bodies(module: &Module) -> Result<(), Error> {
+    match module.functions.iter().find(|function| function.is_external() && !is_intrinsic(&function.name)) {
+        Some(function) => Err(Error::new(
+            ErrorKind::Backend,
+            format!("Function `{}` is declared without a body", function.name),
+            0,
+            0,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// A scratch directory for intermediate build files, removed on drop.
+pub struct BuildDir {
+    path: PathBuf,
+}
+
+impl BuildDir {
+    pub fn new() -> Result<Self, Error> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "rotor-build-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).map_err(|err| {
+            Error::new(ErrorKind::Backend, format!("Unable to create build directory: {}", err), 0, 0)
+        })?;
+        Ok(BuildDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> Result<PathBuf, Error> {
+        let file = self.path.join(name);
+        std::fs::write(&file, contents).map_err(|err| {
+            Error::new(ErrorKind::Backend, format!("Unable to write {}: {}", file.display(), err), 0, 0)
+        })?;
+        Ok(file)
+    }
+}
+
+impl Drop for BuildDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Runs an external tool (compiler, assembler, linker), turning a
+/// failure into an error that carries the tool's output.
+pub fn run_tool(command: &mut Command) -> Result<(), Error> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command.output().map_err(|err| {
+        Error::new(ErrorKind::Backend, format!("Unable to run `{}`: {}", program, err), 0, 0)
+    })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Backend,
+            format!("`{}` failed:\n{}", program, String::from_utf8_lossy(&output.stderr).trim_end()),
+            0,
+            0,
+        ));
+    }
+    Ok(())
+}
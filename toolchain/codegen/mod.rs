@@ -0,0 +1,8 @@
+pub mod common;
+pub mod c;
+pub mod x86_64;
+pub mod wasm;
+
+pub use c::{emit_c, build_c};
+pub use x86_64::{emit_asm, build_asm};
+pub use wasm::{emit_wasm, WasmModule};
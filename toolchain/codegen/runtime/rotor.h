@@ -0,0 +1,86 @@
+/* Copyright (c) 2025, Rotor Language Project
+ * All rights reserved.
+ *
+ * Runtime support for C generated by `rotor build`.
+ * Everything here is plain C99 so it builds with any system compiler.
+ */
+#ifndef ROTOR_RUNTIME_H
+#define ROTOR_RUNTIME_H
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef int32_t rt_i32;
+typedef bool rt_bool;
+typedef const char *rt_str;
+
+static inline void rt_panic(const char *message) {
+    fprintf(stderr, "rotor: panic: %s\n", message);
+    exit(101);
+}
+
+/* i32 arithmetic wraps on overflow, which C leaves undefined for signed
+ * integers, so it is done on the unsigned representation instead. */
+static inline rt_i32 rt_add(rt_i32 a, rt_i32 b) { return (rt_i32)((uint32_t)a + (uint32_t)b); }
+static inline rt_i32 rt_sub(rt_i32 a, rt_i32 b) { return (rt_i32)((uint32_t)a - (uint32_t)b); }
+static inline rt_i32 rt_mul(rt_i32 a, rt_i32 b) { return (rt_i32)((uint32_t)a * (uint32_t)b); }
+static inline rt_i32 rt_neg(rt_i32 a) { return (rt_i32)(0u - (uint32_t)a); }
+
+static inline rt_i32 rt_div(rt_i32 a, rt_i32 b) {
+    if (b == 0) rt_panic("attempt to divide by zero");
+    if (a == INT32_MIN && b == -1) return INT32_MIN;
+    return a / b;
+}
+
+static inline rt_i32 rt_rem(rt_i32 a, rt_i32 b) {
+    if (b == 0) rt_panic("attempt to calculate the remainder with a divisor of zero");
+    if (a == INT32_MIN && b == -1) return 0;
+    return a % b;
+}
+
+/* The command line, saved by `main` for `std.process` */
+static int rt_argc;
+static char **rt_argv;
+
+/* Standard library intrinsics, named after their linked names */
+static inline void r_std__io__console__out(rt_str s) { fputs(s, stdout); fputc('\n', stdout); }
+static inline void r_std__io__console__err(rt_str s) { fputs(s, stderr); fputc('\n', stderr); }
+
+/* Lines are allocated and never freed; the end of input gives "". */
+static inline rt_str r_std__io__console__read_line(void) {
+    size_t len = 0, cap = 64;
+    char *line = malloc(cap);
+    int c;
+    if (line == NULL) rt_panic("out of memory");
+    fflush(stdout);
+    while ((c = getchar()) != EOF && c != '\n') {
+        if (len + 1 == cap) {
+            cap *= 2;
+            line = realloc(line, cap);
+            if (line == NULL) rt_panic("out of memory");
+        }
+        line[len++] = (char)c;
+    }
+    line[len] = '\0';
+    return line;
+}
+
+static inline void r_std__process__exit(rt_i32 code) { exit(code); }
+static inline rt_i32 r_std__process__arg_count(void) { return rt_argc; }
+
+static inline rt_str r_std__process__arg(rt_i32 index) {
+    if (index < 0 || index >= rt_argc) rt_panic("argument index out of range");
+    return rt_argv[index];
+}
+
+static inline rt_i32 r_std__string__len(rt_str s) { return (rt_i32)strlen(s); }
+
+static inline rt_i32 r_std__string__byte_at(rt_str s, rt_i32 index) {
+    if (index < 0 || (size_t)index >= strlen(s)) rt_panic("byte index out of range");
+    return (unsigned char)s[index];
+}
+
+#endif /* ROTOR_RUNTIME_H */
@@ -0,0 +1,734 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The WebAssembly backend builds a small module model from an IR
+// module, which can then be written out as `.wat` text or as a `.wasm`
+// binary without any external tools.
+//
+// Every Rotor value is an i32: `bool` is 0 or 1 and `str` is a pointer
+// into the exported memory, where each string is stored as its length
+// (4 bytes, little endian) followed by its bytes. `main` is exported.
+// The standard library intrinsics a program calls are imported from the
+// host, with their stator as the module: `console.out` from `std.io` is
+// `"std.io.console" "out"`, taking one string pointer. Other functions
+// declared without a body are imported from `"env"` under their own name.
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::codegen::common::check_main;
+use crate::handle_error::Error;
+use crate::ir::{self, BinOp, BlockId, Const, InstKind, Module, Terminator, UnOp, Value};
+use crate::stdlib::is_intrinsic;
+use crate::typeck::Type;
+
+// Strings start here so no valid pointer is ever 0
+const DATA_START: u32 = 16;
+const PAGE_SIZE: u32 = 65536;
+
+/// The subset of WebAssembly instructions the backend produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Unreachable,
+    // Blocks either produce nothing or a single i32
+    Block,
+    Loop,
+    If { result: bool },
+    Else,
+    End,
+    Br(u32),
+    BrIf(u32),
+    // `br_table 0 1 .. n-1` for n, with n - 1 as the default: branches
+    // to the block as many levels out as the index on the stack
+    BrTable(u32),
+    Return,
+    Call(u32),
+    Drop,
+    LocalGet(u32),
+    LocalSet(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    I32Const(i32),
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtS,
+    I32GtS,
+    I32LeS,
+    I32GeS,
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I32DivS,
+    I32RemS,
+}
+
+impl Instr {
+    fn opcode(self) -> u8 {
+        match self {
+            Instr::Unreachable => 0x00,
+            Instr::Block => 0x02,
+            Instr::Loop => 0x03,
+            Instr::If { .. } => 0x04,
+            Instr::Else => 0x05,
+            Instr::End => 0x0B,
+            Instr::Br(_) => 0x0C,
+            Instr::BrIf(_) => 0x0D,
+            Instr::BrTable(_) => 0x0E,
+            Instr::Return => 0x0F,
+            Instr::Call(_) => 0x10,
+            Instr::Drop => 0x1A,
+            Instr::LocalGet(_) => 0x20,
+            Instr::LocalSet(_) => 0x21,
+            Instr::GlobalGet(_) => 0x23,
+            Instr::GlobalSet(_) => 0x24,
+            Instr::I32Const(_) => 0x41,
+            Instr::I32Eqz => 0x45,
+            Instr::I32Eq => 0x46,
+            Instr::I32Ne => 0x47,
+            Instr::I32LtS => 0x48,
+            Instr::I32GtS => 0x4A,
+            Instr::I32LeS => 0x4C,
+            Instr::I32GeS => 0x4E,
+            Instr::I32Add => 0x6A,
+            Instr::I32Sub => 0x6B,
+            Instr::I32Mul => 0x6C,
+            Instr::I32DivS => 0x6D,
+            Instr::I32RemS => 0x6F,
+        }
+    }
+
+    fn text(self) -> String {
+        match self {
+            Instr::Unreachable => "unreachable".to_string(),
+            Instr::Block => "block".to_string(),
+            Instr::Loop => "loop".to_string(),
+            Instr::If { result: true } => "if (result i32)".to_string(),
+            Instr::If { result: false } => "if".to_string(),
+            Instr::Else => "else".to_string(),
+            Instr::End => "end".to_string(),
+            Instr::Br(depth) => format!("br {}", depth),
+            Instr::BrIf(depth) => format!("br_if {}", depth),
+            Instr::BrTable(count) => {
+                let depths: Vec<String> = (0..count).chain(count.checked_sub(1)).map(|depth| depth.to_string()).collect();
+                format!("br_table {}", depths.join(" "))
+            }
+            Instr::Return => "return".to_string(),
+            Instr::Call(index) => format!("call {}", index),
+            Instr::Drop => "drop".to_string(),
+            Instr::LocalGet(index) => format!("local.get {}", index),
+            Instr::LocalSet(index) => format!("local.set {}", index),
+            Instr::GlobalGet(index) => format!("global.get {}", index),
+            Instr::GlobalSet(index) => format!("global.set {}", index),
+            Instr::I32Const(value) => format!("i32.const {}", value),
+            Instr::I32Eqz => "i32.eqz".to_string(),
+            Instr::I32Eq => "i32.eq".to_string(),
+            Instr::I32Ne => "i32.ne".to_string(),
+            Instr::I32LtS => "i32.lt_s".to_string(),
+            Instr::I32GtS => "i32.gt_s".to_string(),
+            Instr::I32LeS => "i32.le_s".to_string(),
+            Instr::I32GeS => "i32.ge_s".to_string(),
+            Instr::I32Add => "i32.add".to_string(),
+            Instr::I32Sub => "i32.sub".to_string(),
+            Instr::I32Mul => "i32.mul".to_string(),
+            Instr::I32DivS => "i32.div_s".to_string(),
+            Instr::I32RemS => "i32.rem_s".to_string(),
+        }
+    }
+}
+
+/// A function type: every parameter is an i32 and there is at most one result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: u32,
+    pub result: bool,
+}
+
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub ty: u32,
+}
+
+pub struct Function {
+    pub name: String,
+    pub ty: u32,
+    // Locals declared after the parameters, all i32
+    pub locals: u32,
+    pub body: Vec<Instr>,
+}
+
+pub struct Global {
+    pub name: String,
+    pub mutable: bool,
+    pub init: i32,
+}
+
+/// A WebAssembly module. Function indices count imports first.
+pub struct WasmModule {
+    pub types: Vec<FuncType>,
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    pub globals: Vec<Global>,
+    pub memory_pages: u32,
+    // (name, function index)
+    pub exports: Vec<(String, u32)>,
+    pub data: Vec<u8>,
+}
+
+fn func_type(function: &ir::Function) -> FuncType {
+    FuncType { params: function.params.len() as u32, result: function.ret != Type::Void }
+}
+
+fn type_index(types: &mut Vec<FuncType>, ty: FuncType) -> u32 {
+    match types.iter().position(|existing| *existing == ty) {
+        Some(index) => index as u32,
+        None => {
+            types.push(ty);
+            types.len() as u32 - 1
+        }
+    }
+}
+
+struct FnEmitter<'a> {
+    function: &'a ir::Function,
+    function_indices: &'a HashMap<&'a str, u32>,
+    global_indices: &'a HashMap<&'a str, u32>,
+    strings: &'a mut StringTable,
+    body: Vec<Instr>,
+    // The local holding each value; parameters stay in their own
+    locals: Vec<u32>,
+    // The local holding the block to go to next, in functions that
+    // dispatch between blocks
+    label: u32,
+    // Where each block is in the dispatch, by `BlockId`
+    positions: Vec<Option<u32>>,
+    blocks: u32,
+}
+
+struct StringTable {
+    data: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn intern(&mut self, value: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(value) {
+            return *offset;
+        }
+        // Keep each length prefix 4 byte aligned
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        let offset = DATA_START + self.data.len() as u32;
+        // The length prefix counts UTF-8 bytes, as `len` does
+        self.data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(value.as_bytes());
+        self.offsets.insert(value.to_string(), offset);
+        offset
+    }
+}
+
+impl FnEmitter<'_> {
+    fn emit(&mut self, instr: Instr) {
+        self.body.push(instr);
+    }
+
+    fn get(&mut self, value: Value) {
+        self.emit(Instr::LocalGet(self.locals[value.0 as usize]));
+    }
+
+    fn inst(&mut self, result: Option<Value>, kind: &InstKind) {
+        match kind {
+            InstKind::Const(value) => {
+                let value = match value {
+                    Const::Int(n) => *n,
+                    Const::Bool(b) => *b as i32,
+                    Const::Str(s) => self.strings.intern(s) as i32,
+                };
+                self.emit(Instr::I32Const(value));
+            }
+            // Only read in unreachable code
+            InstKind::Undef => self.emit(Instr::I32Const(0)),
+            // Phis are filled in by their predecessors, and parameters
+            // are already in their locals
+            InstKind::Phi(_) | InstKind::Param(_) => return,
+            InstKind::Copy(value) => self.get(*value),
+            InstKind::Binary(BinOp::Div, left, right) => {
+                // i32.div_s traps on i32::MIN / -1, which wraps in the other backends
+                self.get(*right);
+                self.emit(Instr::I32Const(-1));
+                self.emit(Instr::I32Eq);
+                self.emit(Instr::If { result: true });
+                self.emit(Instr::I32Const(0));
+                self.get(*left);
+                self.emit(Instr::I32Sub);
+                self.emit(Instr::Else);
+                self.get(*left);
+                self.get(*right);
+                self.emit(Instr::I32DivS);
+                self.emit(Instr::End);
+            }
+            InstKind::Binary(op, left, right) => {
+                self.get(*left);
+                self.get(*right);
+                self.emit(match op {
+                    BinOp::Add => Instr::I32Add,
+                    BinOp::Sub => Instr::I32Sub,
+                    BinOp::Mul => Instr::I32Mul,
+                    BinOp::Div => Instr::I32DivS,
+                    BinOp::Rem => Instr::I32RemS,
+                    BinOp::Lt => Instr::I32LtS,
+                    BinOp::Gt => Instr::I32GtS,
+                    BinOp::Le => Instr::I32LeS,
+                    BinOp::Ge => Instr::I32GeS,
+                    BinOp::Eq => Instr::I32Eq,
+                    BinOp::Ne => Instr::I32Ne,
+                });
+            }
+            InstKind::Unary(UnOp::Neg, value) => {
+                self.emit(Instr::I32Const(0));
+                self.get(*value);
+                self.emit(Instr::I32Sub);
+            }
+            InstKind::Unary(UnOp::Not, value) => {
+                self.get(*value);
+                self.emit(Instr::I32Eqz);
+            }
+            InstKind::Call(name, args) => {
+                for arg in args {
+                    self.get(*arg);
+                }
+                self.emit(Instr::Call(self.function_indices[name.as_str()]));
+            }
+            InstKind::LoadGlobal(name) => self.emit(Instr::GlobalGet(self.global_indices[name.as_str()])),
+            InstKind::StoreGlobal(name, value) => {
+                self.get(*value);
+                self.emit(Instr::GlobalSet(self.global_indices[name.as_str()]));
+            }
+        }
+        if let Some(result) = result {
+            self.emit(Instr::LocalSet(self.locals[result.0 as usize]));
+        }
+    }
+
+    /// Goes from `from` to `to`, which is `depth` branches out from the
+    /// dispatch loop. The values flowing into the phis of `to` are all
+    /// read before any is written, since a phi can read another phi of
+    /// the same block.
+    fn jump(&mut self, from: BlockId, to: BlockId, depth: u32, falls_through: bool) {
+        let mut phis = vec![];
+        for inst in &self.function.block(to).insts {
+            if let (Some(result), InstKind::Phi(incoming)) = (inst.result, &inst.kind) {
+                if let Some((_, value)) = incoming.iter().find(|(pred, _)| *pred == from) {
+                    self.get(*value);
+                    phis.push(result);
+                }
+            }
+        }
+        for phi in phis.into_iter().rev() {
+            self.emit(Instr::LocalSet(self.locals[phi.0 as usize]));
+        }
+        if !falls_through {
+            let position = self.positions[to.0 as usize].unwrap();
+            self.emit(Instr::I32Const(position as i32));
+            self.emit(Instr::LocalSet(self.label));
+            self.emit(Instr::Br(depth));
+        }
+    }
+
+    /// Emits the function's blocks. A function with more than one is a
+    /// loop around a `br_table` on the `label` local: block n follows the
+    /// end of the nth `block` inside the loop, so a branch to the loop
+    /// goes to whichever block `label` names, and a block falls through
+    /// to the one after it.
+    fn emit_blocks(&mut self, order: &[BlockId]) {
+        let dispatch = self.blocks > 1;
+        if dispatch {
+            self.emit(Instr::Loop);
+            for _ in 0..self.blocks {
+                self.emit(Instr::Block);
+            }
+            self.emit(Instr::LocalGet(self.label));
+            self.emit(Instr::BrTable(self.blocks));
+        }
+        let function = self.function;
+        for (position, block) in order.iter().enumerate() {
+            if dispatch {
+                self.emit(Instr::End);
+            }
+            // How many branches out the loop is from here
+            let depth = self.blocks - 1 - position as u32;
+            let next = order.get(position + 1).copied();
+            let data = function.block(*block);
+            for inst in &data.insts {
+                self.inst(inst.result, &inst.kind);
+            }
+            match &data.term {
+                Terminator::Jump(target) => self.jump(*block, *target, depth, next == Some(*target)),
+                Terminator::Branch(condition, then_block, else_block) => {
+                    // The side that can fall through goes last
+                    self.get(*condition);
+                    let (taken, other) = if next == Some(*then_block) {
+                        self.emit(Instr::I32Eqz);
+                        (else_block, then_block)
+                    } else {
+                        (then_block, else_block)
+                    };
+                    self.emit(Instr::If { result: false });
+                    self.jump(*block, *taken, depth + 1, false);
+                    self.emit(Instr::End);
+                    self.jump(*block, *other, depth, next == Some(*other));
+                }
+                Terminator::Return(value) => {
+                    if let Some(value) = value {
+                        self.get(*value);
+                    }
+                    self.emit(Instr::Return);
+                }
+                Terminator::Unreachable => self.emit(Instr::Unreachable),
+            }
+        }
+        if dispatch {
+            self.emit(Instr::End);
+            // Every block ends in a branch, so the loop is never left
+            self.emit(Instr::Unreachable);
+        }
+        self.emit(Instr::End);
+    }
+}
+
+/// Lowers an IR module to a WebAssembly module.
+pub fn emit_wasm(module: &Module) -> Result<WasmModule, Error> {
+    check_main(module)?;
+
+    let mut wasm = WasmModule {
+        types: vec![],
+        imports: vec![],
+        functions: vec![],
+        globals: vec![],
+        memory_pages: 1,
+        exports: vec![],
+        data: vec![],
+    };
+    // Imports come first in the function index space: the intrinsics the
+    // program actually calls, then functions declared without a body
+    let mut function_indices: HashMap<&str, u32> = HashMap::new();
+    let calls = module.functions.iter().flat_map(|function| &function.blocks).flat_map(|block| &block.insts);
+    for inst in calls {
+        let InstKind::Call(name, _) = &inst.kind else { continue };
+        if !is_intrinsic(name) || function_indices.contains_key(name.as_str()) {
+            continue;
+        }
+        let Some(function) = module.function(name) else { continue };
+        let ty = type_index(&mut wasm.types, func_type(function));
+        let (stator, item) = name.rsplit_once('.').unwrap();
+        function_indices.insert(name, wasm.imports.len() as u32);
+        wasm.imports.push(Import { module: stator.to_string(), name: item.to_string(), ty });
+    }
+    for function in module.functions.iter().filter(|function| function.is_external() && !is_intrinsic(&function.name)) {
+        let ty = type_index(&mut wasm.types, func_type(function));
+        function_indices.insert(&function.name, wasm.imports.len() as u32);
+        wasm.imports.push(Import { module: "env".to_string(), name: function.name.clone(), ty });
+    }
+    let defined: Vec<&ir::Function> = module.functions.iter().filter(|function| !function.is_external()).collect();
+    for (i, function) in defined.iter().enumerate() {
+        function_indices.insert(&function.name, (wasm.imports.len() + i) as u32);
+    }
+
+    let mut strings = StringTable { data: vec![], offsets: HashMap::new() };
+    let mut global_indices: HashMap<&str, u32> = HashMap::new();
+    for global in &module.globals {
+        let init = match &global.init {
+            Const::Int(n) => *n,
+            Const::Bool(b) => *b as i32,
+            Const::Str(s) => strings.intern(s) as i32,
+        };
+        global_indices.insert(&global.name, wasm.globals.len() as u32);
+        wasm.globals.push(Global { name: global.name.clone(), mutable: global.mutable, init });
+    }
+
+    for function in defined {
+        // Parameters are locals already; every other value gets one after them
+        let params = function.params.len() as u32;
+        let mut next_local = params;
+        let mut locals = vec![0; function.values.len()];
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            let Some(result) = inst.result else { continue };
+            locals[result.0 as usize] = match inst.kind {
+                InstKind::Param(index) => index as u32,
+                _ => {
+                    next_local += 1;
+                    next_local - 1
+                }
+            };
+        }
+        let order = function.reverse_postorder();
+        let mut positions = vec![None; function.blocks.len()];
+        for (position, block) in order.iter().enumerate() {
+            positions[block.0 as usize] = Some(position as u32);
+        }
+        let label = next_local;
+        if order.len() > 1 {
+            next_local += 1;
+        }
+
+        let mut emitter = FnEmitter {
+            function,
+            function_indices: &function_indices,
+            global_indices: &global_indices,
+            strings: &mut strings,
+            body: vec![],
+            locals,
+            label,
+            positions,
+            blocks: order.len() as u32,
+        };
+        emitter.emit_blocks(&order);
+
+        let ty = type_index(&mut wasm.types, func_type(function));
+        wasm.functions.push(Function { name: function.name.clone(), ty, locals: next_local - params, body: emitter.body });
+    }
+
+    wasm.exports.push(("main".to_string(), function_indices["main"]));
+    let end = DATA_START + strings.data.len() as u32;
+    wasm.memory_pages = end.div_ceil(PAGE_SIZE).max(1);
+    wasm.data = strings.data;
+    Ok(wasm)
+}
+
+fn wat_string(bytes: &[u8]) -> String {
+    let mut out = String::from("\"");
+    for byte in bytes {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' '..=b'~' => out.push(*byte as char),
+            _ => { let _ = write!(out, "\\{:02x}", byte); }
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+    out.push(id);
+    write_u32(out, contents.len() as u32);
+    out.extend(contents);
+}
+
+const I32: u8 = 0x7F;
+
+impl WasmModule {
+    /// Writes the module in the WebAssembly text format.
+    pub fn to_wat(&self) -> String {
+        let mut out = String::from(";; Generated by rotor. Do not edit.\n(module\n");
+        for (i, ty) in self.types.iter().enumerate() {
+            let _ = write!(out, "  (type (;{};) (func", i);
+            if ty.params > 0 {
+                out.push_str(" (param");
+                for _ in 0..ty.params {
+                    out.push_str(" i32");
+                }
+                out.push(')');
+            }
+            if ty.result {
+                out.push_str(" (result i32)");
+            }
+            out.push_str("))\n");
+        }
+        for (i, import) in self.imports.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  (import \"{}\" \"{}\" (func (;{};) (type {})))",
+                import.module, import.name, i, import.ty
+            );
+        }
+        let first = self.imports.len();
+        for (i, function) in self.functions.iter().enumerate() {
+            let _ = write!(out, "  (func ${} (;{};) (type {})", function.name, first + i, function.ty);
+            if function.locals > 0 {
+                out.push_str("\n    (local");
+                for _ in 0..function.locals {
+                    out.push_str(" i32");
+                }
+                out.push(')');
+            }
+            out.push('\n');
+            let mut indent = 2;
+            // The final `end` closes the function itself
+            for instr in &function.body[..function.body.len() - 1] {
+                if matches!(instr, Instr::End | Instr::Else) {
+                    indent -= 1;
+                }
+                let _ = writeln!(out, "{}{}", "  ".repeat(indent), instr.text());
+                if matches!(instr, Instr::Block | Instr::Loop | Instr::If { .. } | Instr::Else) {
+                    indent += 1;
+                }
+            }
+            out.push_str("  )\n");
+        }
+        let _ = writeln!(out, "  (memory (;0;) {})", self.memory_pages);
+        for (i, global) in self.globals.iter().enumerate() {
+            let ty = if global.mutable { "(mut i32)" } else { "i32" };
+            let _ = writeln!(out, "  (global ${} (;{};) {} (i32.const {}))", global.name, i, ty, global.init);
+        }
+        out.push_str("  (export \"memory\" (memory 0))\n");
+        for (name, index) in &self.exports {
+            let _ = writeln!(out, "  (export \"{}\" (func {}))", name, index);
+        }
+        if !self.data.is_empty() {
+            let _ = writeln!(out, "  (data (i32.const {}) {})", DATA_START, wat_string(&self.data));
+        }
+        out.push_str(")\n");
+        out
+    }
+
+    /// Encodes the module in the WebAssembly binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = b"\0asm".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+
+        let mut section = vec![];
+        write_u32(&mut section, self.types.len() as u32);
+        for ty in &self.types {
+            section.push(0x60);
+            write_u32(&mut section, ty.params);
+            section.extend(std::iter::repeat_n(I32, ty.params as usize));
+            write_u32(&mut section, ty.result as u32);
+            if ty.result {
+                section.push(I32);
+            }
+        }
+        write_section(&mut out, 1, section);
+
+        if !self.imports.is_empty() {
+            let mut section = vec![];
+            write_u32(&mut section, self.imports.len() as u32);
+            for import in &self.imports {
+                write_name(&mut section, &import.module);
+                write_name(&mut section, &import.name);
+                section.push(0x00);
+                write_u32(&mut section, import.ty);
+            }
+            write_section(&mut out, 2, section);
+        }
+
+        let mut section = vec![];
+        write_u32(&mut section, self.functions.len() as u32);
+        for function in &self.functions {
+            write_u32(&mut section, function.ty);
+        }
+        write_section(&mut out, 3, section);
+
+        let mut section = vec![];
+        write_u32(&mut section, 1);
+        section.push(0x00);
+        write_u32(&mut section, self.memory_pages);
+        write_section(&mut out, 5, section);
+
+        if !self.globals.is_empty() {
+            let mut section = vec![];
+            write_u32(&mut section, self.globals.len() as u32);
+            for global in &self.globals {
+                section.push(I32);
+                section.push(global.mutable as u8);
+                section.push(Instr::I32Const(0).opcode());
+                write_i32(&mut section, global.init);
+                section.push(Instr::End.opcode());
+            }
+            write_section(&mut out, 6, section);
+        }
+
+        let mut section = vec![];
+        write_u32(&mut section, self.exports.len() as u32 + 1);
+        write_name(&mut section, "memory");
+        section.push(0x02);
+        write_u32(&mut section, 0);
+        for (name, index) in &self.exports {
+            write_name(&mut section, name);
+            section.push(0x00);
+            write_u32(&mut section, *index);
+        }
+        write_section(&mut out, 7, section);
+
+        let mut section = vec![];
+        write_u32(&mut section, self.functions.len() as u32);
+        for function in &self.functions {
+            let mut body = vec![];
+            if function.locals > 0 {
+                write_u32(&mut body, 1);
+                write_u32(&mut body, function.locals);
+                body.push(I32);
+            } else {
+                write_u32(&mut body, 0);
+            }
+            for instr in &function.body {
+                body.push(instr.opcode());
+                match *instr {
+                    Instr::Block | Instr::Loop | Instr::If { result: false } => body.push(0x40),
+                    Instr::If { result: true } => body.push(I32),
+                    Instr::Br(index)
+                    | Instr::BrIf(index)
+                    | Instr::Call(index)
+                    | Instr::LocalGet(index)
+                    | Instr::LocalSet(index)
+                    | Instr::GlobalGet(index)
+                    | Instr::GlobalSet(index) => write_u32(&mut body, index),
+                    Instr::BrTable(count) => {
+                        write_u32(&mut body, count);
+                        (0..count).for_each(|depth| write_u32(&mut body, depth));
+                        write_u32(&mut body, count.saturating_sub(1));
+                    }
+                    Instr::I32Const(value) => write_i32(&mut body, value),
+                    _ => {}
+                }
+            }
+            write_u32(&mut section, body.len() as u32);
+            section.extend(body);
+        }
+        write_section(&mut out, 10, section);
+
+        if !self.data.is_empty() {
+            let mut section = vec![];
+            write_u32(&mut section, 1);
+            write_u32(&mut section, 0); // active segment in memory 0
+            section.push(Instr::I32Const(0).opcode());
+            write_i32(&mut section, DATA_START as i32);
+            section.push(Instr::End.opcode());
+            write_u32(&mut section, self.data.len() as u32);
+            section.extend_from_slice(&self.data);
+            write_section(&mut out, 11, section);
+        }
+
+        out
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The x86-64 backend emits System V assembly in GAS (AT&T) syntax and
+// links it into a static ELF executable with the system `as` and `ld`.
+// It needs no libc: `_start` calls `main` and exits through a syscall,
+// so it only runs on x86-64 Linux. The standard library's intrinsics are
+// written out in assembly as well; strings returned by `read_line` come
+// from a fixed arena that is never freed.
+//
+// Code is generated from the SSA IR without register allocation: every
+// value lives in its own 8 byte slot below %rbp and instructions work
+// through %eax and %ecx. Phis are resolved by copying the incoming
+// values into the phi's slot on each edge into its block.
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use crate::codegen::common::{check_bodies, check_main, run_tool, BuildDir};
+use crate::handle_error::Error;
+use crate::ir::{BinOp, BlockId, Const, Function, InstKind, Module, Terminator, UnOp, Value};
+use crate::typeck::Type;
+
+const ARG_REGISTERS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+// Every panic prints its message to stderr and exits with code 101
+const PANICS: &[(&str, &str)] = &[
+    ("rt_panic_div_zero", "rotor: panic: attempt to divide by zero\n"),
+    ("rt_panic_rem_zero", "rotor: panic: attempt to calculate the remainder with a divisor of zero\n"),
+    ("rt_panic_arg_range", "rotor: panic: argument index out of range\n"),
+    ("rt_panic_byte_range", "rotor: panic: byte index out of range\n"),
+    ("rt_panic_out_of_memory", "rotor: panic: out of memory\n"),
+];
+
+// Bytes available to strings read at runtime
+const HEAP_SIZE: usize = 1 << 20;
+
+// The standard library intrinsics, following the System V calling convention
+const INTRINSICS: &str = concat!(
+    // std.io.console: write the NUL terminated string and a newline
+    "\n    .globl r_std__io__console__out\nr_std__io__console__out:\n    mov $1, %r8d\n    jmp rt_write_line\n",
+    "\n    .globl r_std__io__console__err\nr_std__io__console__err:\n    mov $2, %r8d\n",
+    "rt_write_line:\n    mov %rdi, %rsi\n    xor %edx, %edx\n",
+    "1:\n    cmpb $0, (%rsi,%rdx)\n    je 2f\n    inc %rdx\n    jmp 1b\n",
+    "2:\n    mov %r8d, %edi\n    mov $1, %eax\n    syscall\n",
+    "    mov %r8d, %edi\n    lea rt_newline(%rip), %rsi\n    mov $1, %edx\n    mov $1, %eax\n    syscall\n    ret\n",
+    // Reads one byte at a time into the arena until a newline or the end of input
+    "\n    .globl r_std__io__console__read_line\nr_std__io__console__read_line:\n",
+    "    mov rt_heap_used(%rip), %r8\n    lea rt_heap(%rip), %r9\n    mov %r8, %r10\n",
+    "1:\n    cmp $rt_heap_size - 1, %r10\n    jae rt_panic_out_of_memory\n",
+    "    xor %eax, %eax\n    xor %edi, %edi\n    lea (%r9,%r10), %rsi\n    mov $1, %edx\n    syscall\n",
+    "    cmp $1, %rax\n    jne 2f\n    cmpb $10, (%r9,%r10)\n    je 2f\n    inc %r10\n    jmp 1b\n",
+    "2:\n    movb $0, (%r9,%r10)\n    lea 1(%r10), %rax\n    mov %rax, rt_heap_used(%rip)\n",
+    "    lea (%r9,%r8), %rax\n    ret\n",
+    // std.process
+    "\n    .globl r_std__process__exit\nr_std__process__exit:\n    mov $60, %eax\n    syscall\n",
+    "\n    .globl r_std__process__arg_count\nr_std__process__arg_count:\n    mov rt_argc(%rip), %rax\n    ret\n",
+    "\n    .globl r_std__process__arg\nr_std__process__arg:\n    movslq %edi, %rdi\n",
+    "    cmp rt_argc(%rip), %rdi\n    jae rt_panic_arg_range\n",
+    "    mov rt_argv(%rip), %rax\n    mov (%rax,%rdi,8), %rax\n    ret\n",
+    // std.string
+    "\n    .globl r_std__string__len\nr_std__string__len:\n    xor %eax, %eax\n",
+    "1:\n    cmpb $0, (%rdi,%rax)\n    je 2f\n    inc %rax\n    jmp 1b\n2:\n    ret\n",
+    "\n    .globl r_std__string__byte_at\nr_std__string__byte_at:\n    movslq %esi, %rsi\n    xor %eax, %eax\n",
+    "1:\n    cmpb $0, (%rdi,%rax)\n    je 2f\n    inc %rax\n    jmp 1b\n",
+    "2:\n    cmp %rax, %rsi\n    jae rt_panic_byte_range\n    movzbl (%rdi,%rsi), %eax\n    ret\n",
+);
+
+fn asm_name(name: &str) -> String {
+    format!("r_{}", name.replace('.', "__"))
+}
+
+// Globals and functions are in separate namespaces, so they can share a name
+fn asm_global(name: &str) -> String {
+    format!("rg_{}", name.replace('.', "__"))
+}
+
+fn asm_string(value: &str) -> String {
+    // Escaped byte by byte, like the C backend
+    let mut out = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' '..=b'~' => out.push(byte as char),
+            _ => { let _ = write!(out, "\\{:03o}", byte); }
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn slot(value: Value) -> String {
+    format!("{}(%rbp)", -8 * (value.0 as i64 + 1))
+}
+
+fn const_operand(value: &Const, strings: &mut Vec<String>) -> String {
+    match value {
+        Const::Int(n) => n.to_string(),
+        Const::Bool(b) => (*b as i32).to_string(),
+        Const::Str(s) => {
+            strings.push(s.clone());
+            format!(".Lstr{}", strings.len() - 1)
+        }
+    }
+}
+
+struct AsmEmitter<'a> {
+    function: &'a Function,
+    // Text of the function being emitted
+    body: String,
+    // String literals, emitted into .rodata at the end
+    strings: &'a mut Vec<String>,
+    labels: &'a mut usize,
+}
+
+impl AsmEmitter<'_> {
+    fn ins(&mut self, text: &str) {
+        self.body.push_str("    ");
+        self.body.push_str(text);
+        self.body.push('\n');
+    }
+
+    fn label(&mut self, label: &str) {
+        self.body.push_str(label);
+        self.body.push_str(":\n");
+    }
+
+    fn new_label(&mut self) -> String {
+        *self.labels += 1;
+        format!(".L{}", self.labels)
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".L{}_{}", asm_name(&self.function.name), block)
+    }
+
+    /// Applies `op` to %eax (left) and %ecx (right), leaving the result in %eax.
+    fn binary(&mut self, op: BinOp) {
+        let set = match op {
+            BinOp::Add => return self.ins("add %ecx, %eax"),
+            BinOp::Sub => return self.ins("sub %ecx, %eax"),
+            BinOp::Mul => return self.ins("imul %ecx, %eax"),
+            BinOp::Div | BinOp::Rem => return self.divide(op == BinOp::Rem),
+            BinOp::Lt => "setl",
+            BinOp::Gt => "setg",
+            BinOp::Le => "setle",
+            BinOp::Ge => "setge",
+            BinOp::Eq => "sete",
+            BinOp::Ne => "setne",
+        };
+        self.ins("cmp %ecx, %eax");
+        self.ins(&format!("{} %al", set));
+        self.ins("movzbl %al, %eax");
+    }
+
+    fn divide(&mut self, remainder: bool) {
+        // idiv faults on a zero divisor and on i32::MIN / -1, so both are
+        // handled before it runs (the latter wraps, like the C backend)
+        let panic = if remainder { "rt_panic_rem_zero" } else { "rt_panic_div_zero" };
+        let (normal, end) = (self.new_label(), self.new_label());
+        self.ins("test %ecx, %ecx");
+        self.ins(&format!("jz {}", panic));
+        self.ins("cmp $-1, %ecx");
+        self.ins(&format!("jne {}", normal));
+        self.ins(if remainder { "xor %eax, %eax" } else { "neg %eax" });
+        self.ins(&format!("jmp {}", end));
+        self.label(&normal);
+        self.ins("cltd");
+        self.ins("idiv %ecx");
+        if remainder {
+            self.ins("mov %edx, %eax");
+        }
+        self.label(&end);
+    }
+
+    fn call(&mut self, name: &str, args: &[Value]) {
+        // The frame keeps %rsp 16 byte aligned, so only an odd number of
+        // stack arguments needs padding. Those are pushed in reverse, as
+        // System V expects, and the first six go in registers.
+        let stack_args = args.len().saturating_sub(ARG_REGISTERS.len());
+        let padding = stack_args % 2;
+        if padding == 1 {
+            self.ins("sub $8, %rsp");
+        }
+        for arg in args.iter().skip(ARG_REGISTERS.len()).rev() {
+            self.ins(&format!("push {}", slot(*arg)));
+        }
+        for (arg, register) in args.iter().zip(ARG_REGISTERS) {
+            self.ins(&format!("mov {}, {}", slot(*arg), register));
+        }
+        self.ins(&format!("call {}", asm_name(name)));
+        let cleanup = 8 * (stack_args + padding);
+        if cleanup > 0 {
+            self.ins(&format!("add ${}, %rsp", cleanup));
+        }
+    }
+
+    /// Copies the values flowing from `from` into the phis of `to`. The
+    /// copies happen at once, since a phi can read another phi of the
+    /// same block.
+    fn edge_copies(&mut self, from: BlockId, to: BlockId) {
+        let mut moves = vec![];
+        for inst in &self.function.block(to).insts {
+            if let (Some(result), InstKind::Phi(incoming)) = (inst.result, &inst.kind) {
+                if let Some((_, value)) = incoming.iter().find(|(pred, _)| *pred == from) {
+                    if *value != result {
+                        moves.push((*value, result));
+                    }
+                }
+            }
+        }
+        for (source, _) in &moves {
+            self.ins(&format!("push {}", slot(*source)));
+        }
+        for (_, dest) in moves.iter().rev() {
+            self.ins(&format!("pop {}", slot(*dest)));
+        }
+    }
+
+    fn jump(&mut self, from: BlockId, to: BlockId, next: Option<BlockId>) {
+        self.edge_copies(from, to);
+        if next != Some(to) {
+            let label = self.block_label(to);
+            self.ins(&format!("jmp {}", label));
+        }
+    }
+
+    fn inst(&mut self, result: Option<Value>, kind: &InstKind) {
+        match kind {
+            InstKind::Const(value) => {
+                let operand = const_operand(value, self.strings);
+                if let Const::Str(_) = value {
+                    self.ins(&format!("lea {}(%rip), %rax", operand));
+                } else {
+                    self.ins(&format!("mov ${}, %eax", operand));
+                }
+            }
+            // Phis are filled in by their predecessors
+            InstKind::Undef | InstKind::Phi(_) => return,
+            InstKind::Param(index) => match ARG_REGISTERS.get(*index) {
+                Some(register) => self.ins(&format!("mov {}, %rax", register)),
+                None => {
+                    let caller = 16 + 8 * (index - ARG_REGISTERS.len());
+                    self.ins(&format!("mov {}(%rbp), %rax", caller));
+                }
+            },
+            InstKind::Copy(value) => self.ins(&format!("mov {}, %rax", slot(*value))),
+            InstKind::Binary(op, left, right) => {
+                self.ins(&format!("mov {}, %eax", slot(*left)));
+                self.ins(&format!("mov {}, %ecx", slot(*right)));
+                self.binary(*op);
+            }
+            InstKind::Unary(op, value) => {
+                self.ins(&format!("mov {}, %eax", slot(*value)));
+                match op {
+                    UnOp::Neg => self.ins("neg %eax"),
+                    UnOp::Not => self.ins("xor $1, %eax"),
+                }
+            }
+            InstKind::Call(name, args) => self.call(name, args),
+            InstKind::LoadGlobal(name) => self.ins(&format!("mov {}(%rip), %rax", asm_global(name))),
+            InstKind::StoreGlobal(name, value) => {
+                self.ins(&format!("mov {}, %rax", slot(*value)));
+                self.ins(&format!("mov %rax, {}(%rip)", asm_global(name)));
+            }
+        }
+        if let Some(result) = result {
+            self.ins(&format!("mov %rax, {}", slot(result)));
+        }
+    }
+
+    fn emit(&mut self) -> String {
+        let function = self.function;
+        let order = function.reverse_postorder();
+        for (i, block) in order.iter().enumerate() {
+            let next = order.get(i + 1).copied();
+            let label = self.block_label(*block);
+            self.label(&label);
+            let data = function.block(*block);
+            for inst in &data.insts {
+                self.inst(inst.result, &inst.kind);
+            }
+            match &data.term {
+                Terminator::Jump(target) => self.jump(*block, *target, next),
+                Terminator::Branch(condition, then_block, else_block) => {
+                    let otherwise = self.new_label();
+                    self.ins(&format!("mov {}, %eax", slot(*condition)));
+                    self.ins("test %eax, %eax");
+                    self.ins(&format!("jz {}", otherwise));
+                    self.jump(*block, *then_block, None);
+                    self.label(&otherwise);
+                    self.jump(*block, *else_block, next);
+                }
+                Terminator::Return(value) => {
+                    if let Some(value) = value {
+                        self.ins(&format!("mov {}, %rax", slot(*value)));
+                    }
+                    self.ins("leave");
+                    self.ins("ret");
+                }
+                Terminator::Unreachable => self.ins("ud2"),
+            }
+        }
+
+        let frame = (8 * function.values.len()).div_ceil(16) * 16;
+        let mut out = String::new();
+        let symbol = asm_name(&function.name);
+        let _ = writeln!(out, "\n    .globl {}\n{}:", symbol, symbol);
+        out.push_str("    push %rbp\n    mov %rsp, %rbp\n");
+        if frame > 0 {
+            let _ = writeln!(out, "    sub ${}, %rsp", frame);
+        }
+        out.push_str(&self.body);
+        out
+    }
+}
+
+/// Lowers an IR module to x86-64 assembly (GAS syntax).
+pub fn emit_asm(module: &Module) -> Result<String, Error> {
+    let main_ret = check_main(module)?;
+    check_bodies(module)?;
+    let mut strings = vec![];
+    let mut labels = 0;
+
+    let mut text = String::from("# Generated by rotor. Do not edit.\n    .text\n");
+    let _ = writeln!(text, "    .set rt_heap_size, {}", HEAP_SIZE);
+    text.push_str("\n    .globl _start\n_start:\n");
+    text.push_str("    mov (%rsp), %rax\n    mov %rax, rt_argc(%rip)\n    lea 8(%rsp), %rax\n    mov %rax, rt_argv(%rip)\n");
+    text.push_str("    xor %rbp, %rbp\n    call r_main\n");
+    if main_ret == Type::I32 {
+        text.push_str("    mov %eax, %edi\n");
+    } else {
+        text.push_str("    xor %edi, %edi\n");
+    }
+    text.push_str("    mov $60, %eax\n    syscall\n");
+
+    for (label, message) in PANICS {
+        let _ = write!(
+            text,
+            "\n{}:\n    mov $1, %eax\n    mov $2, %edi\n    lea {}_msg(%rip), %rsi\n    mov ${}, %edx\n    syscall\n    mov $60, %eax\n    mov $101, %edi\n    syscall\n",
+            label,
+            label,
+            message.len()
+        );
+    }
+    text.push_str(INTRINSICS);
+
+    for function in module.functions.iter().filter(|function| !function.is_external()) {
+        let mut emitter = AsmEmitter { function, body: String::new(), strings: &mut strings, labels: &mut labels };
+        text.push_str(&emitter.emit());
+    }
+
+    let mut data = String::from("\n    .data\n");
+    for global in &module.globals {
+        let value = const_operand(&global.init, &mut strings);
+        let _ = writeln!(data, "{}:\n    .quad {}", asm_global(&global.name), value);
+    }
+
+    let mut rodata = String::from("\n    .section .rodata\nrt_newline:\n    .byte 10\n");
+    for (label, message) in PANICS {
+        let _ = writeln!(rodata, "{}_msg:\n    .ascii {}", label, asm_string(message));
+    }
+    for (i, value) in strings.iter().enumerate() {
+        let _ = writeln!(rodata, ".Lstr{}:\n    .asciz {}", i, asm_string(value));
+    }
+
+    let bss = "\n    .bss\n    .balign 8\nrt_argc:\n    .zero 8\nrt_argv:\n    .zero 8\nrt_heap_used:\n    .zero 8\nrt_heap:\n    .zero rt_heap_size\n";
+
+    Ok(format!("{}{}{}{}", text, data, rodata, bss))
+}
+/// Assembles and links output from `emit_asm` into an executable at `output`.
+/// `AS` and `LD` override the assembler and linker.
+pub fn build_asm(asm: &str, output: &Path) -> Result<(), Error> {
+    let dir = BuildDir::new()?;
+    let source = dir.write("main.s", asm)?;
+    let object = dir.path().join("main.o");
+
+    let assembler = std::env::var("AS").unwrap_or_else(|_| "as".to_string());
+    run_tool(Command::new(assembler).arg("--64").arg("-o").arg(&object).arg(&source))?;
+
+    let linker = std::env::var("LD").unwrap_or_else(|_| "ld".to_string());
+    run_tool(Command::new(linker).arg("-o").arg(output).arg(&object))
+}
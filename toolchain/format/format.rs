@@ -0,0 +1,315 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The formatter reprints a parsed program in one canonical style: four
+// space indentation, one statement per line, a space around binary
+// operators (except `..`), braces on the same line, `else` cuddled
+// between braces and the names in a `use` list sorted. Parentheses are
+// only written where precedence needs them. Blank lines between
+// statements are kept, but never more than one in a row, and top-level
+// functions with a body always get one around them.
+//
+// The parse tree has no comments, so they are put back by position: a
+// comment is printed before the first statement (or closing brace) that
+// comes after it, and a comment on the same line as the end of a
+// statement stays at the end of that line. Comments in the middle of a
+// statement end up on their own line above it.
+use std::collections::HashMap;
+use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::{lex, Comment, TokenKind};
+use crate::parser::nodes::{Block, Expr, Program, Stmt, UseImports};
+use crate::parser::{parse_program_recovering, TokenStream};
+
+const INDENT: &str = "    ";
+
+struct Printer<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    // The next comment to print
+    next: usize,
+    // Offsets of every `{`, and the offset of its matching `}`
+    opens: Vec<usize>,
+    closes: HashMap<usize, usize>,
+    // Where the source printed so far ends
+    last_end: usize,
+    // Where the block being printed ends
+    limit: usize,
+    depth: usize,
+    out: String,
+}
+
+// Same precedence as the parser; higher binds tighter
+fn binding_power(op: TokenKind) -> u8 {
+    match op {
+        TokenKind::Or => 1,
+        TokenKind::And => 2,
+        TokenKind::EqualEqual | TokenKind::NotEqual => 3,
+        TokenKind::LessThan
+        | TokenKind::GreaterThan
+        | TokenKind::LessThanOrEqual
+        | TokenKind::GreaterThanOrEqual => 4,
+        TokenKind::Range => 5,
+        TokenKind::Plus | TokenKind::Line => 6,
+        _ => 7,
+    }
+}
+
+impl Printer<'_> {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn blank_line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("{\n") && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Whether the source has an empty line between what was printed last and `offset`.
+    fn blank_before(&self, offset: usize) -> bool {
+        self.last_end < offset && self.source[self.last_end..offset].matches('\n').count() >= 2
+    }
+
+    /// Prints the comments that start before `offset`, each on its own line.
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next).filter(|comment| comment.span.start < offset).cloned() {
+            if self.blank_before(comment.span.start) {
+                self.blank_line();
+            }
+            let mut lines = comment.text.lines();
+            self.line(lines.next().unwrap_or_default().trim_end());
+            // Later lines of a block comment are kept exactly as written
+            for rest in lines {
+                self.out.push_str(rest.trim_end());
+                self.out.push('\n');
+            }
+            self.last_end = comment.span.end;
+            self.next += 1;
+        }
+    }
+
+    /// Appends a comment that starts on the same line as `end` to the last printed line.
+    fn trailing_comment(&mut self, end: usize) {
+        let Some(comment) = self.comments.get(self.next) else { return };
+        let between = &self.source[end.min(comment.span.start)..comment.span.start];
+        if comment.span.start < end || comment.span.start >= self.limit || between.contains('\n') || comment.text.contains('\n') {
+            return;
+        }
+        self.out.pop();
+        self.out.push(' ');
+        self.out.push_str(comment.text.trim_end());
+        self.out.push('\n');
+        self.last_end = comment.span.end;
+        self.next += 1;
+    }
+
+    /// The offsets of the first `{` at or after `offset` and of its `}`.
+    fn braces_after(&self, offset: usize) -> (usize, usize) {
+        let index = self.opens.partition_point(|&open| open < offset);
+        let open = self.opens.get(index).copied().unwrap_or(self.source.len());
+        (open, self.closes.get(&open).copied().unwrap_or(self.source.len()))
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt], top_level: bool) {
+        let mut spaced = false;
+        for stmt in stmts {
+            let span = stmt.span();
+            let function = top_level && matches!(stmt, Stmt::FnDecl { body: Some(_), .. });
+            let first_start = self.comments.get(self.next).map_or(span.start, |comment| comment.span.start.min(span.start));
+            if function || spaced || self.blank_before(first_start) {
+                self.blank_line();
+            }
+            spaced = function;
+            self.comments_before(span.start);
+            if self.blank_before(span.start) {
+                self.blank_line();
+            }
+            self.stmt(stmt);
+            self.last_end = self.last_end.max(span.end);
+            self.trailing_comment(span.end);
+        }
+    }
+
+    /// Prints a block whose `{` is the first one at or after `offset`,
+    /// finishing the current line with ` {`. Returns where the block ends.
+    fn block(&mut self, block: &Block, offset: usize) -> usize {
+        let (open, close) = self.braces_after(offset);
+        self.out.pop();
+        let pending = self.comments.get(self.next).is_some_and(|comment| comment.span.start < close);
+        if block.is_empty() && !pending {
+            self.out.push_str(" {}\n");
+            return close + 1;
+        }
+        self.out.push_str(" {\n");
+        self.last_end = open + 1;
+        let outer = std::mem::replace(&mut self.limit, close);
+        self.trailing_comment(open + 1);
+        self.depth += 1;
+        self.stmts(block, false);
+        self.comments_before(close);
+        self.depth -= 1;
+        self.limit = outer;
+        self.line("}");
+        self.last_end = close + 1;
+        close + 1
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, ty, value, constant, .. } => {
+                let keyword = if *constant { "const" } else { "let" };
+                let ty = ty.map_or(String::new(), |ty| format!(": {}", ty));
+                self.line(&format!("{} {}{} = {};", keyword, name, ty, expr(value)));
+            }
+            Stmt::UseStmt { stator, imports, .. } => {
+                let list = match imports {
+                    UseImports::Wildcard => "*".to_string(),
+                    UseImports::List(names) => {
+                        let mut names = names.clone();
+                        names.sort();
+                        names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+                    }
+                };
+                self.line(&format!("use {} [{}]", stator, list));
+            }
+            Stmt::FnDecl { name, params, return_ty, body, public, span } => {
+                let params: Vec<String> = params.iter().map(|param| format!("{}: {}", param.name, param.ty)).collect();
+                let ret = return_ty.map_or(String::new(), |ty| format!(" {}", ty));
+                let head = format!("{}fn {}({}){}", if *public { "pub " } else { "" }, name, params.join(", "), ret);
+                match body {
+                    Some(body) => {
+                        self.line(&head);
+                        self.block(body, span.start);
+                    }
+                    None => self.line(&format!("{};", head)),
+                }
+            }
+            Stmt::IfStmt { .. } => {
+                self.line("");
+                self.if_chain(stmt);
+            }
+            Stmt::ForStmt { variable, iterable, body, .. } => {
+                self.line(&format!("for {} in {}", variable, expr(iterable)));
+                self.block(body, iterable.span().end);
+            }
+            Stmt::WhileStmt { condition, body, .. } => {
+                self.line(&format!("while {}", expr(condition)));
+                self.block(body, condition.span().end);
+            }
+            Stmt::AssignStmt { name, value, .. } => self.line(&format!("{} = {};", name, expr(value))),
+            Stmt::ReturnStmt { value: Some(value), .. } => self.line(&format!("return {};", expr(value))),
+            Stmt::ReturnStmt { value: None, .. } => self.line("return;"),
+            Stmt::CallStmt(call) => self.line(&format!("{};", expr(call))),
+        }
+    }
+
+    /// Prints an `if` and its `else` branches onto the current line, which
+    /// has already been started.
+    fn if_chain(&mut self, stmt: &Stmt) {
+        let Stmt::IfStmt { condition, then_branch, else_branch, .. } = stmt else { return };
+        self.out.pop();
+        self.out.push_str(&format!("if {}\n", expr(condition)));
+        let end = self.block(then_branch, condition.span().end);
+        let Some(else_branch) = else_branch else { return };
+        self.out.pop();
+        // `else if` is stored as an else block holding only the next `if`
+        let (open, _) = self.braces_after(end);
+        match else_branch.as_slice() {
+            [next @ Stmt::IfStmt { span, .. }] if span.start < open => {
+                self.out.push_str(" else \n");
+                self.if_chain(next);
+            }
+            _ => {
+                self.out.push_str(" else\n");
+                self.block(else_branch, end);
+            }
+        }
+    }
+}
+
+fn operand(child: &Expr, parent: u8, right: bool) -> String {
+    match child {
+        Expr::BinaryOp { op, .. } if binding_power(*op) < parent || (right && binding_power(*op) == parent) => {
+            format!("({})", expr(child))
+        }
+        _ => expr(child),
+    }
+}
+
+fn list(exprs: &[Expr]) -> String {
+    exprs.iter().map(expr).collect::<Vec<_>>().join(", ")
+}
+
+fn expr(expr_: &Expr) -> String {
+    match expr_ {
+        Expr::Literal { kind: TokenKind::String, value, .. } => format!("\"{}\"", value),
+        Expr::Literal { value, .. } => value.clone(),
+        Expr::Variable { name, .. } => name.to_string(),
+        Expr::BinaryOp { left, op: TokenKind::Range, right, .. } => {
+            let power = binding_power(TokenKind::Range);
+            format!("{}..{}", operand(left, power, false), operand(right, power, true))
+        }
+        Expr::BinaryOp { left, op, right, .. } => {
+            let power = binding_power(*op);
+            format!("{} {} {}", operand(left, power, false), op, operand(right, power, true))
+        }
+        Expr::UnaryOp { op, expr: inner, .. } => match inner.as_ref() {
+            Expr::BinaryOp { .. } => format!("{}({})", op, expr(inner)),
+            _ => format!("{}{}", op, expr(inner)),
+        },
+        Expr::FnCall { name, args, .. } => format!("{}({})", name, list(args)),
+        Expr::Array { elements, .. } => format!("[{}]", list(elements)),
+    }
+}
+
+/// Formats a whole source file. Fails with the lexer and parser errors
+/// if the source doesn't parse, since there's nothing to reprint then.
+pub fn format_source(source: &str) -> Result<String, Vec<Error>> {
+    let lexed = lex(source);
+    if !lexed.errors.is_empty() {
+        return Err(lexed.errors.into_iter().map(|error| Error::new(ErrorKind::InvalidToken, error, 0, 0)).collect());
+    }
+
+    let mut opens = vec![];
+    let mut closes = HashMap::new();
+    let mut stack = vec![];
+    for token in &lexed.tokens {
+        match token.kind {
+            TokenKind::LCurly => {
+                opens.push(token.span().start);
+                stack.push(token.span().start);
+            }
+            TokenKind::RCurly => {
+                if let Some(open) = stack.pop() {
+                    closes.insert(open, token.span().start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+    let (program, errors): (Program, Vec<Error>) = parse_program_recovering(&mut stream);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut printer = Printer {
+        source,
+        comments: lexed.comments,
+        next: 0,
+        opens,
+        closes,
+        last_end: 0,
+        limit: source.len(),
+        depth: 0,
+        out: String::new(),
+    };
+    printer.stmts(&program, true);
+    printer.comments_before(source.len());
+    Ok(printer.out)
+}
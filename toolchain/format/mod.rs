@@ -0,0 +1,2 @@
+pub mod format;
+pub use format::format_source;
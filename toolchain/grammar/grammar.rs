@@ -0,0 +1,136 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Editor grammars, generated from the lexer's own tables so highlighting
+// never drifts from what `lex` accepts. Every keyword and symbol comes from
+// `KEYWORDS` and `SYMBOLS`, and `scope` matches on every `TokenKind`, so a
+// new token doesn't build until it has been given a scope.
+//
+// The TextMate grammar is what VS Code and most other editors load for
+// highlighting before (or instead of) a language server.
+use crate::lexer::{TokenKind, KEYWORDS, SYMBOLS};
+use crate::lsp::Json;
+
+/// The TextMate scope of a keyword or symbol. `None` for tokens that have
+/// no fixed spelling; the grammar matches those with their own patterns.
+pub fn scope(kind: TokenKind) -> Option<&'static str> {
+    let scope = match kind {
+        TokenKind::Let | TokenKind::Const | TokenKind::Fn => "storage.type.rotor",
+        TokenKind::Pub => "storage.modifier.rotor",
+        TokenKind::Use => "keyword.other.import.rotor",
+        TokenKind::If | TokenKind::Else => "keyword.control.conditional.rotor",
+        TokenKind::For | TokenKind::While | TokenKind::In => "keyword.control.loop.rotor",
+        TokenKind::Return => "keyword.control.flow.rotor",
+        // `f32` lexes as a float for now
+        TokenKind::I32 | TokenKind::BOOL | TokenKind::STR | TokenKind::Float => "support.type.primitive.rotor",
+        TokenKind::Boolean => "constant.language.boolean.rotor",
+
+        TokenKind::Identifier | TokenKind::Integer | TokenKind::String | TokenKind::Newline | TokenKind::Eof => return None,
+
+        TokenKind::Dot => "punctuation.accessor.rotor",
+        TokenKind::Range => "keyword.operator.range.rotor",
+        TokenKind::Equal => "keyword.operator.assignment.rotor",
+        TokenKind::Semicolon => "punctuation.terminator.rotor",
+        TokenKind::Colon => "punctuation.separator.type.rotor",
+        TokenKind::Comma => "punctuation.separator.comma.rotor",
+        TokenKind::LParen | TokenKind::RParen => "punctuation.brackets.round.rotor",
+        TokenKind::LCurly | TokenKind::RCurly => "punctuation.brackets.curly.rotor",
+        TokenKind::LSquare | TokenKind::RSquare => "punctuation.brackets.square.rotor",
+        TokenKind::Plus | TokenKind::Line | TokenKind::Star | TokenKind::Slash | TokenKind::Modulus => {
+            "keyword.operator.arithmetic.rotor"
+        }
+        TokenKind::And | TokenKind::Or | TokenKind::Not => "keyword.operator.logical.rotor",
+        TokenKind::GreaterThan
+        | TokenKind::LessThan
+        | TokenKind::GreaterThanOrEqual
+        | TokenKind::LessThanOrEqual
+        | TokenKind::EqualEqual
+        | TokenKind::NotEqual => "keyword.operator.comparison.rotor",
+    };
+    Some(scope)
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if "\\^$.|?*+()[]{}".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// One pattern per scope, matching every spelling in `table` with that
+/// scope. Order is kept, so longer symbols still come before their prefixes.
+fn grouped(table: &[(&str, TokenKind)], words: bool) -> Vec<Json> {
+    let mut groups: Vec<(&str, Vec<String>)> = vec![];
+    for (text, kind) in table {
+        let Some(scope) = scope(*kind) else { continue };
+        let text = escape_regex(text);
+        match groups.iter_mut().find(|(name, _)| *name == scope) {
+            Some((_, spellings)) => spellings.push(text),
+            None => groups.push((scope, vec![text])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(scope, spellings)| {
+            let alternatives = spellings.join("|");
+            let pattern = if words { format!("\\b(?:{})\\b", alternatives) } else { format!("(?:{})", alternatives) };
+            Json::object([("name", scope.into()), ("match", pattern.into())])
+        })
+        .collect()
+}
+
+fn include(name: &str) -> Json {
+    Json::object([("include", format!("#{}", name).into())])
+}
+
+/// The TextMate grammar for `.rt` files, as used by VS Code.
+pub fn textmate_grammar() -> Json {
+    let comments = vec![
+        Json::object([("name", "comment.line.double-slash.rotor".into()), ("match", "//.*$".into())]),
+        Json::object([("name", "comment.block.rotor".into()), ("begin", "/\\*".into()), ("end", "\\*/".into())]),
+    ];
+    let strings = Json::object([("name", "string.quoted.double.rotor".into()), ("begin", "\"".into()), ("end", "\"".into())]);
+    // Only a dot followed by a digit makes a float, as in the lexer
+    let numbers = Json::object([("name", "constant.numeric.rotor".into()), ("match", "\\b[0-9]+(?:\\.[0-9]+)?\\b".into())]);
+    let declarations = Json::object([
+        ("match", "\\b(fn)\\s+([A-Za-z_][A-Za-z0-9_]*)".into()),
+        ("captures", Json::object([
+            ("1", Json::object([("name", "storage.type.rotor".into())])),
+            ("2", Json::object([("name", "entity.name.function.rotor".into())])),
+        ])),
+    ]);
+    let calls = Json::object([
+        ("match", "\\b([A-Za-z_][A-Za-z0-9_]*)\\s*(?=\\()".into()),
+        ("captures", Json::object([("1", Json::object([("name", "entity.name.function.call.rotor".into())]))])),
+    ]);
+
+    Json::object([
+        ("$schema", "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json".into()),
+        ("name", "Rotor".into()),
+        ("scopeName", "source.rotor".into()),
+        ("fileTypes", vec![Json::from("rt")].into()),
+        ("patterns", vec![
+            include("comments"),
+            include("strings"),
+            include("numbers"),
+            // Before keywords for `fn`, and calls after them for `if (..)`
+            include("declarations"),
+            include("keywords"),
+            include("calls"),
+            include("symbols"),
+        ].into()),
+        ("repository", Json::object([
+            ("comments", Json::object([("patterns", comments.into())])),
+            ("strings", strings),
+            ("numbers", numbers),
+            ("declarations", declarations),
+            ("keywords", Json::object([("patterns", grouped(KEYWORDS, true).into())])),
+            ("calls", calls),
+            ("symbols", Json::object([("patterns", grouped(SYMBOLS, false).into())])),
+        ])),
+    ])
+}
@@ -0,0 +1,2 @@
+pub mod grammar;
+pub use grammar::{scope, textmate_grammar};
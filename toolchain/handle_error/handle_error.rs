@@ -1,5 +1,8 @@
 // Copyright (c) 2025, Rotor Language Project
 // All rights reserved.
+use std::fmt;
+use crate::lexer::Span;
+use crate::source_map::FileId;
 
 /// Represents the severity level of an error or log message.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -11,7 +14,7 @@ pub enum Level {
     Critical,
     Fatal,
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorKind {
     InvalidToken,
     UnknownIdentifier,
@@ -19,6 +22,24 @@ pub enum ErrorKind {
     UnexpectedEof,
     InvalidEscapeSequence,
     UnterminatedString,
+    TypeMismatch,
+    DuplicateDefinition,
+    ConstAssignment,
+    WrongArgumentCount,
+    MissingReturn,
+    Unsupported,
+    Backend,
+    InvalidIr,
+    UnknownStator,
+    UnknownImport,
+    PrivateItem,
+    ImportCycle,
+    Project,
+    InvalidManifest,
+    UnusedVariable,
+    UnreachableCode,
+    // A program failed while being evaluated, e.g. in the REPL
+    Runtime,
 }
 
 #[allow(dead_code)]
@@ -28,6 +49,8 @@ pub struct Error {
     pub(crate) message: String,
     pub(crate) line: usize,
     pub(crate) column: usize,
+    // The file in a `SourceMap` the error is about, when known
+    pub(crate) file: Option<FileId>,
 }
 
 impl Error {
@@ -37,10 +60,52 @@ impl Error {
             message: message.into(),
             line,
             column,
+            file: None,
         }
     }
 
+    /// An error about the code at `span`, in the span's file.
+    pub fn at(kind: ErrorKind, message: impl Into<String>, span: Span) -> Self {
+        Error::new(kind, message, span.line, span.column).in_file(span.file)
+    }
+
+    pub fn in_file(mut self, file: FileId) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// Pushes the error as it is displayed, with its position.
     pub fn push_new(&self, destination: &mut Vec<String>) {
-        destination.push(self.message.clone());
+        destination.push(self.to_string());
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn file(&self) -> Option<FileId> {
+        self.file
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Line 0 means the error has no position
+        match self.line {
+            0 => write!(f, "{}", self.message),
+            line => write!(f, "{}:{}: {}", line, self.column, self.message),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,498 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// A typed SSA intermediate representation sitting between the AST and
+// the backends. A function is a list of basic blocks; each block holds
+// phi nodes first, then ordinary instructions, and always ends in a
+// single terminator. Every value is defined exactly once.
+use std::fmt;
+use crate::typeck::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Const {
+    Int(i32),
+    Bool(bool),
+    Str(String),
+}
+
+impl Const {
+    pub fn ty(&self) -> Type {
+        match self {
+            Const::Int(_) => Type::I32,
+            Const::Bool(_) => Type::Bool,
+            Const::Str(_) => Type::Str,
+        }
+    }
+}
+
+impl fmt::Display for Const {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Const::Int(value) => write!(f, "{}", value),
+            Const::Bool(value) => write!(f, "{}", value),
+            Const::Str(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl BinOp {
+    pub fn is_comparison(self) -> bool {
+        !matches!(self, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BinOp::Add => "add",
+            BinOp::Sub => "sub",
+            BinOp::Mul => "mul",
+            BinOp::Div => "div",
+            BinOp::Rem => "rem",
+            BinOp::Eq => "eq",
+            BinOp::Ne => "ne",
+            BinOp::Lt => "lt",
+            BinOp::Gt => "gt",
+            BinOp::Le => "le",
+            BinOp::Ge => "ge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstKind {
+    Const(Const),
+    // The value of a variable read where it was never assigned, which
+    // can only happen in unreachable code
+    Undef,
+    Param(usize),
+    Copy(Value),
+    Binary(BinOp, Value, Value),
+    Unary(UnOp, Value),
+    Call(String, Vec<Value>),
+    // One incoming value per predecessor block
+    Phi(Vec<(BlockId, Value)>),
+    LoadGlobal(String),
+    StoreGlobal(String, Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inst {
+    // `None` for instructions that produce nothing (stores, void calls)
+    pub result: Option<Value>,
+    pub kind: InstKind,
+}
+
+impl Inst {
+    pub fn operands(&self) -> Vec<Value> {
+        match &self.kind {
+            InstKind::Const(_) | InstKind::Undef | InstKind::Param(_) | InstKind::LoadGlobal(_) => vec![],
+            InstKind::Copy(value) | InstKind::Unary(_, value) | InstKind::StoreGlobal(_, value) => vec![*value],
+            InstKind::Binary(_, left, right) => vec![*left, *right],
+            InstKind::Call(_, args) => args.clone(),
+            InstKind::Phi(incoming) => incoming.iter().map(|(_, value)| *value).collect(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match &mut self.kind {
+            InstKind::Const(_) | InstKind::Undef | InstKind::Param(_) | InstKind::LoadGlobal(_) => vec![],
+            InstKind::Copy(value) | InstKind::Unary(_, value) | InstKind::StoreGlobal(_, value) => vec![value],
+            InstKind::Binary(_, left, right) => vec![left, right],
+            InstKind::Call(_, args) => args.iter_mut().collect(),
+            InstKind::Phi(incoming) => incoming.iter_mut().map(|(_, value)| value).collect(),
+        }
+    }
+
+    pub fn is_phi(&self) -> bool {
+        matches!(self.kind, InstKind::Phi(_))
+    }
+
+    /// Whether removing the instruction could change what the program does.
+    pub fn has_side_effects(&self) -> bool {
+        // Division can panic, so it is only removable when the divisor is known
+        matches!(self.kind, InstKind::Call(..) | InstKind::StoreGlobal(..) | InstKind::Binary(BinOp::Div | BinOp::Rem, ..))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch(Value, BlockId, BlockId),
+    Return(Option<Value>),
+    Unreachable,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch(_, then_block, else_block) => vec![*then_block, *else_block],
+            Terminator::Return(_) | Terminator::Unreachable => vec![],
+        }
+    }
+
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Terminator::Branch(condition, ..) => vec![*condition],
+            Terminator::Return(Some(value)) => vec![*value],
+            _ => vec![],
+        }
+    }
+
+    pub fn targets_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch(_, then_block, else_block) => vec![then_block, else_block],
+            Terminator::Return(_) | Terminator::Unreachable => vec![],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Terminator::Branch(condition, ..) => vec![condition],
+            Terminator::Return(Some(value)) => vec![value],
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    pub term: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Type>,
+    pub ret: Type,
+    // Empty for functions declared without a body
+    pub blocks: Vec<Block>,
+    // The type of every value, indexed by `Value`
+    pub values: Vec<Type>,
+}
+
+impl Function {
+    pub fn is_external(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn entry(&self) -> BlockId {
+        BlockId(0)
+    }
+
+    pub fn block(&self, id: BlockId) -> &Block {
+        &self.blocks[id.0 as usize]
+    }
+
+    pub fn block_mut(&mut self, id: BlockId) -> &mut Block {
+        &mut self.blocks[id.0 as usize]
+    }
+
+    pub fn block_ids(&self) -> impl Iterator<Item = BlockId> {
+        (0..self.blocks.len() as u32).map(BlockId)
+    }
+
+    pub fn ty(&self, value: Value) -> Type {
+        self.values[value.0 as usize]
+    }
+
+    /// The predecessors of every block, indexed by block.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut preds = vec![vec![]; self.blocks.len()];
+        for id in self.block_ids() {
+            for succ in self.block(id).term.successors() {
+                if !preds[succ.0 as usize].contains(&id) {
+                    preds[succ.0 as usize].push(id);
+                }
+            }
+        }
+        preds
+    }
+
+    /// Blocks reachable from the entry, in reverse postorder.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        if self.is_external() {
+            return vec![];
+        }
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = vec![];
+        // (block, whether its successors were already pushed)
+        let mut stack = vec![(self.entry(), false)];
+        while let Some((block, expanded)) = stack.pop() {
+            if expanded {
+                order.push(block);
+                continue;
+            }
+            if visited[block.0 as usize] {
+                continue;
+            }
+            visited[block.0 as usize] = true;
+            stack.push((block, true));
+            for succ in self.block(block).term.successors().into_iter().rev() {
+                if !visited[succ.0 as usize] {
+                    stack.push((succ, false));
+                }
+            }
+        }
+        order.reverse();
+        order
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Global {
+    pub name: String,
+    pub ty: Type,
+    pub init: Const,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Module {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+impl Module {
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+/// Builds a function block by block. Instructions are appended to the
+/// current block, which starts out as the entry block.
+pub struct FunctionBuilder {
+    function: Function,
+    current: BlockId,
+}
+
+impl FunctionBuilder {
+    pub fn new(name: impl Into<String>, params: Vec<Type>, ret: Type) -> Self {
+        let mut builder = FunctionBuilder {
+            function: Function { name: name.into(), params, ret, blocks: vec![], values: vec![] },
+            current: BlockId(0),
+        };
+        builder.new_block();
+        builder
+    }
+
+    pub fn new_block(&mut self) -> BlockId {
+        self.function.blocks.push(Block { insts: vec![], term: Terminator::Unreachable });
+        BlockId(self.function.blocks.len() as u32 - 1)
+    }
+
+    pub fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    pub fn current(&self) -> BlockId {
+        self.current
+    }
+
+    pub fn function(&self) -> &Function {
+        &self.function
+    }
+
+    fn new_value(&mut self, ty: Type) -> Value {
+        self.function.values.push(ty);
+        Value(self.function.values.len() as u32 - 1)
+    }
+
+    /// Appends an instruction producing a value of type `ty` (nothing for void).
+    pub fn inst(&mut self, kind: InstKind, ty: Type) -> Option<Value> {
+        let result = if ty == Type::Void { None } else { Some(self.new_value(ty)) };
+        let block = self.current;
+        self.function.block_mut(block).insts.push(Inst { result, kind });
+        result
+    }
+
+    pub fn constant(&mut self, value: Const) -> Value {
+        let ty = value.ty();
+        self.inst(InstKind::Const(value), ty).unwrap()
+    }
+
+    pub fn param(&mut self, index: usize) -> Value {
+        let ty = self.function.params[index];
+        self.inst(InstKind::Param(index), ty).unwrap()
+    }
+
+    pub fn binary(&mut self, op: BinOp, left: Value, right: Value) -> Value {
+        let ty = if op.is_comparison() { Type::Bool } else { Type::I32 };
+        self.inst(InstKind::Binary(op, left, right), ty).unwrap()
+    }
+
+    pub fn unary(&mut self, op: UnOp, value: Value) -> Value {
+        let ty = if op == UnOp::Not { Type::Bool } else { Type::I32 };
+        self.inst(InstKind::Unary(op, value), ty).unwrap()
+    }
+
+    pub fn call(&mut self, name: impl Into<String>, args: Vec<Value>, ret: Type) -> Option<Value> {
+        self.inst(InstKind::Call(name.into(), args), ret)
+    }
+
+    pub fn load_global(&mut self, name: impl Into<String>, ty: Type) -> Value {
+        self.inst(InstKind::LoadGlobal(name.into()), ty).unwrap()
+    }
+
+    pub fn store_global(&mut self, name: impl Into<String>, value: Value) {
+        self.inst(InstKind::StoreGlobal(name.into(), value), Type::Void);
+    }
+
+    /// Adds a phi to the start of `block`. Incoming values can be added
+    /// later with `add_phi_incoming`, which allows phis for loops.
+    pub fn phi(&mut self, block: BlockId, ty: Type, incoming: Vec<(BlockId, Value)>) -> Value {
+        let result = self.new_value(ty);
+        let insts = &mut self.function.block_mut(block).insts;
+        let at = insts.iter().take_while(|inst| inst.is_phi()).count();
+        insts.insert(at, Inst { result: Some(result), kind: InstKind::Phi(incoming) });
+        result
+    }
+
+    pub fn add_phi_incoming(&mut self, phi: Value, from: BlockId, value: Value) {
+        for block in &mut self.function.blocks {
+            for inst in &mut block.insts {
+                if inst.result == Some(phi) {
+                    if let InstKind::Phi(incoming) = &mut inst.kind {
+                        incoming.push((from, value));
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.function.block(self.current).term != Terminator::Unreachable
+    }
+
+    fn terminate(&mut self, term: Terminator) {
+        let block = self.current;
+        self.function.block_mut(block).term = term;
+    }
+
+    pub fn jump(&mut self, target: BlockId) {
+        self.terminate(Terminator::Jump(target));
+    }
+
+    pub fn branch(&mut self, condition: Value, then_block: BlockId, else_block: BlockId) {
+        self.terminate(Terminator::Branch(condition, then_block, else_block));
+    }
+
+    pub fn ret(&mut self, value: Option<Value>) {
+        self.terminate(Terminator::Return(value));
+    }
+
+    pub fn finish(self) -> Function {
+        self.function
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|ty| ty.to_string()).collect();
+        write!(f, "fn @{}({}) -> {}", self.name, params.join(", "), self.ret)?;
+        if self.is_external() {
+            return writeln!(f);
+        }
+        writeln!(f, " {{")?;
+        for id in self.block_ids() {
+            let block = self.block(id);
+            writeln!(f, "{}:", id)?;
+            for inst in &block.insts {
+                write!(f, "    ")?;
+                if let Some(result) = inst.result {
+                    write!(f, "{}: {} = ", result, self.ty(result))?;
+                }
+                match &inst.kind {
+                    InstKind::Const(value) => write!(f, "const {}", value)?,
+                    InstKind::Undef => write!(f, "undef")?,
+                    InstKind::Param(index) => write!(f, "param {}", index)?,
+                    InstKind::Copy(value) => write!(f, "copy {}", value)?,
+                    InstKind::Binary(op, left, right) => write!(f, "{} {}, {}", op.name(), left, right)?,
+                    InstKind::Unary(op, value) => {
+                        let name = if *op == UnOp::Neg { "neg" } else { "not" };
+                        write!(f, "{} {}", name, value)?
+                    }
+                    InstKind::Call(name, args) => {
+                        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                        write!(f, "call @{}({})", name, args.join(", "))?
+                    }
+                    InstKind::Phi(incoming) => {
+                        let incoming: Vec<String> =
+                            incoming.iter().map(|(block, value)| format!("[{}: {}]", block, value)).collect();
+                        write!(f, "phi {}", incoming.join(", "))?
+                    }
+                    InstKind::LoadGlobal(name) => write!(f, "load @{}", name)?,
+                    InstKind::StoreGlobal(name, value) => write!(f, "store @{}, {}", name, value)?,
+                }
+                writeln!(f)?;
+            }
+            match &block.term {
+                Terminator::Jump(target) => writeln!(f, "    jmp {}", target)?,
+                Terminator::Branch(condition, then_block, else_block) => {
+                    writeln!(f, "    br {}, {}, {}", condition, then_block, else_block)?
+                }
+                Terminator::Return(Some(value)) => writeln!(f, "    ret {}", value)?,
+                Terminator::Return(None) => writeln!(f, "    ret")?,
+                Terminator::Unreachable => writeln!(f, "    unreachable")?,
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for global in &self.globals {
+            let keyword = if global.mutable { "global" } else { "const" };
+            writeln!(f, "{} @{}: {} = {}", keyword, global.name, global.ty, global.init)?;
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
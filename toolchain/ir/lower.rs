@@ -0,0 +1,376 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Lowers a type checked program to SSA form, following "Simple and
+// Efficient Construction of Static Single Assignment Form" (Braun et
+// al.): each variable's current value is tracked per block, and phis
+// are created on demand when a read reaches a join point. Blocks are
+// "sealed" once all their predecessors are known; reads in unsealed
+// blocks (loop headers) get a placeholder phi that is completed when
+// the block is sealed. Trivial phis are left for the optimizer.
+use std::collections::{HashMap, HashSet};
+use crate::ir::ir::{BinOp, BlockId, Const, Function, FunctionBuilder, Global, InstKind, Module, UnOp, Value};
+use crate::lexer::{Symbol, TokenKind};
+use crate::parser::nodes::{Block, Expr, Program, Stmt};
+use crate::typeck::{Checked, Type};
+
+// Every declaration gets its own id, so shadowed names stay apart
+type VarId = usize;
+
+struct Lowerer<'a> {
+    checked: &'a Checked,
+    builder: FunctionBuilder,
+    scopes: Vec<HashMap<Symbol, VarId>>,
+    var_types: Vec<Type>,
+    current_def: HashMap<(VarId, BlockId), Value>,
+    preds: HashMap<BlockId, Vec<BlockId>>,
+    sealed: HashSet<BlockId>,
+    // Placeholder phis in unsealed blocks, completed by `seal`
+    incomplete: HashMap<BlockId, Vec<(VarId, Value)>>,
+}
+
+fn const_of(kind: TokenKind, value: &str) -> Const {
+    match kind {
+        TokenKind::String => Const::Str(value.to_string()),
+        TokenKind::Boolean => Const::Bool(value == "true"),
+        _ => Const::Int(value.parse().unwrap()),
+    }
+}
+
+fn bin_op(op: TokenKind) -> BinOp {
+    match op {
+        TokenKind::Plus => BinOp::Add,
+        TokenKind::Line => BinOp::Sub,
+        TokenKind::Star => BinOp::Mul,
+        TokenKind::Slash => BinOp::Div,
+        TokenKind::Modulus => BinOp::Rem,
+        TokenKind::EqualEqual => BinOp::Eq,
+        TokenKind::NotEqual => BinOp::Ne,
+        TokenKind::LessThan => BinOp::Lt,
+        TokenKind::GreaterThan => BinOp::Gt,
+        TokenKind::LessThanOrEqual => BinOp::Le,
+        TokenKind::GreaterThanOrEqual => BinOp::Ge,
+        _ => unreachable!("{} is not a binary operator", op),
+    }
+}
+
+impl Lowerer<'_> {
+    fn declare(&mut self, name: Symbol, ty: Type) -> VarId {
+        self.var_types.push(ty);
+        let var = self.var_types.len() - 1;
+        self.scopes.last_mut().unwrap().insert(name, var);
+        var
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<VarId> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name).copied())
+    }
+
+    fn write_var(&mut self, var: VarId, block: BlockId, value: Value) {
+        self.current_def.insert((var, block), value);
+    }
+
+    fn read_var(&mut self, var: VarId, block: BlockId) -> Value {
+        if let Some(value) = self.current_def.get(&(var, block)) {
+            return *value;
+        }
+        let ty = self.var_types[var];
+        let preds = self.preds.get(&block).cloned().unwrap_or_default();
+
+        let value = if !self.sealed.contains(&block) {
+            let phi = self.builder.phi(block, ty, vec![]);
+            self.incomplete.entry(block).or_default().push((var, phi));
+            phi
+        } else if preds.len() == 1 {
+            self.read_var(var, preds[0])
+        } else if preds.is_empty() {
+            // Only reachable from dead code
+            let current = self.builder.current();
+            self.builder.switch_to(block);
+            let undef = self.builder.inst(InstKind::Undef, ty).unwrap();
+            self.builder.switch_to(current);
+            undef
+        } else {
+            let phi = self.builder.phi(block, ty, vec![]);
+            // Written before the operands are read, which breaks cycles
+            self.write_var(var, block, phi);
+            for pred in preds {
+                let incoming = self.read_var(var, pred);
+                self.builder.add_phi_incoming(phi, pred, incoming);
+            }
+            phi
+        };
+        self.write_var(var, block, value);
+        value
+    }
+
+    fn seal(&mut self, block: BlockId) {
+        for (var, phi) in self.incomplete.remove(&block).unwrap_or_default() {
+            for pred in self.preds.get(&block).cloned().unwrap_or_default() {
+                let incoming = self.read_var(var, pred);
+                self.builder.add_phi_incoming(phi, pred, incoming);
+            }
+        }
+        self.sealed.insert(block);
+    }
+
+    fn add_edge(&mut self, from: BlockId, to: BlockId) {
+        self.preds.entry(to).or_default().push(from);
+    }
+
+    fn jump(&mut self, target: BlockId) {
+        let current = self.builder.current();
+        self.add_edge(current, target);
+        self.builder.jump(target);
+    }
+
+    fn branch(&mut self, condition: Value, then_block: BlockId, else_block: BlockId) {
+        let current = self.builder.current();
+        self.add_edge(current, then_block);
+        self.add_edge(current, else_block);
+        self.builder.branch(condition, then_block, else_block);
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Literal { kind, value, .. } => Some(self.builder.constant(const_of(*kind, value))),
+            Expr::Variable { name, .. } => match self.lookup(*name) {
+                Some(var) => {
+                    let block = self.builder.current();
+                    Some(self.read_var(var, block))
+                }
+                None => Some(self.builder.load_global(name.as_str(), self.checked.globals[name])),
+            },
+            Expr::BinaryOp { left, op: op @ (TokenKind::And | TokenKind::Or), right, .. } => {
+                // Short circuit through control flow: when the left side
+                // decides the result, it is also the result
+                let left = self.expr(left)?;
+                let from = self.builder.current();
+                let (rhs, merge) = (self.builder.new_block(), self.builder.new_block());
+                if *op == TokenKind::And {
+                    self.branch(left, rhs, merge);
+                } else {
+                    self.branch(left, merge, rhs);
+                }
+                self.seal(rhs);
+                self.builder.switch_to(rhs);
+                let right = self.expr(right)?;
+                let rhs_end = self.builder.current();
+                self.jump(merge);
+                self.seal(merge);
+                self.builder.switch_to(merge);
+                Some(self.builder.phi(merge, Type::Bool, vec![(from, left), (rhs_end, right)]))
+            }
+            Expr::BinaryOp { left, op, right, .. } => {
+                let left = self.expr(left)?;
+                let right = self.expr(right)?;
+                Some(self.builder.binary(bin_op(*op), left, right))
+            }
+            Expr::UnaryOp { op, expr, .. } => {
+                let value = self.expr(expr)?;
+                let op = if *op == TokenKind::Not { UnOp::Not } else { UnOp::Neg };
+                Some(self.builder.unary(op, value))
+            }
+            Expr::FnCall { name, args, .. } => {
+                let args = args.iter().map(|arg| self.expr(arg).unwrap()).collect();
+                let ret = self.checked.functions[name].ret;
+                self.builder.call(name.as_str(), args, ret)
+            }
+            Expr::Array { .. } => unreachable!("arrays are rejected by the type checker"),
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in block {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, value, .. } => {
+                let value = self.expr(value).unwrap();
+                let ty = self.builder.function().ty(value);
+                let var = self.declare(*name, ty);
+                let block = self.builder.current();
+                self.write_var(var, block, value);
+            }
+            Stmt::AssignStmt { name, value, .. } => {
+                let value = self.expr(value).unwrap();
+                match self.lookup(*name) {
+                    Some(var) => {
+                        let block = self.builder.current();
+                        self.write_var(var, block, value);
+                    }
+                    None => self.builder.store_global(name.as_str(), value),
+                }
+            }
+            Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+                let condition = self.expr(condition).unwrap();
+                let then_block = self.builder.new_block();
+                let merge = self.builder.new_block();
+                let else_block = if else_branch.is_some() { self.builder.new_block() } else { merge };
+                self.branch(condition, then_block, else_block);
+
+                self.seal(then_block);
+                self.builder.switch_to(then_block);
+                self.block(then_branch);
+                if !self.builder.is_terminated() {
+                    self.jump(merge);
+                }
+
+                if let Some(else_branch) = else_branch {
+                    self.seal(else_block);
+                    self.builder.switch_to(else_block);
+                    self.block(else_branch);
+                    if !self.builder.is_terminated() {
+                        self.jump(merge);
+                    }
+                }
+
+                self.seal(merge);
+                self.builder.switch_to(merge);
+            }
+            Stmt::WhileStmt { condition, body, .. } => {
+                let header = self.builder.new_block();
+                self.jump(header);
+                // The header stays unsealed until the back edge exists
+                self.builder.switch_to(header);
+                let condition = self.expr(condition).unwrap();
+                let (body_block, exit) = (self.builder.new_block(), self.builder.new_block());
+                self.branch(condition, body_block, exit);
+
+                self.seal(body_block);
+                self.builder.switch_to(body_block);
+                self.block(body);
+                if !self.builder.is_terminated() {
+                    self.jump(header);
+                }
+                self.seal(header);
+
+                self.seal(exit);
+                self.builder.switch_to(exit);
+            }
+            Stmt::ForStmt { variable, iterable, body, .. } => {
+                let Expr::BinaryOp { left, right, .. } = iterable.as_ref() else {
+                    unreachable!("only ranges pass the type checker");
+                };
+                // The end of the range is evaluated once, before the first iteration
+                let start = self.expr(left).unwrap();
+                let end = self.expr(right).unwrap();
+                self.scopes.push(HashMap::new());
+                let var = self.declare(*variable, Type::I32);
+                let block = self.builder.current();
+                self.write_var(var, block, start);
+
+                let header = self.builder.new_block();
+                self.jump(header);
+                self.builder.switch_to(header);
+                let current = self.read_var(var, header);
+                let in_range = self.builder.binary(BinOp::Lt, current, end);
+                let (body_block, exit) = (self.builder.new_block(), self.builder.new_block());
+                self.branch(in_range, body_block, exit);
+
+                self.seal(body_block);
+                self.builder.switch_to(body_block);
+                self.block(body);
+                if !self.builder.is_terminated() {
+                    let block = self.builder.current();
+                    let current = self.read_var(var, block);
+                    let one = self.builder.constant(Const::Int(1));
+                    let next = self.builder.binary(BinOp::Add, current, one);
+                    self.write_var(var, block, next);
+                    self.jump(header);
+                }
+                self.seal(header);
+                self.scopes.pop();
+
+                self.seal(exit);
+                self.builder.switch_to(exit);
+            }
+            Stmt::ReturnStmt { value, .. } => {
+                let value = value.as_ref().and_then(|value| self.expr(value));
+                self.builder.ret(value);
+                // Anything after a return goes into a block nothing jumps to
+                let dead = self.builder.new_block();
+                self.seal(dead);
+                self.builder.switch_to(dead);
+            }
+            Stmt::CallStmt(call) => {
+                self.expr(call);
+            }
+            // Only allowed at the top level, which is handled by `lower_program`
+            Stmt::FnDecl { .. } | Stmt::UseStmt { .. } => {}
+        }
+    }
+}
+
+fn lower_function(checked: &Checked, name: Symbol, params: &[Symbol], body: &Block) -> Function {
+    let sig = &checked.functions[&name];
+    let mut lowerer = Lowerer {
+        checked,
+        builder: FunctionBuilder::new(name.as_str(), sig.params.clone(), sig.ret),
+        scopes: vec![HashMap::new()],
+        var_types: vec![],
+        current_def: HashMap::new(),
+        preds: HashMap::new(),
+        sealed: HashSet::new(),
+        incomplete: HashMap::new(),
+    };
+    let entry = lowerer.builder.current();
+    lowerer.seal(entry);
+    for (i, param) in params.iter().enumerate() {
+        let value = lowerer.builder.param(i);
+        let var = lowerer.declare(*param, sig.params[i]);
+        lowerer.write_var(var, entry, value);
+    }
+
+    lowerer.block(body);
+    if !lowerer.builder.is_terminated() && sig.ret == Type::Void {
+        lowerer.builder.ret(None);
+    }
+    // Non-void functions return on every path, so falling off the end
+    // keeps the default `unreachable` terminator
+    lowerer.builder.finish()
+}
+
+/// Lowers a type checked program to an IR module.
+pub fn lower_program(program: &Program, checked: &Checked) -> Module {
+    let mut module = Module::default();
+    for stmt in program {
+        match stmt {
+            Stmt::LetStmt { name, value, constant, .. } => {
+                let Expr::Literal { kind, value, .. } = value.as_ref() else {
+                    unreachable!("top-level values must be literals");
+                };
+                module.globals.push(Global {
+                    name: name.to_string(),
+                    ty: checked.globals[name],
+                    init: const_of(*kind, value),
+                    mutable: !constant,
+                });
+            }
+            Stmt::FnDecl { name, params, body, .. } => {
+                let params: Vec<Symbol> = params.iter().map(|param| param.name).collect();
+                let function = match body {
+                    Some(body) => lower_function(checked, *name, &params, body),
+                    None => {
+                        let sig = &checked.functions[name];
+                        Function {
+                            name: name.to_string(),
+                            params: sig.params.clone(),
+                            ret: sig.ret,
+                            blocks: vec![],
+                            values: vec![],
+                        }
+                    }
+                };
+                module.functions.push(function);
+            }
+            _ => {}
+        }
+    }
+    module
+}
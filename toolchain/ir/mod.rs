@@ -0,0 +1,6 @@
+pub mod ir;
+pub mod lower;
+pub mod verify;
+pub use ir::{Value, BlockId, Const, BinOp, UnOp, InstKind, Inst, Terminator, Block, Function, Global, Module, FunctionBuilder};
+pub use lower::lower_program;
+pub use verify::{verify, dominators};
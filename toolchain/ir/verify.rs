@@ -0,0 +1,297 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Checks the invariants every pass may rely on: values are defined once
+// and before they are used (definitions dominate uses), phis sit at the
+// start of their block with one entry per predecessor, and every
+// instruction is applied to operands of the right types. Blocks that
+// cannot be reached from the entry are only checked structurally.
+use std::collections::HashMap;
+use crate::handle_error::{Error, ErrorKind};
+use crate::ir::ir::{BinOp, BlockId, Function, InstKind, Module, Terminator, UnOp, Value};
+use crate::typeck::{FnSig, Type};
+
+/// The immediate dominator of every reachable block, indexed by block.
+/// The entry is its own dominator; unreachable blocks have none.
+pub fn dominators(function: &Function) -> Vec<Option<BlockId>> {
+    let mut idom = vec![None; function.blocks.len()];
+    if function.is_external() {
+        return idom;
+    }
+    // Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm"
+    let order = function.reverse_postorder();
+    let mut rank = vec![usize::MAX; function.blocks.len()];
+    for (i, block) in order.iter().enumerate() {
+        rank[block.0 as usize] = i;
+    }
+    let preds = function.predecessors();
+    idom[function.entry().0 as usize] = Some(function.entry());
+
+    let intersect = |idom: &[Option<BlockId>], mut a: BlockId, mut b: BlockId| {
+        while a != b {
+            while rank[a.0 as usize] > rank[b.0 as usize] {
+                a = idom[a.0 as usize].unwrap();
+            }
+            while rank[b.0 as usize] > rank[a.0 as usize] {
+                b = idom[b.0 as usize].unwrap();
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in order.iter().skip(1) {
+            let mut new_idom = None;
+            for pred in &preds[block.0 as usize] {
+                if idom[pred.0 as usize].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => *pred,
+                    Some(current) => intersect(&idom, *pred, current),
+                });
+            }
+            if idom[block.0 as usize] != new_idom {
+                idom[block.0 as usize] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idom
+}
+
+fn dominates(idom: &[Option<BlockId>], a: BlockId, mut b: BlockId) -> bool {
+    loop {
+        if a == b {
+            return true;
+        }
+        match idom[b.0 as usize] {
+            Some(parent) if parent != b => b = parent,
+            _ => return false,
+        }
+    }
+}
+
+struct Verifier<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    errors: Vec<Error>,
+}
+
+impl Verifier<'_> {
+    fn error(&mut self, message: String) {
+        let message = format!("in @{}: {}", self.function.name, message);
+        self.errors.push(Error::new(ErrorKind::InvalidIr, message, 0, 0));
+    }
+
+    fn expect(&mut self, value: Value, expected: Type, what: &str) {
+        let found = self.function.ty(value);
+        if found != expected {
+            self.error(format!("{} {} must be {}, found {}", what, value, expected, found));
+        }
+    }
+
+    fn signature(&self, name: &str) -> Option<FnSig> {
+        let function = self.module.function(name)?;
+        Some(FnSig { params: function.params.clone(), ret: function.ret })
+    }
+
+    fn check_types(&mut self, result: Option<Value>, kind: &InstKind) {
+        let function = self.function;
+        let result_ty = result.map(|value| function.ty(value)).unwrap_or(Type::Void);
+        let produces = match kind {
+            InstKind::Const(value) => Some(value.ty()),
+            InstKind::Undef => Some(result_ty),
+            InstKind::Param(index) => match function.params.get(*index) {
+                Some(ty) => Some(*ty),
+                None => {
+                    self.error(format!("parameter {} out of range", index));
+                    return;
+                }
+            },
+            InstKind::Copy(value) => Some(function.ty(*value)),
+            InstKind::Binary(op, left, right) => match op {
+                BinOp::Eq | BinOp::Ne => {
+                    if function.ty(*left) != function.ty(*right) {
+                        self.error(format!("{} compares {} with {}", op.name(), function.ty(*left), function.ty(*right)));
+                    }
+                    Some(Type::Bool)
+                }
+                _ => {
+                    self.expect(*left, Type::I32, "operand");
+                    self.expect(*right, Type::I32, "operand");
+                    Some(if op.is_comparison() { Type::Bool } else { Type::I32 })
+                }
+            },
+            InstKind::Unary(op, value) => {
+                let ty = if *op == UnOp::Not { Type::Bool } else { Type::I32 };
+                self.expect(*value, ty, "operand");
+                Some(ty)
+            }
+            InstKind::Call(name, args) => {
+                let Some(sig) = self.signature(name) else {
+                    self.error(format!("call to unknown function @{}", name));
+                    return;
+                };
+                if sig.params.len() != args.len() {
+                    self.error(format!("@{} takes {} arguments, given {}", name, sig.params.len(), args.len()));
+                } else {
+                    for (arg, ty) in args.iter().zip(&sig.params) {
+                        self.expect(*arg, *ty, "argument");
+                    }
+                }
+                Some(sig.ret)
+            }
+            InstKind::Phi(incoming) => {
+                for (_, value) in incoming {
+                    self.expect(*value, result_ty, "incoming value");
+                }
+                Some(result_ty)
+            }
+            InstKind::LoadGlobal(name) | InstKind::StoreGlobal(name, _) => {
+                let Some(global) = self.module.globals.iter().find(|global| &global.name == name) else {
+                    self.error(format!("unknown global @{}", name));
+                    return;
+                };
+                if let InstKind::StoreGlobal(_, value) = kind {
+                    if !global.mutable {
+                        self.error(format!("store to constant @{}", name));
+                    }
+                    self.expect(*value, global.ty, "stored value");
+                    Some(Type::Void)
+                } else {
+                    Some(global.ty)
+                }
+            }
+        };
+        if let Some(ty) = produces {
+            if ty != result_ty {
+                self.error(format!("instruction produces {}, but its result is {}", ty, result_ty));
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        let function = self.function;
+        let count = function.blocks.len() as u32;
+
+        // Where every value is defined: (block, index), terminators use usize::MAX
+        let mut defs: HashMap<Value, (BlockId, usize)> = HashMap::new();
+        for block in function.block_ids() {
+            for (i, inst) in function.block(block).insts.iter().enumerate() {
+                let Some(result) = inst.result else { continue };
+                if result.0 as usize >= function.values.len() {
+                    self.error(format!("{} has no type", result));
+                } else if defs.insert(result, (block, i)).is_some() {
+                    self.error(format!("{} is defined more than once", result));
+                }
+            }
+            for succ in function.block(block).term.successors() {
+                if succ.0 >= count {
+                    self.error(format!("{} jumps to missing block {}", block, succ));
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            return;
+        }
+
+        let preds = function.predecessors();
+        let idom = dominators(function);
+        let reachable = |block: BlockId| idom[block.0 as usize].is_some();
+
+        for block in function.block_ids() {
+            let data = function.block(block);
+            let mut seen_other = false;
+            for (i, inst) in data.insts.iter().enumerate() {
+                if let InstKind::Phi(incoming) = &inst.kind {
+                    if seen_other {
+                        self.error(format!("phi in {} follows other instructions", block));
+                    }
+                    let mut from: Vec<BlockId> = incoming.iter().map(|(pred, _)| *pred).collect();
+                    from.sort();
+                    let mut expected = preds[block.0 as usize].clone();
+                    expected.sort();
+                    if from != expected {
+                        self.error(format!("phi in {} does not have one entry per predecessor", block));
+                    }
+                } else {
+                    seen_other = true;
+                }
+
+                let operands = inst.operands();
+                if let Some(value) = operands.iter().find(|value| !defs.contains_key(value)) {
+                    self.error(format!("{} is used but never defined", value));
+                    continue;
+                }
+                self.check_types(inst.result, &inst.kind);
+                if !reachable(block) {
+                    continue;
+                }
+
+                // A phi operand only has to be available at the end of its predecessor
+                if let InstKind::Phi(incoming) = &inst.kind {
+                    for (pred, value) in incoming {
+                        let (def_block, _) = defs[value];
+                        if reachable(*pred) && !dominates(&idom, def_block, *pred) {
+                            self.error(format!("{} does not dominate its use in {}", value, block));
+                        }
+                    }
+                } else {
+                    for value in operands {
+                        let (def_block, def_index) = defs[&value];
+                        let available = if def_block == block { def_index < i } else { dominates(&idom, def_block, block) };
+                        if !available {
+                            self.error(format!("{} does not dominate its use in {}", value, block));
+                        }
+                    }
+                }
+            }
+
+            let used = match &data.term {
+                Terminator::Branch(condition, ..) => Some(*condition),
+                Terminator::Return(value) => *value,
+                _ => None,
+            };
+            if let Some(value) = used {
+                match defs.get(&value) {
+                    None => self.error(format!("{} is used but never defined", value)),
+                    Some((def_block, _)) => {
+                        if reachable(block) && !dominates(&idom, *def_block, block) {
+                            self.error(format!("{} does not dominate its use in {}", value, block));
+                        }
+                    }
+                }
+            }
+            match &data.term {
+                Terminator::Branch(condition, ..) if defs.contains_key(condition) => {
+                    self.expect(*condition, Type::Bool, "branch condition");
+                }
+                Terminator::Return(Some(value)) if defs.contains_key(value) => {
+                    self.expect(*value, function.ret, "returned value");
+                }
+                Terminator::Return(None) if function.ret != Type::Void => {
+                    self.error(format!("{} returns nothing from a function returning {}", block, function.ret));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Checks every function in `module`, returning all problems found.
+pub fn verify(module: &Module) -> Result<(), Vec<Error>> {
+    let mut errors = vec![];
+    for function in &module.functions {
+        let mut verifier = Verifier { module, function, errors: vec![] };
+        verifier.run();
+        errors.append(&mut verifier.errors);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
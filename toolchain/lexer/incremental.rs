@@ -0,0 +1,126 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Relexing after an edit, for editors that lex on every keystroke. Nothing
+// the lexer does reaches across a newline token: comments and strings that
+// span lines swallow their newlines, so a newline token is only made where
+// the lexer is between tokens, and the state after it is just the next
+// line number. `relex` therefore starts over at the last newline before the
+// edit, and stops as soon as it makes a newline that was also in the old
+// text after the edit; every token from there on is the old one, moved.
+use std::ops::Range;
+use crate::lexer::lexer::{lex_from, LexState, Lexed, Token, TokenKind};
+
+/// A change to a text: the bytes in `start..end` are replaced by `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Edit { start: range.start, end: range.end, text: text.into() }
+    }
+
+    /// Makes the change to `source`.
+    pub fn apply(&self, source: &mut String) {
+        source.replace_range(self.start..self.end, &self.text);
+    }
+
+    /// How far text after the edit moves.
+    pub fn delta(&self) -> isize {
+        self.text.len() as isize - (self.end - self.start) as isize
+    }
+}
+
+/// What `relex` redid, in offsets of the new text. Tokens before `start`
+/// are the old ones; tokens from `end` on are the old ones moved by `delta`
+/// bytes and `lines` lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Damage {
+    pub start: usize,
+    pub end: usize,
+    pub delta: isize,
+    pub lines: isize,
+}
+
+fn shift(value: usize, by: isize) -> usize {
+    (value as isize + by) as usize
+}
+
+/// `token`, moved `delta` bytes and `lines` lines, borrowing from `source`.
+fn moved<'src>(token: &Token<'_>, source: &'src str, delta: isize, lines: isize) -> Token<'src> {
+    let pos = shift(token.pos, delta);
+    Token::new(token.kind, &source[pos..pos + token.text.len()], shift(token.line, lines), token.column, pos)
+}
+
+/// Lexes `source`, the text `old` was lexed from with `edit` made to it,
+/// relexing only the lines around the edit. The result is the same as
+/// `lex(source)`.
+pub fn relex<'src>(old: Lexed<'_>, source: &'src str, edit: &Edit) -> Lexed<'src> {
+    relex_damaged(old, source, edit).0
+}
+
+pub(crate) fn relex_damaged<'src>(mut old: Lexed<'_>, source: &'src str, edit: &Edit) -> (Lexed<'src>, Damage) {
+    let delta = edit.delta();
+    let edited_end = edit.start + edit.text.len();
+
+    // Start after the last newline before the edit
+    let before = old.tokens.partition_point(|token| token.pos < edit.start);
+    let sync = old.tokens[..before].iter().rposition(|token| token.kind == TokenKind::Newline);
+    let state = match sync {
+        Some(index) => LexState { pos: old.tokens[index].pos + 1, line: old.tokens[index].line + 1, column: 1 },
+        None => LexState::START,
+    };
+    let kept_errors = old.error_offsets.partition_point(|offset| *offset < state.pos);
+    let kept_comments = old.comments.partition_point(|comment| comment.span.start < state.pos);
+
+    let mut lexed = Lexed {
+        tokens: old.tokens.drain(..sync.map_or(0, |index| index + 1)).map(|token| moved(&token, source, 0, 0)).collect(),
+        errors: old.errors.drain(..kept_errors).collect(),
+        comments: old.comments.drain(..kept_comments).collect(),
+        eof: LexState::START.eof(),
+        error_offsets: old.error_offsets.drain(..kept_errors).collect(),
+    };
+
+    // Stop at a newline past the edit that the old text had too. Errors
+    // quote their line, so old ones can only be kept if lines didn't move.
+    let mut resumed = None;
+    let last_error = old.error_offsets.last().copied();
+    let end = lex_from(source, state, &mut lexed, |pos, line| {
+        if pos < edited_end {
+            return false;
+        }
+        let old_pos = shift(pos, -delta);
+        let index = old.tokens.partition_point(|token| token.pos < old_pos);
+        let Some(token) = old.tokens.get(index).filter(|token| token.pos == old_pos && token.kind == TokenKind::Newline) else {
+            return false;
+        };
+        let lines = line as isize - token.line as isize;
+        if lines != 0 && last_error.is_some_and(|offset| offset > old_pos) {
+            return false;
+        }
+        resumed = Some((index, old_pos, lines));
+        true
+    });
+
+    let Some((index, old_pos, lines)) = resumed else {
+        return (lexed, Damage { start: state.pos, end: source.len(), delta, lines: 0 });
+    };
+    lexed.tokens.extend(old.tokens[index + 1..].iter().map(|token| moved(token, source, delta, lines)));
+    lexed.eof = moved(&old.eof, source, delta, lines);
+    let from = old.comments.partition_point(|comment| comment.span.start < old_pos);
+    for mut comment in old.comments.drain(from..) {
+        comment.span.start = shift(comment.span.start, delta);
+        comment.span.end = shift(comment.span.end, delta);
+        comment.span.line = shift(comment.span.line, lines);
+        lexed.comments.push(comment);
+    }
+    let from = old.error_offsets.partition_point(|offset| *offset < old_pos);
+    lexed.errors.extend(old.errors.drain(from..));
+    lexed.error_offsets.extend(old.error_offsets.drain(from..).map(|offset| shift(offset, delta)));
+
+    (lexed, Damage { start: state.pos, end: end.pos, delta, lines })
+}
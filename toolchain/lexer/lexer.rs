@@ -1,9 +1,13 @@
 // Copyright (c) 2025, Rotor Language Project
 // All rights reserved.
+use std::collections::VecDeque;
 use std::fmt;
 use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::Symbol;
+use crate::source_map::FileId;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     // Keywords
 
@@ -11,6 +15,11 @@ pub enum TokenKind {
     Let,
     Const,
 
+    // Function-related
+    Fn,
+    Pub,
+    Return,
+
     // Dependency-related
     Use,
 
@@ -46,6 +55,9 @@ pub enum TokenKind {
     Colon,
     Newline,
     Comma,
+    // The end of the input. `lex` never makes one; a `TokenStream` hands
+    // one out once its tokens run out
+    Eof,
 
     // Parentheses
     LParen,
@@ -75,20 +87,137 @@ pub enum TokenKind {
 
 }
 
+/// Every word the lexer reserves, with the token it becomes. `lex` looks
+/// identifiers up here, and tools that need the language's vocabulary (like
+/// the generated TextMate grammar) read the same table.
+pub const KEYWORDS: &[(&str, TokenKind)] = &[
+    ("let", TokenKind::Let),
+    ("const", TokenKind::Const),
+    ("fn", TokenKind::Fn),
+    ("pub", TokenKind::Pub),
+    ("return", TokenKind::Return),
+    ("use", TokenKind::Use),
+    ("if", TokenKind::If),
+    ("else", TokenKind::Else),
+    ("for", TokenKind::For),
+    ("while", TokenKind::While),
+    ("in", TokenKind::In),
+    ("i32", TokenKind::I32),
+    ("f32", TokenKind::Float),
+    ("bool", TokenKind::BOOL),
+    ("str", TokenKind::STR),
+    ("true", TokenKind::Boolean),
+    ("false", TokenKind::Boolean),
+];
+
+/// Every symbol, with its token. Where one symbol starts another, the
+/// longer one comes first; `lex` takes the first that matches. `/` is here
+/// too, though `lex` checks for comments before looking at this table.
+pub const SYMBOLS: &[(&str, TokenKind)] = &[
+    ("==", TokenKind::EqualEqual),
+    ("!=", TokenKind::NotEqual),
+    ("<=", TokenKind::LessThanOrEqual),
+    (">=", TokenKind::GreaterThanOrEqual),
+    ("&&", TokenKind::And),
+    ("||", TokenKind::Or),
+    ("..", TokenKind::Range),
+    ("=", TokenKind::Equal),
+    ("!", TokenKind::Not),
+    ("<", TokenKind::LessThan),
+    (">", TokenKind::GreaterThan),
+    (".", TokenKind::Dot),
+    (";", TokenKind::Semicolon),
+    (":", TokenKind::Colon),
+    (",", TokenKind::Comma),
+    ("(", TokenKind::LParen),
+    (")", TokenKind::RParen),
+    ("{", TokenKind::LCurly),
+    ("}", TokenKind::RCurly),
+    ("[", TokenKind::LSquare),
+    ("]", TokenKind::RSquare),
+    ("+", TokenKind::Plus),
+    ("-", TokenKind::Line),
+    ("*", TokenKind::Star),
+    ("/", TokenKind::Slash),
+    ("%", TokenKind::Modulus),
+];
+
+/// A region of source text. `start` and `end` are byte offsets,
+/// `line` and `column` point at the first character, and `file` is the
+/// file in a `SourceMap` the text is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub file: FileId,
+}
+
+impl Span {
+    /// Returns a span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+            line: self.line,
+            column: self.column,
+            file: self.file,
+        }
+    }
+}
 
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct Token {
+/// A token, borrowing its text from the source it was lexed from, so
+/// lexing allocates nothing per token. Literals are decoded when asked for
+/// with `value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'src> {
     pub kind: TokenKind,
-    pub value: String,
+    /// The token exactly as written, quotes included for strings.
+    pub text: &'src str,
+    pub line: usize,
+    pub column: usize,
+    pub(crate) pos: usize
+}
+
+/// A `//` or `/* */` comment. The parser never sees comments; they are
+/// kept for tools like the formatter. `text` includes the delimiters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
+pub struct Lexed<'src> {
+    pub tokens: Vec<Token<'src>>,
+    pub errors: Vec<String>,
+    pub comments: Vec<Comment>,
+    /// The end of the source, past any trailing comments and blank lines.
+    pub eof: Token<'src>,
+    // Where each error was found, so `relex` can tell which ones an edit touches
+    pub(crate) error_offsets: Vec<usize>
+}
+
+/// Where the lexer is: everything it needs to carry on from a point in the
+/// source. After a newline (outside a string or comment) the state depends
+/// on nothing before it but the line number, which is what lets `relex`
+/// start over at a line instead of at the top of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LexState {
+    pub pos: usize,
     pub line: usize,
     pub column: usize,
-    pos: usize
 }
 
-pub struct Lexed {
-    pub tokens: Vec<Token>,
-    pub errors: Vec<String>
+impl LexState {
+    pub const START: LexState = LexState { pos: 0, line: 1, column: 1 };
+
+    /// The token marking the end of the input, if it ends here.
+    pub fn eof<'src>(&self) -> Token<'src> {
+        Token::new(TokenKind::Eof, "", self.line, self.column, self.pos)
+    }
 }
 
 impl fmt::Display for TokenKind {
@@ -96,6 +225,9 @@ impl fmt::Display for TokenKind {
         let s = match self {
             TokenKind::Let => "let",
             TokenKind::Const => "const",
+            TokenKind::Fn => "fn",
+            TokenKind::Pub => "pub",
+            TokenKind::Return => "return",
             TokenKind::Use => "use",
             TokenKind::If => "if",
             TokenKind::Else => "else",
@@ -120,6 +252,7 @@ impl fmt::Display for TokenKind {
             TokenKind::Colon => ":",
             TokenKind::Newline => "\\n",
             TokenKind::Comma => ",",
+            TokenKind::Eof => "end of file",
 
             TokenKind::LParen => "(",
             TokenKind::RParen => ")",
@@ -148,7 +281,7 @@ impl fmt::Display for TokenKind {
     }
 }
 
-impl Lexed {
+impl Lexed<'_> {
     pub fn get_debug_info(&self) {
         for token in &self.tokens {
             println!("{}", token.get_debug_info());
@@ -161,236 +294,381 @@ impl Lexed {
     }
 }
 
-impl Token {
-    pub fn new(kind: TokenKind, value: impl Into<String>, line: usize, column: usize, pos: usize ) -> Self {
+impl<'src> Token<'src> {
+    pub fn new(kind: TokenKind, text: &'src str, line: usize, column: usize, pos: usize ) -> Self {
         Token {
             kind,
-            value: value.into(),
+            text,
             line,
             column,
             pos
         }
     }
 
+    /// The token's value: a string's contents without the quotes, and the
+    /// text as written for everything else.
+    pub fn value(&self) -> &'src str {
+        match self.kind {
+            TokenKind::String => {
+                let contents = self.text.strip_prefix('"').unwrap_or(self.text);
+                // An unterminated string runs to the end of the file
+                contents.strip_suffix('"').unwrap_or(contents)
+            }
+            _ => self.text,
+        }
+    }
+
+    /// The token's value, interned.
+    pub fn symbol(&self) -> Symbol {
+        Symbol::intern(self.value())
+    }
+
     pub fn is_valid(&self) -> bool {
         match self.kind {
-            TokenKind::Let => self.value == "let",
-            TokenKind::Const => self.value == "const",
-            TokenKind::Use => self.value == "use",
-            TokenKind::If => self.value == "if",
-            TokenKind::Else => self.value == "else",
-            TokenKind::For => self.value == "for",
-            TokenKind::While => self.value == "while",
-            TokenKind::In => self.value == "in",
-            TokenKind::Identifier => !self.value.is_empty(),
-            TokenKind::I32 => self.value == "i32",
-            TokenKind::STR => self.value == "str",
-            TokenKind::Equal => self.value == "=",
-            TokenKind::Integer => self.value.parse::<i32>().is_ok(),
-            TokenKind::Float => self.value.parse::<f32>().is_ok(),
-            TokenKind::String => self.value.starts_with('"') && self.value.ends_with('"'),
-            TokenKind::BOOL => self.value == "bool",
-            TokenKind::Boolean => self.value == "true" || self.value == "false",
-            TokenKind::Dot => self.value == ".",
-            TokenKind::Range => self.value == "..",
-            TokenKind::Semicolon => self.value == ";",
-            TokenKind::Colon => self.value == ":",
-            TokenKind::Comma => self.value == ",",
-            TokenKind::Newline => self.value == "\n",
-            TokenKind::LParen => self.value == "(",
-            TokenKind::RParen => self.value == ")",
-            TokenKind::LCurly => self.value == "{",
-            TokenKind::RCurly => self.value == "}",
-            TokenKind::LSquare => self.value == "[",
-            TokenKind::RSquare => self.value == "]",
-            TokenKind::Plus => self.value == "+",
-            TokenKind::Line => self.value == "-",
-            TokenKind::Star => self.value == "*",
-            TokenKind::Slash => self.value == "/",
-            TokenKind::Modulus => self.value == "%",
-            TokenKind::And => self.value == "&&",
-            TokenKind::Or => self.value == "||",
-            TokenKind::Not => self.value == "!",
-            TokenKind::GreaterThan => self.value == ">",
-            TokenKind::LessThan => self.value == "<",
-            TokenKind::GreaterThanOrEqual => self.value == ">=",
-            TokenKind::LessThanOrEqual => self.value == "<=",
-            TokenKind::EqualEqual => self.value == "==",
-            TokenKind::NotEqual => self.value == "!=",
+            TokenKind::Let => self.text == "let",
+            TokenKind::Const => self.text == "const",
+            TokenKind::Fn => self.text == "fn",
+            TokenKind::Pub => self.text == "pub",
+            TokenKind::Return => self.text == "return",
+            TokenKind::Use => self.text == "use",
+            TokenKind::If => self.text == "if",
+            TokenKind::Else => self.text == "else",
+            TokenKind::For => self.text == "for",
+            TokenKind::While => self.text == "while",
+            TokenKind::In => self.text == "in",
+            TokenKind::Identifier => !self.text.is_empty(),
+            TokenKind::I32 => self.text == "i32",
+            TokenKind::STR => self.text == "str",
+            TokenKind::Equal => self.text == "=",
+            TokenKind::Integer => self.text.parse::<i32>().is_ok(),
+            TokenKind::Float => self.text.parse::<f32>().is_ok(),
+            TokenKind::String => self.text.starts_with('"') && self.text.ends_with('"'),
+            TokenKind::BOOL => self.text == "bool",
+            TokenKind::Boolean => self.text == "true" || self.text == "false",
+            TokenKind::Dot => self.text == ".",
+            TokenKind::Range => self.text == "..",
+            TokenKind::Semicolon => self.text == ";",
+            TokenKind::Colon => self.text == ":",
+            TokenKind::Comma => self.text == ",",
+            TokenKind::Newline => self.text == "\n",
+            TokenKind::Eof => self.text.is_empty(),
+            TokenKind::LParen => self.text == "(",
+            TokenKind::RParen => self.text == ")",
+            TokenKind::LCurly => self.text == "{",
+            TokenKind::RCurly => self.text == "}",
+            TokenKind::LSquare => self.text == "[",
+            TokenKind::RSquare => self.text == "]",
+            TokenKind::Plus => self.text == "+",
+            TokenKind::Line => self.text == "-",
+            TokenKind::Star => self.text == "*",
+            TokenKind::Slash => self.text == "/",
+            TokenKind::Modulus => self.text == "%",
+            TokenKind::And => self.text == "&&",
+            TokenKind::Or => self.text == "||",
+            TokenKind::Not => self.text == "!",
+            TokenKind::GreaterThan => self.text == ">",
+            TokenKind::LessThan => self.text == "<",
+            TokenKind::GreaterThanOrEqual => self.text == ">=",
+            TokenKind::LessThanOrEqual => self.text == "<=",
+            TokenKind::EqualEqual => self.text == "==",
+            TokenKind::NotEqual => self.text == "!=",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.pos,
+            end: self.pos + self.text.len(),
+            line: self.line,
+            column: self.column,
+            file: FileId::default(),
         }
     }
 
     pub fn get_debug_info(&self) -> String {
         format!(
             "Token: {:?}, Value: '{}', Line: {}, Column: {}",
-            self.kind, self.value, self.line, self.column
+            self.kind, self.value(), self.line, self.column
         )
     }
 }
 
-pub fn lex(source: &str) -> Lexed {
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut errors: Vec<String> = Vec::new();
-    let mut line: usize = 1;
-    let mut column: usize = 1;
-    let mut pos: usize = 0;
-    let chars: &[u8] = source.as_bytes();
-
-    while pos < chars.len() {
-        let ch: char = chars[pos] as char;
-
-        match ch {
-            // WARNING: 
-            // The following match arms are not special
-            // so they only need to be eaten and not
-            // have any extra logic for them.
-            '=' | ';' | ':' | '(' | ')' | '{' | '}' | '[' | ']' | '+' | '-' | '*' | '%' => {
-                let kind = match ch {
-                    '=' => TokenKind::Equal,
-                    ';' => TokenKind::Semicolon,
-                    ':' => TokenKind::Colon,
-                    '(' => TokenKind::LParen,
-                    ')' => TokenKind::RParen,
-                    '{' => TokenKind::LCurly,
-                    '}' => TokenKind::RCurly,
-                    '[' => TokenKind::LSquare,
-                    ']' => TokenKind::RSquare,
-                    '+' => TokenKind::Plus,
-                    '-' => TokenKind::Line,
-                    '*' => TokenKind::Star,
-                    '%' => TokenKind::Modulus,
-                    _ => unreachable!(), // Oopsie daisys, you shouldn't be here. Now suffer a terrible error message.
-                    
-                };
-                tokens.push(Token::new(kind, ch.to_string(), line, column, pos));
-                pos += 1;
-                column += 1;
-            }
-            '/' => {
-                if pos + 1 < chars.len() && chars[pos + 1] == b'/' {
-                    // Single-line comment
-                    pos += 2;
-                    column += 2;
-                    while pos < chars.len() && chars[pos] != b'\n' {
+// Tokens are written out as they were when they owned their value
+#[cfg(feature = "serde")]
+impl serde::Serialize for Token<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut token = serializer.serialize_struct("Token", 5)?;
+        token.serialize_field("kind", &self.kind)?;
+        token.serialize_field("value", self.value())?;
+        token.serialize_field("line", &self.line)?;
+        token.serialize_field("column", &self.column)?;
+        token.serialize_field("pos", &self.pos)?;
+        token.end()
+    }
+}
+
+/// A lexer that makes tokens as they are asked for. Each item is a token
+/// or an error about the text it skipped; comments are collected on the
+/// side. Any number of items can be looked at ahead of time with `peek_nth`.
+pub struct Lexer<'src> {
+    source: &'src str,
+    state: LexState,
+    // Items lexed by `peek_nth` and not handed out yet
+    lookahead: VecDeque<Result<Token<'src>, Error>>,
+    // A token lexed along with an error, to be returned after it
+    pending: Option<(usize, Result<Token<'src>, Error>)>,
+    comments: Vec<Comment>,
+}
+
+impl<'src> Lexer<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Lexer::at(source, LexState::START)
+    }
+
+    pub(crate) fn at(source: &'src str, state: LexState) -> Self {
+        Lexer { source, state, lookahead: VecDeque::new(), pending: None, comments: vec![] }
+    }
+
+    /// The item `n` places ahead (0 is the one `next` returns), lexing as
+    /// far as needed.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Result<Token<'src>, Error>> {
+        while self.lookahead.len() <= n {
+            let (_, item) = self.lex_item()?;
+            self.lookahead.push_back(item);
+        }
+        self.lookahead.get(n)
+    }
+
+    pub fn peek(&mut self) -> Option<&Result<Token<'src>, Error>> {
+        self.peek_nth(0)
+    }
+
+    /// The comments passed over so far.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
+    /// The token marking the end of the input, once the lexer has run out.
+    pub(crate) fn eof(&self) -> Token<'src> {
+        self.state.eof()
+    }
+
+    /// Lexes the next token or error, returning where it starts.
+    fn lex_item(&mut self) -> Option<(usize, Result<Token<'src>, Error>)> {
+        if let Some(pending) = self.pending.take() {
+            return Some(pending);
+        }
+        let source = self.source;
+        let chars: &[u8] = source.as_bytes();
+        let LexState { mut pos, mut line, mut column } = self.state;
+        let mut item = None;
+
+        while item.is_none() && pos < chars.len() {
+            // Tokens are sliced out of the source, so `pos` has to stay on a
+            // char boundary
+            let ch: char = match chars[pos] {
+                byte if byte.is_ascii() => byte as char,
+                _ => source[pos..].chars().next().unwrap_or(char::REPLACEMENT_CHARACTER),
+            };
+            let start = pos;
+
+            match ch {
+                '/' => {
+                    let start = Span { start: pos, end: pos, line, column, ..Span::default() };
+                    if pos + 1 < chars.len() && chars[pos + 1] == b'/' {
+                        // Single-line comment
+                        pos += 2;
+                        column += 2;
+                        while pos < chars.len() && chars[pos] != b'\n' {
+                            pos += 1;
+                            column += 1;
+                        }
+                        self.comments.push(Comment { text: source[start.start..pos].to_string(), span: Span { end: pos, ..start } });
+                    } else if pos + 1 < chars.len() && chars[pos + 1] == b'*' {
+                        // Multi-line comment
+                        pos += 2;
+                        column += 2;
+                        while pos < chars.len() {
+                            if pos + 1 < chars.len() && chars[pos] == b'*' && chars[pos + 1] == b'/' {
+                                pos += 2;
+                                column += 2;
+                                break;
+                            }
+                            if chars[pos] == b'\n' {
+                                line += 1;
+                                column = 1;
+                            } else {
+                                column += 1;
+                            }
+                            pos += 1;
+                        }
+                        self.comments.push(Comment { text: source[start.start..pos].to_string(), span: Span { end: pos, ..start } });
+                    } else {
+                        item = Some(Ok(Token::new(TokenKind::Slash, "/", line, column, pos)));
                         pos += 1;
                         column += 1;
                     }
-                } else if pos + 1 < chars.len() && chars[pos + 1] == b'*' {
-                    // Multi-line comment
-                    pos += 2;
-                    column += 2;
-                    while pos < chars.len() {
-                        if pos + 1 < chars.len() && chars[pos] == b'*' && chars[pos + 1] == b'/' {
-                            pos += 2;
-                            column += 2;
-                            break;
+                }
+                c if SYMBOLS.iter().any(|(text, _)| text.starts_with(c)) => {
+                    // Longer symbols come first in the table, so `==` wins over `=`
+                    match SYMBOLS.iter().find(|(text, _)| source[pos..].starts_with(text)) {
+                        Some((text, kind)) => {
+                            item = Some(Ok(Token::new(*kind, text, line, column, pos)));
+                            pos += text.len();
+                            column += text.len();
                         }
-                        if chars[pos] == b'\n' {
-                            line += 1;
-                            column = 1;
-                        } else {
+                        // A lone '&' or '|'
+                        None => {
+                            item = Some(Err(Error::new(
+                                ErrorKind::InvalidToken,
+                                format!("Invalid token: {}", ch),
+                                line,
+                                column,
+                            )));
+                            pos += 1;
                             column += 1;
                         }
-                        pos += 1;
                     }
-                } else {
-                    tokens.push(Token::new(TokenKind::Slash, "/", line, column, pos));
-                    pos += 1;
-                    column += 1;
                 }
-            }
-            // The '.' character is already handled in the first match arm above.
-            c if c.is_alphabetic() || c == '_' => {
-                let start_column = column;
-                let start_pos: usize = pos;
-                let mut identifier = String::new();
-
-                while pos < chars.len() && ((chars[pos] as char).is_alphanumeric() || chars[pos] == b'_') {
-                    identifier.push(chars[pos] as char);
-                    pos += 1;
-                    column += 1;
+                c if c.is_alphabetic() || c == '_' => {
+                    let start_column = column;
+                    let start_pos: usize = pos;
+                    let rest = &source[pos..];
+                    let identifier = &rest[..rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len())];
+                    pos += identifier.len();
+                    column += identifier.len();
+
+                    let kind = KEYWORDS.iter().find(|(word, _)| *word == identifier).map_or(TokenKind::Identifier, |(_, kind)| *kind);
+                    item = Some(Ok(Token::new(kind, identifier, line, start_column, start_pos)));
                 }
+                c if c.is_ascii_digit() => {
+                    let start_column = column;
+                    let start_pos: usize = pos; // i feel like eating a jobonga. you don't know what that is? uncultured -_-
+                    let mut other_numeric_type: TokenKind = TokenKind::Integer;
 
-                match identifier.as_str() {
-                    "let" => tokens.push(Token::new(TokenKind::Let, identifier, line, start_column, start_pos)),
-                    "const" => tokens.push(Token::new(TokenKind::Const, identifier, line, start_column, start_pos)),
-                    "if" => tokens.push(Token::new(TokenKind::If, identifier, line, start_column, start_pos)),
-                    "else" => tokens.push(Token::new(TokenKind::Else, identifier, line, start_column, start_pos)),
-                    "for" => tokens.push(Token::new(TokenKind::For, identifier, line, start_column, start_pos)),
-                    "while" => tokens.push(Token::new(TokenKind::While, identifier, line, start_column, start_pos)),
-                    "in" => tokens.push(Token::new(TokenKind::In, identifier, line, start_column, start_pos)),
-                    "i32" => tokens.push(Token::new(TokenKind::I32, identifier, line, start_column, start_pos)),
-                    "f32" => tokens.push(Token::new(TokenKind::Float, identifier, line, start_column, start_pos)),
-                    "bool" => tokens.push(Token::new(TokenKind::BOOL, identifier, line, start_column, start_pos)),
-                    "str" => tokens.push(Token::new(TokenKind::STR, identifier, line, start_column, start_pos)),
-                    "true" => tokens.push(Token::new(TokenKind::Boolean, identifier, line, start_column, start_pos)),
-                    "false" => tokens.push(Token::new(TokenKind::Boolean, identifier, line, start_column, start_pos)),
-                    "use" => tokens.push(Token::new(TokenKind::Use, identifier, line, start_column, start_pos)),
-                    _ => tokens.push(Token::new(TokenKind::Identifier, identifier, line, start_column, start_pos)),
-                }
-            }
-            c if c.is_digit(10) => {
-                let start_column = column;
-                let start_pos: usize = pos; // i feel like eating a jobonga. you don't know what that is? uncultured -_-
-                let mut number = String::new();
-                let mut other_numeric_type: TokenKind = TokenKind::Integer;
-
-                while pos < chars.len() && (chars[pos] as char).is_digit(10) {
-                    if chars[pos] == b'.' && other_numeric_type == TokenKind::Integer {
-                        other_numeric_type = TokenKind::Float;
-                        number.push('.');
+                    while pos < chars.len() {
+                        if chars[pos] == b'.'
+                            && other_numeric_type == TokenKind::Integer
+                            && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())
+                        {
+                            // Only a dot followed by a digit makes a float,
+                            // so `0..10` still lexes as a range.
+                            other_numeric_type = TokenKind::Float;
+                        } else if !chars[pos].is_ascii_digit() {
+                            break;
+                        }
                         pos += 1;
                         column += 1;
+                    }
+
+                    item = Some(Ok(Token::new(other_numeric_type, &source[start_pos..pos], line, start_column, start_pos)));
+                }
+                c if c.is_whitespace() => {
+                    if c == '\n' {
+                        item = Some(Ok(Token::new(TokenKind::Newline, "\n", line, column, pos)));
+                        line += 1;
+                        column = 1;
+                    } else if c == '\t' {
+                        column += 4; // This is assuming a tab will be 4 spaces. idk anyone who actually has a tab set to 8 spaces. if you do, you should probably call emergency services and get help.
                     } else {
-                        number.push(chars[pos] as char);
+                        column += c.len_utf8();
+                    }
+                    pos += c.len_utf8();
+                }
+
+                '\"' => {
+                    let start_column = column;
+                    let start_pos: usize = pos;
+                    pos += 1;
+                    column += 1;
+                    while pos < chars.len() && chars[pos] != b'\"' {
                         pos += 1;
                         column += 1;
                     }
-                    
+                    let unterminated = pos >= chars.len();
+                    if !unterminated {
+                        // Eat the closing quote
+                        column += 1;
+                        pos += 1;
+                    }
+                    item = Some(Ok(Token::new(TokenKind::String, &source[start_pos..pos], line, start_column, start_pos)));
+                    if unterminated {
+                        // The string still makes a token, handed out after the error
+                        self.pending = item.take().map(|token| (start_pos, token));
+                        item = Some(Err(Error::new(
+                            ErrorKind::UnterminatedString,
+                            "Unterminated string",
+                            line,
+                            start_column,
+                        )));
+                    }
                 }
 
-                tokens.push(Token::new(other_numeric_type, number, line, start_column, start_pos));
-            }
-            c if c.is_whitespace() => {
-                if c == '\n' {
-                    tokens.push(Token::new(TokenKind::Newline, "\n", line, column, pos));
-                    line += 1;
-                    column = 1;
-                } else if c == '\t' {
-                    column += 4; // This is assuming a tab will be 4 spaces. idk anyone who actually has a tab set to 8 spaces. if you do, you should probably call emergency services and get help.
-                } else {
-                    column += 1;
+                _ => {
+                    item = Some(Err(Error::new(
+                        ErrorKind::InvalidToken,
+                        format!("Invalid token: {}", ch), //                                            o        o
+                        line, //                                                                                     |               <- this is gart. dont be mean to him.
+                        column, //                                                                                 \___/                he's really nice and helpful.
+                    )));  //                                                                           so don't hurt him or i will hurt you. >:(
+                    pos += ch.len_utf8();
+                    column += ch.len_utf8();
                 }
-                pos += 1;
             }
+            if let Some(item) = item.take() {
+                self.state = LexState { pos, line, column };
+                return Some((start, item));
+            }
+        }
+        self.state = LexState { pos, line, column };
+        None
+    }
+}
 
-            '\"' => {
-                let start_column = column;
-                let mut string = String::new();
-                pos += 1;
-                column += 1;
-                while pos < chars.len() && chars[pos] != b'\"' {
-                    string.push(chars[pos] as char);
-                    pos += 1;
-                    column += 1;
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.lookahead.pop_front() {
+            Some(item) => Some(item),
+            None => self.lex_item().map(|(_, item)| item),
+        }
+    }
+}
+
+pub fn lex(source: &str) -> Lexed<'_> {
+    let mut lexed = Lexed { tokens: vec![], errors: vec![], comments: vec![], eof: LexState::START.eof(), error_offsets: vec![] };
+    lex_from(source, LexState::START, &mut lexed, |_, _| false);
+    lexed
+}
+
+/// Lexes `source` from `state`, adding to `lexed`. After each newline
+/// token, `stop` is given the newline's offset and line; if it returns
+/// true, lexing stops there and the state after the newline is returned.
+pub(crate) fn lex_from<'src>(source: &'src str, state: LexState, lexed: &mut Lexed<'src>, mut stop: impl FnMut(usize, usize) -> bool) -> LexState {
+    let mut lexer = Lexer::at(source, state);
+    while let Some((start, item)) = lexer.lex_item() {
+        lexed.comments.append(&mut lexer.comments);
+        match item {
+            Ok(token) => {
+                lexed.tokens.push(token);
+                if token.kind == TokenKind::Newline && stop(token.pos, token.line) {
+                    return lexer.state;
                 }
-                // Eat the closing quote
-                column += 1;
-                pos += 1;
-                tokens.push(Token::new(TokenKind::String, string, line, start_column, pos));
             }
-
-            _ => {
-                Error::new(
-                    ErrorKind::InvalidToken,
-                    format!("Invalid token({}, {}): {}", line, column, ch), //                      o        o
-                    line, //                                                                                     |               <- this is gart. dont be mean to him.
-                    column, //                                                                                 \___/                he's really nice and helpful.
-                ).push_new(&mut errors);  //                                                                           so don't hurt him or i will hurt you. >:(
-                pos += 1;
-                column += 1;
+            Err(error) => {
+                error.push_new(&mut lexed.errors);
+                lexed.error_offsets.push(start);
             }
         }
     }
-    Lexed { tokens, errors }
+    lexed.comments.append(&mut lexer.comments);
+    lexed.eof = lexer.eof();
+    lexer.state
 }
@@ -1,2 +1,8 @@
 pub mod lexer;
-pub use lexer::{TokenKind, Token, lex, Lexed};
+pub mod trivia;
+pub mod incremental;
+pub mod symbol;
+pub use lexer::{TokenKind, Token, Span, Comment, KEYWORDS, SYMBOLS, lex, Lexed, Lexer};
+pub use trivia::{Trivia, TriviaKind, LosslessToken, LosslessLexed, lex_lossless};
+pub use incremental::{Edit, relex};
+pub use symbol::Symbol;
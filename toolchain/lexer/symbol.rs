@@ -0,0 +1,134 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Interned names. Every identifier the parser keeps is stored once in a
+// global table and passed around as a `Symbol` pointing at that copy, so
+// symbols are `Copy`, compare as pointers and read their text without a
+// lock. The table only grows: its strings live as long as the process,
+// which for a language server or REPL means every distinct name typed.
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+
+/// An interned string. Two symbols are equal exactly when their texts are.
+#[derive(Clone, Copy)]
+pub struct Symbol(&'static str);
+
+fn interner() -> &'static Mutex<HashSet<&'static str>> {
+    static INTERNER: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    INTERNER.get_or_init(Mutex::default)
+}
+
+impl Symbol {
+    /// The symbol for `text`, adding it to the table the first time.
+    pub fn intern(text: &str) -> Symbol {
+        let mut interner = interner().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(interned) = interner.get(text) {
+            return Symbol(interned);
+        }
+        let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+        interner.insert(text);
+        Symbol(text)
+    }
+
+    /// The text the symbol was interned from.
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+// There is one copy of each text, so its address identifies it
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol::intern(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol::intern(&text)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+// Ordered by text, so sorting names doesn't depend on which was seen first
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Printed as the string it stands for, so ASTs debug-print as they did
+// before names were interned
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Symbol::from)
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Lossless lexing. `lex` throws away everything between tokens; here that
+// text is kept as trivia attached to the tokens around it, so that joining
+// every token's leading trivia, text and trailing trivia gives back the
+// source byte for byte.
+//
+// A token's trailing trivia is whatever follows it on the same line, up to
+// (but not including) the line break. Everything else before a token is its
+// leading trivia, and what comes after the last token is kept separately.
+// Newlines are always trivia here, unlike the `Newline` tokens of `lex`,
+// and characters `lex` rejected are kept as `Skipped` trivia.
+use crate::lexer::{lex, Lexed, Span, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+    // Text `lex` reported as an error
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LosslessToken {
+    pub kind: TokenKind,
+    /// The token exactly as written, quotes included for strings.
+    pub text: String,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl LosslessToken {
+    pub fn write_to(&self, out: &mut String) {
+        for trivia in &self.leading {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&self.text);
+        for trivia in &self.trailing {
+            out.push_str(&trivia.text);
+        }
+    }
+}
+
+pub struct LosslessLexed {
+    pub tokens: Vec<LosslessToken>,
+    /// Trivia after the last token (or the whole file if it has no tokens).
+    pub eof: Vec<Trivia>,
+    pub errors: Vec<String>,
+}
+
+impl LosslessLexed {
+    /// The source the tokens came from.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for token in &self.tokens {
+            token.write_to(&mut out);
+        }
+        for trivia in &self.eof {
+            out.push_str(&trivia.text);
+        }
+        out
+    }
+}
+
+/// Splits the text between two tokens into trivia.
+fn split_trivia(text: &str) -> Vec<Trivia> {
+    let mut trivia = vec![];
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let line_end = rest.find('\n').map_or(rest.len(), |end| if rest[..end].ends_with('\r') { end - 1 } else { end });
+        let (kind, len) = if rest.starts_with("\r\n") {
+            (TriviaKind::Newline, 2)
+        } else if rest.starts_with('\n') {
+            (TriviaKind::Newline, 1)
+        } else if rest.starts_with("//") {
+            (TriviaKind::LineComment, line_end)
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            (TriviaKind::BlockComment, body.find("*/").map_or(rest.len(), |end| end + 4))
+        } else {
+            let space = rest.find(|ch: char| !ch.is_whitespace() || ch == '\n').unwrap_or(rest.len()).min(line_end);
+            match space {
+                0 => (TriviaKind::Skipped, rest.chars().next().map_or(1, char::len_utf8)),
+                _ => (TriviaKind::Whitespace, space),
+            }
+        };
+        trivia.push(Trivia { kind, text: rest[..len].to_string() });
+        pos += len;
+    }
+    trivia
+}
+
+/// Moves the trivia before the first line break to the previous token.
+fn split_at_line_break(mut trivia: Vec<Trivia>) -> (Vec<Trivia>, Vec<Trivia>) {
+    let at = trivia.iter().position(|piece| piece.kind == TriviaKind::Newline).unwrap_or(trivia.len());
+    let leading = trivia.split_off(at);
+    (trivia, leading)
+}
+
+fn char_boundary(source: &str, mut offset: usize) -> usize {
+    offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+/// Lexes `source` keeping every byte of it. The tokens and errors are the
+/// same as `lex` gives, minus the `Newline` tokens.
+pub fn lex_lossless(source: &str) -> LosslessLexed {
+    attach_trivia(source, &lex(source))
+}
+
+/// Adds the trivia to tokens `lex` already found in `source`.
+pub(crate) fn attach_trivia(source: &str, lexed: &Lexed) -> LosslessLexed {
+    let spans: Vec<(TokenKind, Span)> = lexed
+        .tokens
+        .iter()
+        .filter(|token| token.kind != TokenKind::Newline)
+        .map(|token| (token.kind, token.span()))
+        .collect();
+
+    let mut tokens: Vec<LosslessToken> = vec![];
+    let mut last_end = 0;
+    for (index, (kind, span)) in spans.iter().enumerate() {
+        let start = char_boundary(source, span.start.max(last_end));
+        // Never run into the next token, even if a span is off
+        let next_start = spans.get(index + 1).map_or(source.len(), |(_, next)| next.start);
+        let end = char_boundary(source, span.end.min(next_start).max(start));
+
+        let gap = split_trivia(&source[last_end..start]);
+        let (trailing, leading) = if tokens.is_empty() { (vec![], gap) } else { split_at_line_break(gap) };
+        if let Some(previous) = tokens.last_mut() {
+            previous.trailing = trailing;
+        }
+        tokens.push(LosslessToken {
+            kind: *kind,
+            text: source[start..end].to_string(),
+            span: Span { start, end, ..*span },
+            leading,
+            trailing: vec![],
+        });
+        last_end = end;
+    }
+
+    let rest = split_trivia(&source[last_end..]);
+    let eof = match tokens.last_mut() {
+        Some(last) => {
+            let (trailing, eof) = split_at_line_break(rest);
+            last.trailing = trailing;
+            eof
+        }
+        None => rest,
+    };
+    LosslessLexed { tokens, eof, errors: lexed.errors.clone() }
+}
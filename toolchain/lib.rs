@@ -1,7 +1,23 @@
+// Each module lives in a folder of the same name (e.g. `lexer/lexer.rs`)
+#![allow(clippy::module_inception)]
+
 pub mod lexer;
 pub mod parser;
 pub mod handle_error;
+pub mod typeck;
+pub mod codegen;
+pub mod ir;
+pub mod opt;
+pub mod loader;
+pub mod stdlib;
+pub mod project;
+pub mod format;
+pub mod lsp;
+pub mod grammar;
+pub mod repl;
+pub mod source_map;
 
-pub use lexer::{TokenKind, Token, lex, Lexed};
+pub use lexer::{TokenKind, Token, lex, Lexed, Lexer};
 pub use parser::{TokenStream, p_let_stmt, p_use_stmt, p_if_stmt};
-pub use handle_error::{ErrorKind, Error};
\ No newline at end of file
+pub use handle_error::{ErrorKind, Error};
+pub use source_map::{FileId, SourceMap};
@@ -0,0 +1,488 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The loader turns `use stator [imports]` into files. A stator path maps
+// onto the source tree: `use util [..]` loads `src/util.rt` and
+// `use net.http [..]` loads `src/net/http.rt`, while paths under `std`
+// come from the standard library built into the toolchain and paths
+// starting with a dependency's name come from that dependency. Every stator
+// is parsed once, however often it is imported, and an import that leads
+// back to a stator still being loaded is reported as a cycle.
+//
+// An imported name may also be a stator of its own: `use std.io [console]`
+// loads `std.io.console` and makes its public functions callable as
+// `console.out(..)`.
+//
+// Loaded stators are linked into a single program. The entry stator's
+// items keep their names; everything else is qualified with its stator
+// path (`util.helper`), and calls through imports are rewritten to the
+// qualified names, so a name is only visible where it was imported.
+//
+// Every file read goes into the loader's `SourceMap`, and the spans of a
+// stator's tree are marked with its file, so errors found in the linked
+// program still say which file they are about.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::{Lexer, Span, Symbol};
+use crate::parser::nodes::{walk_expr_mut, walk_stmt_mut, Block, Expr, FnParam, Program, Stmt, UseImports, VisitorMut};
+use crate::parser::{parse_program_recovering, TokenStream};
+use crate::source_map::{FileId, SourceMap};
+use crate::stdlib;
+
+pub struct Stator {
+    /// The dotted path the stator is imported by.
+    pub name: String,
+    pub file: PathBuf,
+    /// The file in the loader's `SourceMap`.
+    pub id: FileId,
+    pub program: Program,
+}
+
+pub struct Loader {
+    root: PathBuf,
+    // Entry files of other projects, by the stator name they are imported as
+    dependencies: HashMap<String, PathBuf>,
+    // Dependencies come before the stators that import them
+    stators: Vec<Stator>,
+    index: HashMap<String, usize>,
+    // Stators whose files didn't parse, so they are only reported once
+    failed: HashSet<String>,
+    // Stators currently being loaded, to detect cycles
+    stack: Vec<String>,
+    entry: String,
+    errors: Vec<Error>,
+    sources: SourceMap,
+}
+
+/// Marks every span of a tree as being in `file`.
+struct InFile(FileId);
+
+impl VisitorMut for InFile {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::LetStmt { span, .. }
+            | Stmt::UseStmt { span, .. }
+            | Stmt::FnDecl { span, .. }
+            | Stmt::IfStmt { span, .. }
+            | Stmt::ForStmt { span, .. }
+            | Stmt::WhileStmt { span, .. }
+            | Stmt::AssignStmt { span, .. }
+            | Stmt::ReturnStmt { span, .. } => span.file = self.0,
+            Stmt::CallStmt(_) => {}
+        }
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut FnParam) {
+        param.span.file = self.0;
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Literal { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::BinaryOp { span, .. }
+            | Expr::UnaryOp { span, .. }
+            | Expr::FnCall { span, .. }
+            | Expr::Array { span, .. } => span.file = self.0,
+        }
+        walk_expr_mut(self, expr);
+    }
+}
+
+fn is_std(name: &str) -> bool {
+    name == "std" || name.starts_with("std.")
+}
+
+impl Loader {
+    /// A loader resolving stator paths against `root`, usually a project's `src/`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Loader {
+            root: root.into(),
+            dependencies: HashMap::new(),
+            stators: vec![],
+            index: HashMap::new(),
+            failed: HashSet::new(),
+            stack: vec![],
+            entry: String::new(),
+            errors: vec![],
+            sources: SourceMap::new(),
+        }
+    }
+
+    /// Makes the project whose entry stator is `entry` importable as
+    /// `name`; `name.inner` then loads `inner.rt` next to the entry.
+    pub fn add_dependency(&mut self, name: &str, entry: PathBuf) {
+        self.dependencies.insert(name.to_string(), entry);
+    }
+
+    /// The file a stator path maps to.
+    pub fn stator_file(&self, name: &str) -> PathBuf {
+        let mut parts = name.split('.');
+        let mut file = match self.dependencies.get(parts.next().unwrap_or_default()) {
+            Some(entry) if !name.contains('.') => return entry.clone(),
+            Some(entry) => entry.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            None => {
+                parts = name.split('.');
+                self.root.clone()
+            }
+        };
+        file.extend(parts);
+        file.set_extension("rt");
+        file
+    }
+
+    pub fn stators(&self) -> &[Stator] {
+        &self.stators
+    }
+
+    /// Every file read so far, including those that failed to parse.
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    pub fn into_sources(self) -> SourceMap {
+        self.sources
+    }
+
+    /// Where a stator comes from, with its source if it exists. Standard
+    /// library stators are labelled `<std>/io/console.rt` and so on.
+    fn locate(&self, name: &str) -> (PathBuf, Option<String>) {
+        if is_std(name) {
+            let mut file = PathBuf::from("<std>");
+            file.extend(name.split('.').skip(1));
+            file.set_extension("rt");
+            return (file, stdlib::source(name).map(str::to_string));
+        }
+        let file = self.stator_file(name);
+        let source = std::fs::read_to_string(&file).ok();
+        (file, source)
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        if is_std(name) { stdlib::source(name).is_some() } else { self.stator_file(name).is_file() }
+    }
+
+    /// Whether the loaded stator `stator` declares an item called `item`.
+    fn declares(&self, stator: &str, item: &str) -> bool {
+        let Some(&index) = self.index.get(stator) else { return false };
+        self.stators[index].program.iter().any(|stmt| match stmt {
+            Stmt::FnDecl { name, .. } | Stmt::LetStmt { name, .. } => name == item,
+            _ => false,
+        })
+    }
+
+    fn parse(&mut self, id: FileId) -> Option<Program> {
+        // The parser pulls tokens from the lexer as it goes
+        let mut stream = TokenStream::from_lexer(Lexer::new(self.sources.text(id)));
+        let (mut program, errors) = parse_program_recovering(&mut stream);
+        let lex_errors = stream.take_lex_errors();
+        let errors = if lex_errors.is_empty() { errors } else { lex_errors };
+        if errors.is_empty() {
+            InFile(id).visit_program_mut(&mut program);
+            return Some(program);
+        }
+        self.errors.extend(errors.into_iter().map(|error| error.in_file(id)));
+        None
+    }
+
+    /// Loads the stator `name`, imported at `span`.
+    fn import(&mut self, name: &str, span: Span) {
+        if let Some(start) = self.stack.iter().position(|loading| loading == name) {
+            let mut chain = self.stack[start..].to_vec();
+            chain.push(name.to_string());
+            self.errors.push(Error::at(ErrorKind::ImportCycle, format!("Import cycle: {}", chain.join(" -> ")), span));
+            return;
+        }
+        if self.index.contains_key(name) || self.failed.contains(name) {
+            return;
+        }
+        let (file, source) = self.locate(name);
+        let Some(source) = source else {
+            let expected = if is_std(name) { "not in the standard library".to_string() } else { format!("expected {}", file.display()) };
+            self.errors.push(Error::at(ErrorKind::UnknownStator, format!("Unknown stator `{}` ({})", name, expected), span));
+            return;
+        };
+        self.load(name, file, source);
+    }
+
+    fn load(&mut self, name: &str, file: PathBuf, source: String) {
+        let id = self.sources.add(&file, source);
+        let Some(program) = self.parse(id) else {
+            self.failed.insert(name.to_string());
+            return;
+        };
+
+        self.stack.push(name.to_string());
+        for stmt in &program {
+            let Stmt::UseStmt { stator, imports, span } = stmt else { continue };
+            self.import(stator, *span);
+            // Imported names that are not items may be stators of their own
+            if let UseImports::List(names) = imports {
+                for item in names {
+                    let child = format!("{}.{}", stator, item);
+                    if !self.declares(stator, item) && self.exists(&child) {
+                        self.import(&child, *span);
+                    }
+                }
+            }
+        }
+        self.stack.pop();
+
+        self.index.insert(name.to_string(), self.stators.len());
+        self.stators.push(Stator { name: name.to_string(), file, id, program });
+    }
+
+    /// Loads `entry` and every stator it imports, directly or not.
+    pub fn load_entry(&mut self, entry: &Path) -> Result<(), Vec<Error>> {
+        match std::fs::read_to_string(entry) {
+            Ok(source) => self.load_entry_source(entry, &source),
+            Err(error) => Err(vec![Error::new(ErrorKind::UnknownStator, format!("Unable to read {}: {}", entry.display(), error), 0, 0)]),
+        }
+    }
+
+    /// Like `load_entry`, with the entry's source already in memory.
+    pub fn load_entry_source(&mut self, entry: &Path, source: &str) -> Result<(), Vec<Error>> {
+        self.entry = entry.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let name = self.entry.clone();
+        self.load(&name, entry.to_path_buf(), source.to_string());
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        Ok(())
+    }
+
+    /// The public items of every stator, by stator name.
+    fn exports(&self) -> HashMap<&str, Vec<&str>> {
+        let mut exports = HashMap::new();
+        for stator in &self.stators {
+            let items = stator
+                .program
+                .iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::FnDecl { name, public: true, .. } => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            exports.insert(stator.name.as_str(), items);
+        }
+        exports
+    }
+
+    /// Resolves the imports of one stator to the qualified names they
+    /// refer to, split into imported functions and imported stators.
+    fn resolve_imports(
+        &self,
+        stator: &Stator,
+        exports: &HashMap<&str, Vec<&str>>,
+        errors: &mut Vec<Error>,
+    ) -> (HashMap<Symbol, Symbol>, HashMap<String, String>) {
+        let mut imports: HashMap<Symbol, Symbol> = HashMap::new();
+        let mut namespaces: HashMap<String, String> = HashMap::new();
+        for stmt in &stator.program {
+            let Stmt::UseStmt { stator: target, imports: list, span } = stmt else { continue };
+            let public = &exports[target.as_str()];
+            let names: Vec<&str> = match list {
+                UseImports::Wildcard => public.clone(),
+                UseImports::List(names) => names.iter().map(|name| name.as_str()).collect(),
+            };
+            for name in names {
+                let qualified = format!("{}.{}", target, name);
+                let namespace = !public.contains(&name) && self.index.contains_key(&qualified);
+                if !public.contains(&name) && !namespace {
+                    let (kind, message) = if self.declares(target, name) {
+                        (ErrorKind::PrivateItem, format!("`{}` is private to stator `{}`", name, target))
+                    } else {
+                        (ErrorKind::UnknownImport, format!("Stator `{}` has no item `{}`", target, name))
+                    };
+                    errors.push(Error::at(kind, message, *span));
+                    continue;
+                }
+                let declared_here = stator.program.iter().any(|stmt| matches!(stmt, Stmt::FnDecl { name: item, .. } if item == name));
+                if declared_here || imports.contains_key(&Symbol::intern(name)) || namespaces.contains_key(name) {
+                    errors.push(Error::at(
+                        ErrorKind::DuplicateDefinition,
+                        format!("`{}` is imported more than once or clashes with a local item", name),
+                        *span,
+                    ));
+                    continue;
+                }
+                if namespace {
+                    namespaces.insert(name.to_string(), qualified);
+                } else {
+                    imports.insert(Symbol::intern(name), Symbol::from(qualified));
+                }
+            }
+        }
+        (imports, namespaces)
+    }
+
+    /// Checks the imports of every loaded stator and links them into one
+    /// program. The stators are moved into it; their files stay in
+    /// `sources`.
+    pub fn link(&mut self) -> Result<Program, Vec<Error>> {
+        let exports = self.exports();
+        let mut errors = vec![];
+        let mut resolved = vec![];
+        for stator in &self.stators {
+            resolved.push(self.resolve_imports(stator, &exports, &mut errors));
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let public: HashSet<String> = exports
+            .iter()
+            .flat_map(|(stator, items)| items.iter().map(move |item| format!("{}.{}", stator, item)))
+            .collect();
+        let entry = self.entry.as_str();
+        let mut linked = vec![];
+        for (stator, (imports, namespaces)) in std::mem::take(&mut self.stators).into_iter().zip(resolved) {
+            let qualify = |name: Symbol| {
+                if stator.name == entry { name } else { Symbol::from(format!("{}.{}", stator.name, name)) }
+            };
+            let mut functions = imports;
+            let mut globals = HashMap::new();
+            for stmt in &stator.program {
+                match stmt {
+                    Stmt::FnDecl { name, .. } => { functions.insert(*name, qualify(*name)); }
+                    Stmt::LetStmt { name, .. } => { globals.insert(*name, qualify(*name)); }
+                    _ => {}
+                }
+            }
+            let mut rewriter = Rewriter { functions: &functions, namespaces: &namespaces, public: &public, globals: &globals, scopes: vec![] };
+            for mut stmt in stator.program {
+                match &mut stmt {
+                    Stmt::UseStmt { .. } => continue,
+                    Stmt::FnDecl { name, params, body, .. } => {
+                        *name = qualify(*name);
+                        if let Some(body) = body {
+                            rewriter.scopes.push(params.iter().map(|param| param.name).collect());
+                            rewriter.block(body);
+                            rewriter.scopes.pop();
+                        }
+                    }
+                    Stmt::LetStmt { name, .. } => *name = qualify(*name),
+                    _ => {}
+                }
+                linked.push(stmt);
+            }
+        }
+        Ok(linked)
+    }
+}
+
+/// Renames calls and global references in a stator's function bodies
+/// to their linked names, leaving locals that shadow globals alone.
+struct Rewriter<'a> {
+    functions: &'a HashMap<Symbol, Symbol>,
+    // Imported stators, and the public functions that can be called through them
+    namespaces: &'a HashMap<String, String>,
+    public: &'a HashSet<String>,
+    globals: &'a HashMap<Symbol, Symbol>,
+    scopes: Vec<Vec<Symbol>>,
+}
+
+impl Rewriter<'_> {
+    fn global(&self, name: &mut Symbol) {
+        if self.scopes.iter().any(|scope| scope.contains(name)) {
+            return;
+        }
+        if let Some(linked) = self.globals.get(name) {
+            *name = *linked;
+        }
+    }
+
+    fn declare(&mut self, name: Symbol) {
+        self.scopes.last_mut().unwrap().push(name);
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        self.scopes.push(vec![]);
+        for stmt in block {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, value, .. } => {
+                self.expr(value);
+                self.declare(*name);
+            }
+            Stmt::AssignStmt { name, value, .. } => {
+                self.expr(value);
+                self.global(name);
+            }
+            Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+                self.expr(condition);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Stmt::WhileStmt { condition, body, .. } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Stmt::ForStmt { variable, iterable, body, .. } => {
+                self.expr(iterable);
+                self.scopes.push(vec![*variable]);
+                self.block(body);
+                self.scopes.pop();
+            }
+            Stmt::ReturnStmt { value: Some(value), .. } => self.expr(value),
+            Stmt::CallStmt(call) => self.expr(call),
+            Stmt::ReturnStmt { value: None, .. } | Stmt::UseStmt { .. } | Stmt::FnDecl { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Literal { .. } => {}
+            Expr::Variable { name, .. } => self.global(name),
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::UnaryOp { expr, .. } => self.expr(expr),
+            Expr::FnCall { name, args, .. } => {
+                if let Some(linked) = self.functions.get(name) {
+                    *name = *linked;
+                } else if let Some((stator, item)) = name.split_once('.') {
+                    if let Some(namespace) = self.namespaces.get(stator) {
+                        let linked = format!("{}.{}", namespace, item);
+                        if self.public.contains(&linked) {
+                            *name = Symbol::from(linked);
+                        }
+                    }
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+        }
+    }
+}
+
+/// Loads the program starting at `entry`, resolving stators against the
+/// directory it is in.
+pub fn load_program(entry: &Path) -> Result<Program, Vec<Error>> {
+    let mut loader = Loader::new(entry.parent().unwrap_or(Path::new(".")));
+    loader.load_entry(entry)?;
+    loader.link()
+}
+
+/// Like `load_program`, for an entry whose source is already in memory.
+pub fn load_source(entry: &Path, source: &str) -> Result<Program, Vec<Error>> {
+    let mut loader = Loader::new(entry.parent().unwrap_or(Path::new(".")));
+    loader.load_entry_source(entry, source)?;
+    loader.link()
+}
@@ -0,0 +1,2 @@
+pub mod loader;
+pub use loader::{Loader, Stator, load_program, load_source};
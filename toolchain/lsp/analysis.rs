@@ -0,0 +1,508 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// What the language server knows about one open document. Every change
+// re-analyses the whole text, which is plenty fast for files of the size
+// people write.
+//
+// The resolver walks the parse tree with scopes, like the type checker,
+// and links every name it sees to a definition: a local, a parameter, a
+// loop variable, a top-level function or value, or an imported item.
+// Imported items are defined in another stator, which is looked up the way
+// the loader would (the standard library, or a file under the project's
+// `src/`), so hover can show their signatures and go-to-definition can
+// open their file. Types of locals come from running the type checker over
+// the program linked with its imports.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::handle_error::Error;
+use crate::lexer::{lex, lex_lossless, Span, Token, TokenKind, TriviaKind};
+use crate::loader::Loader;
+use crate::parser::nodes::{walk_expr, walk_stmt, Block, Expr, FnParam, Program, Stmt, UseImports, Visitor};
+use crate::parser::{parse_program_recovering, TokenStream};
+use crate::project::find_manifest;
+use crate::stdlib;
+use crate::typeck::{check_program, lint_program, Type};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    Function,
+    Global,
+    Constant,
+    Local,
+    Param,
+    LoopVar,
+    // A name brought in by `use`, or an item reached through an imported stator
+    Import,
+    // A stator brought in by `use`, like `console` in `use std.io [console]`
+    Stator,
+}
+
+/// Where an item from another stator is declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct External {
+    pub stator: String,
+    /// `None` for the standard library, which has no files on disk.
+    pub file: Option<PathBuf>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub kind: DefKind,
+    /// The name where it is declared in this document, if it is declared here.
+    pub span: Option<Span>,
+    /// The whole declaration, e.g. a function with its body.
+    pub full: Option<Span>,
+    /// The declaration as shown on hover, e.g. `fn add(a: i32, b: i32) i32`.
+    pub detail: String,
+    pub external: Option<External>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub span: Span,
+    pub def: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct Analysis {
+    pub text: String,
+    pub lines: LineIndex,
+    pub diagnostics: Vec<Diagnostic>,
+    pub definitions: Vec<Definition>,
+    /// Every use of a name, declarations included, in source order.
+    pub references: Vec<Reference>,
+    /// The directory the document's imports are resolved against.
+    pub root: PathBuf,
+}
+
+/// Converts between byte offsets and LSP positions (0-based lines, UTF-16 columns).
+pub struct LineIndex {
+    starts: Vec<usize>,
+    text: String,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(offset, _)| offset + 1));
+        LineIndex { starts, text: text.to_string() }
+    }
+
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.starts[line];
+        let column = self.text.get(start..offset).map_or(0, |text| text.encode_utf16().count());
+        (line, column)
+    }
+
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let Some(&start) = self.starts.get(line) else { return self.text.len() };
+        let end = self.starts.get(line + 1).copied().unwrap_or(self.text.len());
+        let mut units = 0;
+        for (index, ch) in self.text[start..end].char_indices() {
+            if units >= column || ch == '\n' {
+                return start + index;
+            }
+            units += ch.len_utf16();
+        }
+        end
+    }
+
+    /// The offset of a position as the lexer counts it: 1-based, with a
+    /// tab taking four columns. Position 0:0 means the end of the file.
+    pub fn lexer_offset(&self, line: usize, column: usize) -> usize {
+        let Some(&start) = line.checked_sub(1).and_then(|line| self.starts.get(line)) else { return self.text.len() };
+        let mut current = 1;
+        for (index, byte) in self.text.as_bytes()[start..].iter().enumerate() {
+            if current >= column || *byte == b'\n' {
+                return start + index;
+            }
+            current += if *byte == b'\t' { 4 } else { 1 };
+        }
+        self.text.len()
+    }
+}
+
+fn type_name(kind: TokenKind) -> String {
+    Type::from_kind(kind).map_or(kind.to_string(), |ty| ty.to_string())
+}
+
+pub(crate) fn signature(name: &str, params: &[FnParam], return_ty: Option<TokenKind>, public: bool) -> String {
+    let params: Vec<String> = params.iter().map(|param| format!("{}: {}", param.name, type_name(param.ty))).collect();
+    let ret = return_ty.map_or(String::new(), |ty| format!(" {}", type_name(ty)));
+    format!("{}fn {}({}){}", if public { "pub " } else { "" }, name, params.join(", "), ret)
+}
+
+/// The directory stator paths are resolved against for `file`.
+fn stator_root(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    match find_manifest(&dir) {
+        Some(manifest) => manifest.parent().unwrap_or(Path::new(".")).join("src"),
+        None => dir,
+    }
+}
+
+/// The source of the stator `stator`, with its file unless it is in the
+/// standard library.
+pub(crate) fn stator_source(root: &Path, stator: &str) -> Option<(Option<PathBuf>, String)> {
+    match stdlib::source(stator) {
+        Some(source) => Some((None, source.to_string())),
+        None => {
+            let file = Loader::new(root).stator_file(stator);
+            let source = std::fs::read_to_string(&file).ok()?;
+            Some((Some(file), source))
+        }
+    }
+}
+
+/// Finds the public function `item` of the stator `stator`.
+fn find_external(root: &Path, stator: &str, item: &str) -> Option<(Definition, External)> {
+    let (file, source) = stator_source(root, stator)?;
+    let lexed = lex(&source);
+    let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+    let (program, _) = parse_program_recovering(&mut stream);
+    let tokens = lex(&source).tokens;
+    program.iter().find_map(|stmt| match stmt {
+        Stmt::FnDecl { name, params, return_ty, public: true, span, .. } if name == item => {
+            let external = External { stator: stator.to_string(), file: file.clone(), span: name_after(&tokens, span.start, name) };
+            let definition = Definition {
+                name: item.to_string(),
+                kind: DefKind::Import,
+                span: None,
+                full: None,
+                detail: format!("{}\n\nfrom `{}`", signature(name, params, *return_ty, true), stator),
+                external: Some(external.clone()),
+            };
+            Some((definition, external))
+        }
+        _ => None,
+    })
+}
+
+/// The span of the first identifier `name` at or after `offset`.
+fn name_after(tokens: &[Token], offset: usize, name: &str) -> Span {
+    let from = tokens.partition_point(|token| token.span().start < offset);
+    tokens[from..]
+        .iter()
+        .find(|token| token.kind == TokenKind::Identifier && token.text == name)
+        .map_or(Span { start: offset, end: offset + name.len(), ..Span::default() }, Token::span)
+}
+
+struct Resolver<'a> {
+    tokens: &'a [Token<'a>],
+    root: PathBuf,
+    definitions: Vec<Definition>,
+    references: Vec<Reference>,
+    // Top-level functions and values, and imported names
+    items: HashMap<String, usize>,
+    // Imported stators, by the name they were imported as
+    namespaces: HashMap<String, String>,
+    // Items reached through a namespace, by qualified name
+    externals: HashMap<String, Option<usize>>,
+    scopes: Vec<HashMap<String, usize>>,
+    // Types the type checker found for `let`s, by the offset of the statement
+    types: HashMap<usize, Type>,
+}
+
+impl Resolver<'_> {
+    fn define(&mut self, definition: Definition) -> usize {
+        let index = self.definitions.len();
+        if let Some(span) = definition.span {
+            self.references.push(Reference { span, def: index });
+        }
+        self.definitions.push(definition);
+        index
+    }
+
+    fn local(&mut self, name: &str, kind: DefKind, at: usize, detail: String) {
+        let span = name_after(self.tokens, at, name);
+        let index = self.define(Definition { name: name.to_string(), kind, span: Some(span), full: None, detail, external: None });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), index);
+        }
+    }
+
+    fn refer(&mut self, name: &str, span: Span) {
+        let def = self.scopes.iter().rev().find_map(|scope| scope.get(name)).or_else(|| self.items.get(name)).copied();
+        if let Some(def) = def {
+            self.references.push(Reference { span: Span { end: span.start + name.len(), ..span }, def });
+        }
+    }
+
+    /// Resolves a call through an imported stator, like `console.out`.
+    fn refer_external(&mut self, name: &str, span: Span) {
+        let Some((namespace, item)) = name.rsplit_once('.') else { return };
+        let (head, rest) = namespace.split_once('.').map_or((namespace, ""), |(head, rest)| (head, rest));
+        let Some(stator) = self.namespaces.get(head) else { return };
+        if let Some(&def) = self.items.get(head) {
+            self.references.push(Reference { span: Span { end: span.start + head.len(), ..span }, def });
+        }
+        let stator = if rest.is_empty() { stator.clone() } else { format!("{}.{}", stator, rest) };
+        let qualified = format!("{}.{}", stator, item);
+        let def = match self.externals.get(&qualified) {
+            Some(def) => *def,
+            None => {
+                let def = find_external(&self.root, &stator, item).map(|(definition, _)| self.define(definition));
+                self.externals.insert(qualified, def);
+                def
+            }
+        };
+        if let Some(def) = def {
+            // Only the last segment names the item
+            let start = span.start + namespace.len() + 1;
+            self.references.push(Reference { span: Span { start, end: start + item.len(), ..span }, def });
+        }
+    }
+
+    fn top_level(&mut self, program: &Program) {
+        for stmt in program {
+            match stmt {
+                Stmt::FnDecl { name, params, return_ty, public, span, .. } => {
+                    let definition = Definition {
+                        name: name.to_string(),
+                        kind: DefKind::Function,
+                        span: Some(name_after(self.tokens, span.start, name)),
+                        full: Some(*span),
+                        detail: signature(name, params, *return_ty, *public),
+                        external: None,
+                    };
+                    let index = self.define(definition);
+                    self.items.insert(name.to_string(), index);
+                }
+                Stmt::LetStmt { name, ty, constant, span, .. } => {
+                    let ty = ty.map(type_name).or_else(|| self.types.get(&span.start).map(Type::to_string));
+                    let keyword = if *constant { "const" } else { "let" };
+                    let definition = Definition {
+                        name: name.to_string(),
+                        kind: if *constant { DefKind::Constant } else { DefKind::Global },
+                        span: Some(name_after(self.tokens, span.start, name)),
+                        full: Some(*span),
+                        detail: format!("{} {}{}", keyword, name, ty.map_or(String::new(), |ty| format!(": {}", ty))),
+                        external: None,
+                    };
+                    let index = self.define(definition);
+                    self.items.insert(name.to_string(), index);
+                }
+                Stmt::UseStmt { stator, imports: UseImports::List(names), span } => {
+                    // Look for the names after the `[`, not in the stator path
+                    let list = self.tokens.iter().find(|token| token.kind == TokenKind::LSquare && token.span().start > span.start);
+                    let list_start = list.map_or(span.start, |token| token.span().start);
+                    for name in names {
+                        let name_span = name_after(self.tokens, list_start, name);
+                        let qualified = format!("{}.{}", stator, name);
+                        let (kind, detail, external) = match find_external(&self.root, stator, name) {
+                            Some((definition, external)) => (DefKind::Import, definition.detail, Some(external)),
+                            None if stdlib::source(&qualified).is_some() || Loader::new(&self.root).stator_file(&qualified).is_file() => {
+                                self.namespaces.insert(name.to_string(), qualified.clone());
+                                (DefKind::Stator, format!("stator {}", qualified), None)
+                            }
+                            None => (DefKind::Import, format!("{} (not found in `{}`)", name, stator), None),
+                        };
+                        let definition = Definition { name: name.to_string(), kind, span: Some(name_span), full: Some(*span), detail, external };
+                        let index = self.define(definition);
+                        self.items.insert(name.to_string(), index);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Visitor for Resolver<'_> {
+    fn visit_block(&mut self, block: &Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in block {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            // Top-level values were defined by `top_level`
+            Stmt::LetStmt { name, ty, constant, span, .. } if !self.scopes.is_empty() => {
+                walk_stmt(self, stmt);
+                let ty = ty.map(type_name).or_else(|| self.types.get(&span.start).map(Type::to_string));
+                let keyword = if *constant { "const" } else { "let" };
+                let detail = format!("{} {}{}", keyword, name, ty.map_or(String::new(), |ty| format!(": {}", ty)));
+                self.local(name, DefKind::Local, span.start, detail);
+            }
+            Stmt::FnDecl { .. } => {
+                self.scopes.push(HashMap::new());
+                walk_stmt(self, stmt);
+                self.scopes.pop();
+            }
+            Stmt::ForStmt { variable, iterable, body, span } => {
+                self.visit_expr(iterable);
+                self.scopes.push(HashMap::new());
+                self.local(variable, DefKind::LoopVar, span.start, format!("{}: i32", variable));
+                self.visit_block(body);
+                self.scopes.pop();
+            }
+            Stmt::AssignStmt { name, span, .. } => {
+                self.refer(name, *span);
+                walk_stmt(self, stmt);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_param(&mut self, param: &FnParam) {
+        self.local(&param.name, DefKind::Param, param.span.start, format!("{}: {}", param.name, type_name(param.ty)));
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable { name, span, .. } => self.refer(name, *span),
+            Expr::FnCall { name, span, .. } => {
+                if name.contains('.') {
+                    self.refer_external(name, *span);
+                } else {
+                    self.refer(name, *span);
+                }
+                walk_expr(self, expr);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+/// The types of the `let`s in `program`, by statement offset. The program
+/// is linked with its imports first, so calls into other stators have
+/// types; if that fails it is checked on its own.
+fn let_types(file: &Path, text: &str, program: &Program, root: &Path) -> HashMap<usize, Type> {
+    struct Collect(HashMap<usize, Type>);
+    impl Visitor for Collect {
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            match stmt {
+                // Other stators' items are qualified, and their offsets are
+                // in other files
+                Stmt::FnDecl { name, .. } if name.contains('.') => {}
+                Stmt::LetStmt { name, .. } if name.contains('.') => {}
+                Stmt::LetStmt { ty: Some(ty), span, .. } => {
+                    if let Some(ty) = Type::from_kind(*ty) {
+                        self.0.insert(span.start, ty);
+                    }
+                }
+                _ => walk_stmt(self, stmt),
+            }
+        }
+    }
+
+    let mut loader = Loader::new(root);
+    let linked = loader.load_entry_source(file, text).ok().and_then(|_| loader.link().ok());
+    let mut checked = linked.unwrap_or_else(|| program.clone());
+    let _ = check_program(&mut checked);
+    let mut collect = Collect(HashMap::new());
+    collect.visit_program(&checked);
+    collect.0
+}
+
+impl Analysis {
+    pub fn new(file: &Path, text: &str) -> Self {
+        let lines = LineIndex::new(text);
+        let mut diagnostics = vec![];
+
+        // Lexer errors, found again from the lossless tokens so they have offsets
+        let lossless = lex_lossless(text);
+        let mut offset = 0;
+        for token in &lossless.tokens {
+            for trivia in &token.leading {
+                if trivia.kind == TriviaKind::Skipped {
+                    let message = format!("Invalid token: {}", trivia.text);
+                    diagnostics.push(Diagnostic { severity: Severity::Error, message, start: offset, end: offset + trivia.text.len() });
+                }
+                offset += trivia.text.len();
+            }
+            if token.kind == TokenKind::String && (token.text.len() < 2 || !token.text.ends_with('"')) {
+                let message = "Unterminated string".to_string();
+                diagnostics.push(Diagnostic { severity: Severity::Error, message, start: offset, end: offset + token.text.len() });
+            }
+            offset += token.text.len();
+            for trivia in &token.trailing {
+                if trivia.kind == TriviaKind::Skipped {
+                    let message = format!("Invalid token: {}", trivia.text);
+                    diagnostics.push(Diagnostic { severity: Severity::Error, message, start: offset, end: offset + trivia.text.len() });
+                }
+                offset += trivia.text.len();
+            }
+        }
+        for trivia in &lossless.eof {
+            if trivia.kind == TriviaKind::Skipped {
+                let message = format!("Invalid token: {}", trivia.text);
+                diagnostics.push(Diagnostic { severity: Severity::Error, message, start: offset, end: offset + trivia.text.len() });
+            }
+            offset += trivia.text.len();
+        }
+
+        let tokens: Vec<Token> = lex(text).tokens.into_iter().filter(|token| token.kind != TokenKind::Newline).collect();
+        let lexed = lex(text);
+        let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+        let (program, errors) = parse_program_recovering(&mut stream);
+        let mut push = |severity: Severity, error: &Error| {
+            let start = lines.lexer_offset(error.line(), error.column());
+            // Point at the token the error is about
+            let end = tokens.iter().find(|token| token.span().start == start).map_or(start + 1, |token| token.span().end);
+            diagnostics.push(Diagnostic { severity, message: error.message().to_string(), start, end: end.min(text.len()) });
+        };
+        for error in &errors {
+            push(Severity::Error, error);
+        }
+        for warning in lint_program(&program) {
+            push(Severity::Warning, &warning);
+        }
+
+        let root = stator_root(file);
+        let types = let_types(file, text, &program, &root);
+        let mut resolver = Resolver {
+            tokens: &tokens,
+            root: root.clone(),
+            definitions: vec![],
+            references: vec![],
+            items: HashMap::new(),
+            namespaces: HashMap::new(),
+            externals: HashMap::new(),
+            scopes: vec![],
+            types,
+        };
+        resolver.top_level(&program);
+        resolver.visit_program(&program);
+        let definitions = resolver.definitions;
+        let mut references = resolver.references;
+        references.sort_by_key(|reference| reference.span.start);
+
+        Analysis { text: text.to_string(), lines, diagnostics, definitions, references, root }
+    }
+
+    /// The reference (or declaration) at `offset`, if there is one.
+    pub fn reference_at(&self, offset: usize) -> Option<Reference> {
+        self.references.iter().find(|reference| reference.span.start <= offset && offset <= reference.span.end).copied()
+    }
+
+    pub fn references_to(&self, def: usize) -> impl Iterator<Item = &Reference> {
+        self.references.iter().filter(move |reference| reference.def == def)
+    }
+
+    /// The top-level definitions, for the outline.
+    pub fn symbols(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.iter().filter(|definition| definition.full.is_some() && !matches!(definition.kind, DefKind::Import | DefKind::Stator))
+    }
+}
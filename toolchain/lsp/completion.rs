@@ -0,0 +1,319 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Completion. Code being typed rarely parses, and the recovering parser
+// drops the whole function around an error, so the context of the cursor
+// is worked out from tokens alone: what comes right before it, which
+// scopes it is in, and what each of them declared so far. The analysis of
+// the last version that parsed only adds details, like signatures and
+// types, where it has them.
+//
+// What is offered depends on where the cursor is:
+//   - in a `use` list, the public items and stators the stator exports;
+//   - after `.`, the members of an imported stator (`console.` offers
+//     `out`), or the stators under a path being written after `use`;
+//   - after `:`, the types;
+//   - anywhere else, the names in scope, and the keywords that can start
+//     a statement if the cursor is at the start of one.
+use std::path::Path;
+use crate::lexer::{lex, Token, TokenKind, KEYWORDS};
+use crate::loader::Loader;
+use crate::lsp::analysis::{signature, stator_source, Analysis};
+use crate::parser::nodes::Stmt;
+use crate::parser::{parse_program_recovering, TokenStream};
+use crate::stdlib;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Type,
+    Variable,
+    Constant,
+    Function,
+    Stator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: String,
+}
+
+impl Completion {
+    fn new(label: &str, kind: CompletionKind, detail: impl Into<String>) -> Self {
+        Completion { label: label.to_string(), kind, detail: detail.into() }
+    }
+}
+
+const TOP_LEVEL_KEYWORDS: &[&str] = &["fn", "pub", "use", "let", "const"];
+const BLOCK_KEYWORDS: &[&str] = &["let", "const", "if", "for", "while", "return"];
+const TYPES: &[&str] = &["i32", "bool", "str"];
+
+fn is_word(token: &Token) -> bool {
+    token.kind == TokenKind::Identifier || KEYWORDS.iter().any(|(word, _)| *word == token.text)
+}
+
+/// The stators directly under `path` (the top-level ones for `""`).
+fn child_stators(root: &Path, path: &str) -> Vec<Completion> {
+    let prefix = if path.is_empty() { String::new() } else { format!("{}.", path) };
+    let mut names: Vec<String> = stdlib::stators()
+        .filter_map(|name| name.strip_prefix(prefix.as_str()))
+        .map(|rest| rest.split('.').next().unwrap_or(rest).to_string())
+        .collect();
+    // Stators on disk are files, and the directories next to them
+    let dir = if path.is_empty() { root.to_path_buf() } else { Loader::new(root).stator_file(path).with_extension("") };
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let file = entry.path();
+        if file.extension().is_some_and(|extension| extension == "rt") {
+            names.extend(file.file_stem().map(|stem| stem.to_string_lossy().to_string()));
+        }
+    }
+    names.sort();
+    names.dedup();
+    names.iter().map(|name| Completion::new(name, CompletionKind::Stator, format!("stator {}{}", prefix, name))).collect()
+}
+
+/// What `use stator [..]` can import: its public functions and the stators under it.
+fn stator_members(root: &Path, stator: &str) -> Vec<Completion> {
+    let mut members = vec![];
+    if let Some((_, source)) = stator_source(root, stator) {
+        let lexed = lex(&source);
+        let (program, _) = parse_program_recovering(&mut TokenStream::with_eof(lexed.tokens, lexed.eof));
+        for stmt in &program {
+            if let Stmt::FnDecl { name, params, return_ty, public: true, .. } = stmt {
+                members.push(Completion::new(name, CompletionKind::Function, signature(name, params, *return_ty, true)));
+            }
+        }
+    }
+    members.extend(child_stators(root, stator));
+    members
+}
+
+/// The dotted path ending just before `end` (exclusive), like `std.io`,
+/// and the index of its first token.
+fn path_before(tokens: &[&Token], end: usize) -> (String, usize) {
+    let mut start = end;
+    while start > 0 && tokens[start - 1].kind == TokenKind::Identifier {
+        start -= 1;
+        if start > 0 && tokens[start - 1].kind == TokenKind::Dot {
+            start -= 1;
+        } else {
+            break;
+        }
+    }
+    let path: String = tokens[start..end].iter().map(|token| token.text).collect();
+    (path, start)
+}
+
+/// A name declared so far, with the offset it was declared at.
+struct Declared {
+    name: String,
+    kind: CompletionKind,
+    at: usize,
+}
+
+/// The imports of the document: the stator of each `use`, and the names it lists.
+fn imports<'a, 'src>(tokens: &[&'a Token<'src>]) -> Vec<(String, Vec<&'a Token<'src>>)> {
+    let mut imports = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Use {
+            continue;
+        }
+        let mut end = index + 1;
+        while tokens.get(end).is_some_and(|token| matches!(token.kind, TokenKind::Identifier | TokenKind::Dot)) {
+            end += 1;
+        }
+        let stator: String = tokens[index + 1..end].iter().map(|token| token.text).collect();
+        let mut names = vec![];
+        if tokens.get(end).is_some_and(|token| token.kind == TokenKind::LSquare) {
+            for token in &tokens[end + 1..] {
+                match token.kind {
+                    TokenKind::Identifier => names.push(*token),
+                    TokenKind::Comma => {}
+                    _ => break,
+                }
+            }
+        }
+        imports.push((stator, names));
+    }
+    imports
+}
+
+/// The names visible at `offset`: everything at the top level, and what
+/// the blocks around `offset` declared before it.
+fn in_scope(tokens: &[&Token], offset: usize, root: &Path) -> (Vec<Declared>, usize) {
+    let mut top_level = vec![];
+    let mut scopes: Vec<Vec<Declared>> = vec![];
+    // Parameters and loop variables belong to the next block
+    let mut pending = vec![];
+    let mut depth: usize = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        let before = token.span().end <= offset;
+        let next = tokens.get(index + 1).filter(|next| next.kind == TokenKind::Identifier);
+        match token.kind {
+            TokenKind::LCurly => {
+                depth += 1;
+                if before {
+                    scopes.push(std::mem::take(&mut pending));
+                }
+            }
+            TokenKind::RCurly => {
+                depth = depth.saturating_sub(1);
+                if before {
+                    scopes.pop();
+                }
+            }
+            TokenKind::Fn if depth == 0 => {
+                let Some(name) = next else { continue };
+                top_level.push(Declared { name: name.text.to_string(), kind: CompletionKind::Function, at: name.span().start });
+                if !before {
+                    continue;
+                }
+                // The parameters: every name followed by `:` up to the `)`
+                pending.clear();
+                for (param, colon) in tokens[index + 1..].iter().zip(&tokens[index + 2..]) {
+                    if param.kind == TokenKind::RParen || param.span().end > offset {
+                        break;
+                    }
+                    if param.kind == TokenKind::Identifier && colon.kind == TokenKind::Colon {
+                        pending.push(Declared { name: param.text.to_string(), kind: CompletionKind::Variable, at: param.span().start });
+                    }
+                }
+            }
+            TokenKind::Let | TokenKind::Const => {
+                let Some(name) = next else { continue };
+                let kind = if token.kind == TokenKind::Const { CompletionKind::Constant } else { CompletionKind::Variable };
+                let declared = Declared { name: name.text.to_string(), kind, at: name.span().start };
+                match scopes.last_mut() {
+                    Some(scope) if before && depth > 0 => scope.push(declared),
+                    _ if depth == 0 => top_level.push(declared),
+                    _ => {}
+                }
+            }
+            TokenKind::For if before => {
+                if let Some(name) = next {
+                    pending = vec![Declared { name: name.text.to_string(), kind: CompletionKind::Variable, at: name.span().start }];
+                }
+            }
+            _ => {}
+        }
+    }
+    for (stator, names) in imports(tokens) {
+        for name in names {
+            let qualified = format!("{}.{}", stator, name.text);
+            let stator = stdlib::source(&qualified).is_some() || Loader::new(root).stator_file(&qualified).is_file();
+            let kind = if stator { CompletionKind::Stator } else { CompletionKind::Function };
+            top_level.push(Declared { name: name.text.to_string(), kind, at: name.span().start });
+        }
+    }
+    let depth = scopes.len();
+    // Inner declarations shadow outer ones
+    let mut names: Vec<Declared> = vec![];
+    for declared in scopes.into_iter().rev().flatten().chain(top_level) {
+        // Not the name being declared at the cursor
+        let at_cursor = declared.at < offset && offset <= declared.at + declared.name.len();
+        if !at_cursor && !names.iter().any(|other| other.name == declared.name) {
+            names.push(declared);
+        }
+    }
+    (names, depth)
+}
+
+/// What could be written at `offset`, filtered by the word being typed there.
+pub fn complete(analysis: &Analysis, offset: usize) -> Vec<Completion> {
+    let lexed = lex(&analysis.text);
+    let tokens: Vec<&Token> = lexed.tokens.iter().filter(|token| token.kind != TokenKind::Newline).collect();
+
+    // The word being typed, which is replaced by the completion
+    let (prefix, before) = match tokens.iter().position(|token| token.span().start < offset && offset <= token.span().end) {
+        Some(index) if is_word(tokens[index]) => {
+            let token = tokens[index];
+            (&token.text[..offset - token.span().start], index)
+        }
+        _ => ("", tokens.partition_point(|token| token.span().end <= offset)),
+    };
+    let previous = before.checked_sub(1).map(|index| tokens[index]);
+
+    let mut completions = if let Some((stator, listed)) = use_list(&tokens, before) {
+        let mut members = stator_members(&analysis.root, &stator);
+        members.retain(|member| !listed.contains(&member.label));
+        members
+    } else {
+        complete_after(analysis, &tokens, offset, before, previous)
+    };
+    completions.retain(|completion| completion.label.starts_with(prefix));
+    completions
+}
+
+fn complete_after(analysis: &Analysis, tokens: &[&Token], offset: usize, before: usize, previous: Option<&Token>) -> Vec<Completion> {
+    let root = analysis.root.as_path();
+    match previous.map(|token| token.kind) {
+        Some(TokenKind::Dot) => {
+            let (path, start) = path_before(tokens, before - 1);
+            let after_use = start > 0 && tokens[start - 1].kind == TokenKind::Use;
+            if after_use {
+                child_stators(root, &path)
+            } else {
+                // The head of the path is an imported stator
+                let (head, rest) = path.split_once('.').unwrap_or((path.as_str(), ""));
+                let stator = imports(tokens)
+                    .into_iter()
+                    .find(|(_, names)| names.iter().any(|name| name.text == head))
+                    .map(|(stator, _)| format!("{}.{}", stator, head));
+                match stator {
+                    Some(stator) if rest.is_empty() => stator_members(root, &stator),
+                    Some(stator) => stator_members(root, &format!("{}.{}", stator, rest)),
+                    None => vec![],
+                }
+            }
+        }
+        Some(TokenKind::Use) => child_stators(root, ""),
+        Some(TokenKind::Colon) => TYPES.iter().map(|ty| Completion::new(ty, CompletionKind::Type, "type")).collect(),
+        previous => {
+            let (names, depth) = in_scope(tokens, offset, root);
+            let mut completions: Vec<Completion> = names
+                .into_iter()
+                .map(|declared| {
+                    let detail = analysis
+                        .reference_at(declared.at)
+                        .filter(|reference| reference.span.start == declared.at)
+                        .map(|reference| analysis.definitions[reference.def].detail.clone())
+                        .unwrap_or_default();
+                    // Only the declaration, without notes like where it came from
+                    let detail = detail.split("\n\n").next().unwrap_or_default();
+                    Completion::new(&declared.name, declared.kind, detail)
+                })
+                .collect();
+            let statement_start = matches!(previous, None | Some(TokenKind::Semicolon | TokenKind::LCurly | TokenKind::RCurly | TokenKind::RSquare));
+            if statement_start {
+                let keywords = if depth == 0 { TOP_LEVEL_KEYWORDS } else { BLOCK_KEYWORDS };
+                completions.extend(keywords.iter().map(|word| Completion::new(word, CompletionKind::Keyword, "keyword")));
+                if previous == Some(TokenKind::RCurly) && depth > 0 {
+                    completions.push(Completion::new("else", CompletionKind::Keyword, "keyword"));
+                }
+            } else {
+                completions.extend(["true", "false"].iter().map(|word| Completion::new(word, CompletionKind::Keyword, "bool")));
+            }
+            completions
+        }
+    }
+}
+
+/// If the cursor, before token `before`, is inside `use stator [..`, the
+/// stator and the names already listed.
+fn use_list(tokens: &[&Token], before: usize) -> Option<(String, Vec<String>)> {
+    let mut index = before;
+    let mut listed = vec![];
+    loop {
+        index = index.checked_sub(1)?;
+        match tokens[index].kind {
+            TokenKind::Identifier => listed.push(tokens[index].text.to_string()),
+            TokenKind::Comma => {}
+            TokenKind::LSquare => break,
+            _ => return None,
+        }
+    }
+    let (stator, start) = path_before(tokens, index);
+    (start > 0 && tokens[start - 1].kind == TokenKind::Use && !stator.is_empty()).then_some((stator, listed))
+}
@@ -0,0 +1,399 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// A language server speaking JSON-RPC over any reader and writer, which
+// `rotor lsp` connects to stdin and stdout. Messages are framed with a
+// `Content-Length` header, as the protocol requires.
+//
+// Documents are synced in full: every open and change re-analyses the text
+// and publishes its diagnostics, so the editor always shows the current
+// lexer and parser errors and lint warnings. Requests for symbols, hover,
+// definitions and references are answered from the last analysis.
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use crate::lexer::Span;
+use crate::lsp::analysis::{Analysis, DefKind, LineIndex, Severity};
+use crate::lsp::completion::{complete, CompletionKind};
+use crate::lsp::json::Json;
+use crate::lsp::semantic::{semantic_tokens, TOKEN_MODIFIERS, TOKEN_TYPES};
+
+// JSON-RPC error codes
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+
+// LSP symbol kinds
+const SYMBOL_FUNCTION: usize = 12;
+const SYMBOL_VARIABLE: usize = 13;
+const SYMBOL_CONSTANT: usize = 14;
+
+// LSP completion item kinds
+const COMPLETION_FUNCTION: usize = 3;
+const COMPLETION_VARIABLE: usize = 6;
+const COMPLETION_MODULE: usize = 9;
+const COMPLETION_KEYWORD: usize = 14;
+const COMPLETION_CONSTANT: usize = 21;
+const COMPLETION_TYPE: usize = 25;
+
+// The largest body read, so a bogus header can't make us allocate anything
+const MAX_CONTENT_LENGTH: usize = 64 << 20;
+
+/// Reads one message, or `None` at the end of the input. A body that isn't
+/// JSON is an `Err` with the reason; the messages after it can still be read.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Result<Json, String>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Message without a Content-Length header"));
+    };
+    if length > MAX_CONTENT_LENGTH {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Message of {} bytes is too large", length)));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    let body = match String::from_utf8(body) {
+        Ok(body) => body,
+        Err(error) => return Ok(Some(Err(error.to_string()))),
+    };
+    Ok(Some(Json::parse(&body)))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    // Undo percent-encoding, which editors use for spaces and the like
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn position(lines: &LineIndex, offset: usize) -> Json {
+    let (line, character) = lines.position(offset);
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+fn range(lines: &LineIndex, start: usize, end: usize) -> Json {
+    Json::object([("start", position(lines, start)), ("end", position(lines, end))])
+}
+
+fn location(uri: &str, lines: &LineIndex, span: Span) -> Json {
+    Json::object([("uri", uri.into()), ("range", range(lines, span.start, span.end))])
+}
+
+pub struct Server {
+    documents: HashMap<String, Analysis>,
+    shutdown: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server { documents: HashMap::new(), shutdown: false }
+    }
+
+    /// Serves requests until the client says `exit` or closes the input.
+    /// Returns the exit code the protocol asks for.
+    pub fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<i32> {
+        while let Some(message) = read_message(input)? {
+            let message = match message {
+                Ok(message) => message,
+                Err(reason) => {
+                    // There is no id to answer to, so the reply has a null one
+                    let error = Json::object([("code", Json::Number(PARSE_ERROR)), ("message", format!("Parse error: {}", reason).into())]);
+                    write_message(output, &Json::object([("jsonrpc", "2.0".into()), ("id", Json::Null), ("error", error)]))?;
+                    continue;
+                }
+            };
+            if message["method"].as_str() == Some("exit") {
+                return Ok(if self.shutdown { 0 } else { 1 });
+            }
+            for reply in self.handle(&message) {
+                write_message(output, &reply)?;
+            }
+        }
+        Ok(if self.shutdown { 0 } else { 1 })
+    }
+
+    /// The responses and notifications a message leads to.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => Ok(Json::object([
+                ("capabilities", Json::object([
+                    ("textDocumentSync", 1.into()),
+                    ("documentSymbolProvider", true.into()),
+                    ("hoverProvider", true.into()),
+                    ("definitionProvider", true.into()),
+                    ("referencesProvider", true.into()),
+                    ("completionProvider", Json::object([("triggerCharacters", vec![Json::from("."), Json::from("[")].into())])),
+                    ("semanticTokensProvider", Json::object([
+                        ("legend", Json::object([
+                            ("tokenTypes", TOKEN_TYPES.iter().map(|&name| name.into()).collect::<Vec<Json>>().into()),
+                            ("tokenModifiers", TOKEN_MODIFIERS.iter().map(|&name| name.into()).collect::<Vec<Json>>().into()),
+                        ])),
+                        ("full", true.into()),
+                    ])),
+                ])),
+                ("serverInfo", Json::object([("name", "rotor".into())])),
+            ])),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                return self.update(document["uri"].as_str().unwrap_or_default(), document["text"].as_str().unwrap_or_default());
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // With full sync the last change holds the whole text
+                match params["contentChanges"].as_array().last().and_then(|change| change["text"].as_str()) {
+                    Some(text) => return self.update(uri, text),
+                    None => return vec![],
+                }
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                return vec![publish(uri, vec![])];
+            }
+            "textDocument/documentSymbol" => Ok(self.symbols(params)),
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/definition" => Ok(self.definition(params)),
+            "textDocument/references" => Ok(self.references(params)),
+            "textDocument/completion" => Ok(self.completion(params)),
+            "textDocument/semanticTokens/full" => Ok(self.semantic_tokens(params)),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+        };
+
+        // Notifications get no reply, not even for methods we don't know
+        let Some(id) = id else { return vec![] };
+        let result = if method.is_empty() { Err((INVALID_REQUEST, "Request without a method".to_string())) } else { result };
+        let reply = match result {
+            Ok(result) => Json::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+            Err((code, message)) => {
+                let error = Json::object([("code", Json::Number(code)), ("message", message.into())]);
+                Json::object([("jsonrpc", "2.0".into()), ("id", id), ("error", error)])
+            }
+        };
+        vec![reply]
+    }
+
+    fn update(&mut self, uri: &str, text: &str) -> Vec<Json> {
+        let analysis = Analysis::new(&uri_to_path(uri), text);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                };
+                Json::object([
+                    ("range", range(&analysis.lines, diagnostic.start, diagnostic.end)),
+                    ("severity", severity.into()),
+                    ("source", "rotor".into()),
+                    ("message", diagnostic.message.as_str().into()),
+                ])
+            })
+            .collect();
+        self.documents.insert(uri.to_string(), analysis);
+        vec![publish(uri, diagnostics)]
+    }
+
+    /// The document and offset a request points at.
+    fn at<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a Analysis, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let analysis = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        Some((uri, analysis, analysis.lines.offset(line, character)))
+    }
+
+    fn symbols(&self, params: &Json) -> Json {
+        let Some(analysis) = params["textDocument"]["uri"].as_str().and_then(|uri| self.documents.get(uri)) else {
+            return Json::Null;
+        };
+        let symbols = analysis
+            .symbols()
+            .filter_map(|definition| {
+                let (span, full) = (definition.span?, definition.full?);
+                let kind = match definition.kind {
+                    DefKind::Function => SYMBOL_FUNCTION,
+                    DefKind::Constant => SYMBOL_CONSTANT,
+                    _ => SYMBOL_VARIABLE,
+                };
+                Some(Json::object([
+                    ("name", definition.name.as_str().into()),
+                    ("detail", definition.detail.as_str().into()),
+                    ("kind", kind.into()),
+                    ("range", range(&analysis.lines, full.start, full.end)),
+                    ("selectionRange", range(&analysis.lines, span.start, span.end)),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        symbols.into()
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let Some((_, analysis, offset)) = self.at(params) else { return Json::Null };
+        let Some(reference) = analysis.reference_at(offset) else { return Json::Null };
+        let definition = &analysis.definitions[reference.def];
+        let (code, rest) = definition.detail.split_once("\n\n").map_or((definition.detail.as_str(), ""), |(code, rest)| (code, rest));
+        let mut value = format!("```rotor\n{}\n```", code);
+        if !rest.is_empty() {
+            value.push_str("\n\n");
+            value.push_str(rest);
+        }
+        Json::object([
+            ("contents", Json::object([("kind", "markdown".into()), ("value", value.into())])),
+            ("range", range(&analysis.lines, reference.span.start, reference.span.end)),
+        ])
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let Some((uri, analysis, offset)) = self.at(params) else { return Json::Null };
+        let Some(reference) = analysis.reference_at(offset) else { return Json::Null };
+        let definition = &analysis.definitions[reference.def];
+        match (&definition.external, definition.span) {
+            // Imported items live in the file of their stator, if it has one
+            (Some(external), _) => match &external.file {
+                Some(file) => {
+                    let text = std::fs::read_to_string(file).unwrap_or_default();
+                    location(&path_to_uri(file), &LineIndex::new(&text), external.span)
+                }
+                None => Json::Null,
+            },
+            (None, Some(span)) => location(uri, &analysis.lines, span),
+            (None, None) => Json::Null,
+        }
+    }
+
+    fn references(&self, params: &Json) -> Json {
+        let Some((uri, analysis, offset)) = self.at(params) else { return Json::Null };
+        let Some(reference) = analysis.reference_at(offset) else { return Json::Null };
+        let declaration = analysis.definitions[reference.def].span;
+        let include_declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or(true);
+        analysis
+            .references_to(reference.def)
+            .filter(|other| include_declaration || Some(other.span) != declaration)
+            .map(|other| location(uri, &analysis.lines, other.span))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let Some((_, analysis, offset)) = self.at(params) else { return Json::Null };
+        complete(analysis, offset)
+            .into_iter()
+            .map(|completion| {
+                let kind = match completion.kind {
+                    CompletionKind::Keyword => COMPLETION_KEYWORD,
+                    CompletionKind::Type => COMPLETION_TYPE,
+                    CompletionKind::Variable => COMPLETION_VARIABLE,
+                    CompletionKind::Constant => COMPLETION_CONSTANT,
+                    CompletionKind::Function => COMPLETION_FUNCTION,
+                    CompletionKind::Stator => COMPLETION_MODULE,
+                };
+                Json::object([("label", completion.label.into()), ("kind", kind.into()), ("detail", completion.detail.into())])
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Tokens are sent as five numbers each: the line (relative to the
+    /// previous token), the column (relative to it on the same line), the
+    /// length, the type and the modifier bits.
+    fn semantic_tokens(&self, params: &Json) -> Json {
+        let Some(analysis) = params["textDocument"]["uri"].as_str().and_then(|uri| self.documents.get(uri)) else {
+            return Json::Null;
+        };
+        let mut data = vec![];
+        let (mut last_line, mut last_column) = (0, 0);
+        for token in semantic_tokens(analysis) {
+            let (line, column) = analysis.lines.position(token.start);
+            let length = analysis.text[token.start..token.end].encode_utf16().count();
+            let delta_column = if line == last_line { column - last_column } else { column };
+            data.extend([line - last_line, delta_column, length, token.kind as usize, token.modifiers as usize].map(Json::from));
+            (last_line, last_column) = (line, column);
+        }
+        Json::object([("data", data.into())])
+    }
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", Json::object([("uri", uri.into()), ("diagnostics", diagnostics.into())])),
+    ])
+}
+
+/// Runs a server on stdin and stdout, returning the process exit code.
+pub fn serve_stdio() -> i32 {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match Server::new().run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            1
+        }
+    }
+}
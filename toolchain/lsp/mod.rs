@@ -0,0 +1,11 @@
+pub mod lsp;
+pub mod json;
+pub mod analysis;
+pub mod semantic;
+pub mod completion;
+
+pub use lsp::{Server, read_message, write_message, serve_stdio};
+pub use json::Json;
+pub use analysis::{Analysis, DefKind, Definition, Diagnostic, LineIndex, Reference, Severity};
+pub use semantic::{SemanticKind, SemanticToken, TOKEN_MODIFIERS, TOKEN_TYPES, semantic_tokens};
+pub use completion::{Completion, CompletionKind, complete};
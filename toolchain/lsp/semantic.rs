@@ -0,0 +1,163 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Semantic tokens: highlighting from what the server knows rather than
+// from regular expressions. Keywords, literals and operators are classified
+// by their `TokenKind`; identifiers by what the resolver linked them to, so
+// a parameter looks like a parameter everywhere it is used, and a call
+// through `console.out` shows `console` as a stator.
+//
+// The protocol has no token type for constants; they are variables with
+// the `readonly` modifier, which is how editor themes expect them.
+use crate::lexer::{lex, TokenKind};
+use crate::lsp::analysis::{Analysis, DefKind};
+
+/// The token types, in the order of `SemanticKind`, for the legend.
+pub const TOKEN_TYPES: &[&str] =
+    &["keyword", "type", "variable", "function", "parameter", "namespace", "string", "number", "comment", "operator"];
+
+/// The token modifiers, by bit, for the legend.
+pub const TOKEN_MODIFIERS: &[&str] = &["declaration", "readonly", "defaultLibrary"];
+
+pub const DECLARATION: u32 = 1;
+pub const READONLY: u32 = 1 << 1;
+pub const DEFAULT_LIBRARY: u32 = 1 << 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticKind {
+    Keyword,
+    Type,
+    Variable,
+    Function,
+    Parameter,
+    Namespace,
+    String,
+    Number,
+    Comment,
+    Operator,
+}
+
+/// A classified piece of one line of the document, by byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub start: usize,
+    pub end: usize,
+    pub kind: SemanticKind,
+    pub modifiers: u32,
+}
+
+fn classify_kind(kind: TokenKind, value: &str) -> Option<SemanticKind> {
+    let kind = match kind {
+        TokenKind::Let
+        | TokenKind::Const
+        | TokenKind::Fn
+        | TokenKind::Pub
+        | TokenKind::Return
+        | TokenKind::Use
+        | TokenKind::If
+        | TokenKind::Else
+        | TokenKind::For
+        | TokenKind::While
+        | TokenKind::In
+        | TokenKind::Boolean => SemanticKind::Keyword,
+        TokenKind::I32 | TokenKind::BOOL | TokenKind::STR => SemanticKind::Type,
+        // `f32` lexes as a float for now
+        TokenKind::Float if value == "f32" => SemanticKind::Type,
+        TokenKind::Integer | TokenKind::Float => SemanticKind::Number,
+        TokenKind::String => SemanticKind::String,
+        TokenKind::Range
+        | TokenKind::Equal
+        | TokenKind::Plus
+        | TokenKind::Line
+        | TokenKind::Star
+        | TokenKind::Slash
+        | TokenKind::Modulus
+        | TokenKind::And
+        | TokenKind::Or
+        | TokenKind::Not
+        | TokenKind::GreaterThan
+        | TokenKind::LessThan
+        | TokenKind::GreaterThanOrEqual
+        | TokenKind::LessThanOrEqual
+        | TokenKind::EqualEqual
+        | TokenKind::NotEqual => SemanticKind::Operator,
+        // Names are classified by the resolver, and punctuation isn't highlighted
+        TokenKind::Identifier
+        | TokenKind::Dot
+        | TokenKind::Semicolon
+        | TokenKind::Colon
+        | TokenKind::Newline
+        | TokenKind::Eof
+        | TokenKind::Comma
+        | TokenKind::LParen
+        | TokenKind::RParen
+        | TokenKind::LCurly
+        | TokenKind::RCurly
+        | TokenKind::LSquare
+        | TokenKind::RSquare => return None,
+    };
+    Some(kind)
+}
+
+/// What the name at `start` refers to, if the resolver knows.
+fn classify_name(analysis: &Analysis, start: usize) -> Option<(SemanticKind, u32)> {
+    let index = analysis.references.partition_point(|reference| reference.span.start < start);
+    let reference = analysis.references.get(index).filter(|reference| reference.span.start == start)?;
+    let definition = &analysis.definitions[reference.def];
+    let mut modifiers = 0;
+    if definition.span == Some(reference.span) {
+        modifiers |= DECLARATION;
+    }
+    let kind = match definition.kind {
+        DefKind::Function => SemanticKind::Function,
+        DefKind::Global | DefKind::Local | DefKind::LoopVar => SemanticKind::Variable,
+        DefKind::Constant => {
+            modifiers |= READONLY;
+            SemanticKind::Variable
+        }
+        DefKind::Param => SemanticKind::Parameter,
+        DefKind::Import => SemanticKind::Function,
+        DefKind::Stator => SemanticKind::Namespace,
+    };
+    if definition.external.as_ref().is_some_and(|external| external.stator == "std" || external.stator.starts_with("std.")) {
+        modifiers |= DEFAULT_LIBRARY;
+    }
+    Some((kind, modifiers))
+}
+
+/// Splits `start..end` at line breaks, since most editors can't take a
+/// token spanning lines.
+fn push_lines(tokens: &mut Vec<SemanticToken>, text: &str, start: usize, end: usize, kind: SemanticKind, modifiers: u32) {
+    let mut from = start;
+    for (offset, _) in text[start..end].match_indices('\n') {
+        if start + offset > from {
+            tokens.push(SemanticToken { start: from, end: start + offset, kind, modifiers });
+        }
+        from = start + offset + 1;
+    }
+    if end > from {
+        tokens.push(SemanticToken { start: from, end, kind, modifiers });
+    }
+}
+
+/// Every highlighted piece of the document, in order.
+pub fn semantic_tokens(analysis: &Analysis) -> Vec<SemanticToken> {
+    let text = analysis.text.as_str();
+    let lexed = lex(text);
+    let mut tokens = vec![];
+    for comment in &lexed.comments {
+        push_lines(&mut tokens, text, comment.span.start, comment.span.end, SemanticKind::Comment, 0);
+    }
+    for token in &lexed.tokens {
+        let span = token.span();
+        let classified = match token.kind {
+            TokenKind::Identifier => classify_name(analysis, span.start),
+            kind => classify_kind(kind, token.text).map(|kind| (kind, 0)),
+        };
+        if let Some((kind, modifiers)) = classified {
+            push_lines(&mut tokens, text, span.start, span.end, kind, modifiers);
+        }
+    }
+    tokens.sort_by_key(|token| token.start);
+    tokens
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Dead code elimination. Blocks that cannot be reached are deleted,
+// then every instruction whose result is never needed is removed:
+// values are marked live starting from side effects and terminators,
+// so dead cycles of phis go away too. Finally the surviving values are
+// renumbered so they are dense again.
+use std::collections::{HashMap, HashSet};
+use crate::ir::{BinOp, Const, Function, InstKind, Module, Value};
+use crate::opt::opt::{remove_unreachable, Pass};
+
+pub struct DeadCode;
+
+fn remove_dead_insts(function: &mut Function) -> bool {
+    let mut defs: HashMap<Value, (usize, usize)> = HashMap::new();
+    let mut nonzero: HashSet<Value> = HashSet::new();
+    for (b, block) in function.blocks.iter().enumerate() {
+        for (i, inst) in block.insts.iter().enumerate() {
+            if let Some(result) = inst.result {
+                defs.insert(result, (b, i));
+                if matches!(inst.kind, InstKind::Const(Const::Int(n)) if n != 0) {
+                    nonzero.insert(result);
+                }
+            }
+        }
+    }
+
+    // Division only has a side effect (the panic) when the divisor can be zero
+    let is_root = |kind: &InstKind| match kind {
+        InstKind::Binary(BinOp::Div | BinOp::Rem, _, divisor) => !nonzero.contains(divisor),
+        InstKind::Call(..) | InstKind::StoreGlobal(..) => true,
+        _ => false,
+    };
+
+    let mut live: HashSet<Value> = HashSet::new();
+    let mut work: Vec<Value> = vec![];
+    for block in &function.blocks {
+        for inst in &block.insts {
+            if is_root(&inst.kind) {
+                work.extend(inst.operands());
+            }
+        }
+        work.extend(block.term.operands());
+    }
+    while let Some(value) = work.pop() {
+        if !live.insert(value) {
+            continue;
+        }
+        if let Some((b, i)) = defs.get(&value) {
+            work.extend(function.blocks[*b].insts[*i].operands());
+        }
+    }
+
+    let mut changed = false;
+    for block in &mut function.blocks {
+        let before = block.insts.len();
+        block.insts.retain(|inst| is_root(&inst.kind) || inst.result.is_some_and(|result| live.contains(&result)));
+        changed |= block.insts.len() != before;
+    }
+    changed
+}
+
+fn renumber_values(function: &mut Function) {
+    let mut map: HashMap<Value, Value> = HashMap::new();
+    let mut types = vec![];
+    for block in &function.blocks {
+        for inst in &block.insts {
+            if let Some(result) = inst.result {
+                map.insert(result, Value(types.len() as u32));
+                types.push(function.ty(result));
+            }
+        }
+    }
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            if let Some(result) = &mut inst.result {
+                *result = map[result];
+            }
+            for operand in inst.operands_mut() {
+                *operand = map[operand];
+            }
+        }
+        for operand in block.term.operands_mut() {
+            *operand = map[operand];
+        }
+    }
+    function.values = types;
+}
+
+impl Pass for DeadCode {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run(&mut self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            if function.is_external() {
+                continue;
+            }
+            let removed = remove_unreachable(function) | remove_dead_insts(function);
+            let sparse = function.values.len() != function.blocks.iter().flat_map(|block| &block.insts).filter(|inst| inst.result.is_some()).count();
+            if removed || sparse {
+                renumber_values(function);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Constant folding and propagation. Operations on constants are
+// evaluated at compile time with the same wrapping semantics as the
+// backends, `const` globals are replaced by their value, copies and
+// phis that merge a single value are forwarded to their users, and
+// branches on a known condition become jumps.
+use std::collections::HashMap;
+use crate::ir::{BinOp, Const, Function, InstKind, Module, Terminator, UnOp, Value};
+use crate::opt::opt::{replace_uses, Pass};
+
+pub struct ConstFold;
+
+fn fold_binary(op: BinOp, left: &Const, right: &Const) -> Option<Const> {
+    match (left, right) {
+        (Const::Int(a), Const::Int(b)) => Some(match op {
+            BinOp::Add => Const::Int(a.wrapping_add(*b)),
+            BinOp::Sub => Const::Int(a.wrapping_sub(*b)),
+            BinOp::Mul => Const::Int(a.wrapping_mul(*b)),
+            // Dividing by zero panics at run time, so it is left alone
+            BinOp::Div if *b != 0 => Const::Int(a.wrapping_div(*b)),
+            BinOp::Rem if *b != 0 => Const::Int(a.wrapping_rem(*b)),
+            BinOp::Div | BinOp::Rem => return None,
+            BinOp::Eq => Const::Bool(a == b),
+            BinOp::Ne => Const::Bool(a != b),
+            BinOp::Lt => Const::Bool(a < b),
+            BinOp::Gt => Const::Bool(a > b),
+            BinOp::Le => Const::Bool(a <= b),
+            BinOp::Ge => Const::Bool(a >= b),
+        }),
+        (Const::Bool(a), Const::Bool(b)) => match op {
+            BinOp::Eq => Some(Const::Bool(a == b)),
+            BinOp::Ne => Some(Const::Bool(a != b)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn fold_unary(op: UnOp, value: &Const) -> Option<Const> {
+    match (op, value) {
+        (UnOp::Neg, Const::Int(n)) => Some(Const::Int(n.wrapping_neg())),
+        (UnOp::Not, Const::Bool(b)) => Some(Const::Bool(!b)),
+        _ => None,
+    }
+}
+
+fn fold_function(function: &mut Function, globals: &HashMap<String, Const>) -> bool {
+    let mut changed = false;
+    loop {
+        let mut consts: HashMap<Value, Const> = HashMap::new();
+        for block in &function.blocks {
+            for inst in &block.insts {
+                if let (Some(result), InstKind::Const(value)) = (inst.result, &inst.kind) {
+                    consts.insert(result, value.clone());
+                }
+            }
+        }
+
+        let mut round = false;
+        let mut subst: HashMap<Value, Value> = HashMap::new();
+        let resolve = |subst: &HashMap<Value, Value>, mut value: Value| {
+            while let Some(next) = subst.get(&value) {
+                value = *next;
+            }
+            value
+        };
+        for block in &mut function.blocks {
+            for inst in &mut block.insts {
+                let Some(result) = inst.result else { continue };
+                let folded = match &inst.kind {
+                    InstKind::LoadGlobal(name) => globals.get(name).cloned(),
+                    InstKind::Binary(op, left, right) => match (consts.get(left), consts.get(right)) {
+                        (Some(left), Some(right)) => fold_binary(*op, left, right),
+                        _ => None,
+                    },
+                    InstKind::Unary(op, value) => consts.get(value).and_then(|value| fold_unary(*op, value)),
+                    InstKind::Copy(value) => {
+                        subst.insert(result, resolve(&subst, *value));
+                        None
+                    }
+                    InstKind::Phi(incoming) => {
+                        let mut values: Vec<Value> = incoming
+                            .iter()
+                            .map(|(_, value)| resolve(&subst, *value))
+                            .filter(|value| *value != result)
+                            .collect();
+                        values.sort();
+                        values.dedup();
+                        let first = values.first().and_then(|value| consts.get(value));
+                        if values.len() == 1 {
+                            subst.insert(result, values[0]);
+                            None
+                        } else if first.is_some() && values.iter().all(|value| consts.get(value) == first) {
+                            first.cloned()
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(value) = folded {
+                    inst.kind = InstKind::Const(value);
+                    round = true;
+                }
+            }
+            // A folded phi has to move below the phis that are left
+            let (mut phis, rest): (Vec<_>, Vec<_>) = block.insts.drain(..).partition(|inst| inst.is_phi());
+            phis.extend(rest);
+            block.insts = phis;
+        }
+        if !subst.is_empty() {
+            for block in &mut function.blocks {
+                block.insts.retain(|inst| !inst.result.is_some_and(|result| subst.contains_key(&result)));
+            }
+            replace_uses(function, &subst);
+            round = true;
+        }
+
+        for id in function.block_ids() {
+            let Terminator::Branch(condition, then_block, else_block) = function.block(id).term else { continue };
+            let Some(Const::Bool(taken)) = consts.get(&condition) else { continue };
+            let (target, dropped) = if *taken { (then_block, else_block) } else { (else_block, then_block) };
+            function.block_mut(id).term = Terminator::Jump(target);
+            if dropped != target {
+                for inst in &mut function.block_mut(dropped).insts {
+                    if let InstKind::Phi(incoming) = &mut inst.kind {
+                        incoming.retain(|(from, _)| *from != id);
+                    }
+                }
+            }
+            round = true;
+        }
+
+        if !round {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+impl Pass for ConstFold {
+    fn name(&self) -> &'static str {
+        "const-fold"
+    }
+
+    fn run(&mut self, module: &mut Module) -> bool {
+        let globals: HashMap<String, Const> = module
+            .globals
+            .iter()
+            .filter(|global| !global.mutable)
+            .map(|global| (global.name.clone(), global.init.clone()))
+            .collect();
+        let mut changed = false;
+        for function in &mut module.functions {
+            changed |= fold_function(function, &globals);
+        }
+        changed
+    }
+}
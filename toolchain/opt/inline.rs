@@ -0,0 +1,158 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Inlines calls to small functions. The calling block is split at the
+// call: the callee's blocks are copied in between, its parameters
+// become the arguments and every `ret` jumps to the second half, where
+// a phi collects the returned value. Functions that call themselves are
+// never inlined, and each caller has a budget so mutual recursion
+// cannot grow it forever.
+use std::collections::HashMap;
+use crate::ir::{Block, BlockId, Function, Inst, InstKind, Module, Terminator, Value};
+use crate::opt::opt::Pass;
+
+pub struct Inline {
+    // Callees with more instructions than this are left alone
+    pub max_insts: usize,
+    // How many calls may be inlined into a single function
+    pub budget: usize,
+}
+
+impl Default for Inline {
+    fn default() -> Self {
+        Inline { max_insts: 16, budget: 32 }
+    }
+}
+
+fn is_candidate(function: &Function, max_insts: usize) -> bool {
+    if function.is_external() {
+        return false;
+    }
+    let insts = function.blocks.iter().flat_map(|block| &block.insts);
+    let size = insts.clone().count();
+    let recursive = insts.clone().any(|inst| matches!(&inst.kind, InstKind::Call(name, _) if *name == function.name));
+    // A copied entry block must not be a jump target, or its phis would need the caller's blocks
+    let entry_targeted = !function.predecessors()[0].is_empty();
+    size <= max_insts && !recursive && !entry_targeted
+}
+
+fn inline_call(caller: &mut Function, at: BlockId, index: usize, callee: &Function) {
+    let block = caller.block_mut(at);
+    let after = block.insts.split_off(index + 1);
+    let call = block.insts.pop().unwrap();
+    let InstKind::Call(_, args) = call.kind else { unreachable!() };
+    let term = std::mem::replace(&mut block.term, Terminator::Unreachable);
+
+    // The second half of the split block
+    let cont = BlockId(caller.blocks.len() as u32);
+    for succ in term.successors() {
+        for inst in &mut caller.block_mut(succ).insts {
+            if let InstKind::Phi(incoming) = &mut inst.kind {
+                for (from, _) in incoming.iter_mut() {
+                    if *from == at {
+                        *from = cont;
+                    }
+                }
+            }
+        }
+    }
+    caller.blocks.push(Block { insts: after, term });
+
+    // Fresh values for everything the callee defines, except parameters
+    let base = caller.blocks.len() as u32;
+    let mut values: HashMap<Value, Value> = HashMap::new();
+    for inst in callee.blocks.iter().flat_map(|block| &block.insts) {
+        let Some(result) = inst.result else { continue };
+        let value = match inst.kind {
+            InstKind::Param(i) => args[i],
+            _ => {
+                caller.values.push(callee.ty(result));
+                Value(caller.values.len() as u32 - 1)
+            }
+        };
+        values.insert(result, value);
+    }
+
+    let mut returns = vec![];
+    for (i, block) in callee.blocks.iter().enumerate() {
+        let id = BlockId(base + i as u32);
+        let mut insts = vec![];
+        for inst in &block.insts {
+            if let InstKind::Param(_) = inst.kind {
+                continue;
+            }
+            let mut inst = inst.clone();
+            inst.result = inst.result.map(|result| values[&result]);
+            for operand in inst.operands_mut() {
+                *operand = values[operand];
+            }
+            if let InstKind::Phi(incoming) = &mut inst.kind {
+                for (from, _) in incoming.iter_mut() {
+                    *from = BlockId(base + from.0);
+                }
+            }
+            insts.push(inst);
+        }
+        let mut term = block.term.clone();
+        for operand in term.operands_mut() {
+            *operand = values[operand];
+        }
+        for target in term.targets_mut() {
+            *target = BlockId(base + target.0);
+        }
+        if let Terminator::Return(value) = term {
+            returns.push((id, value));
+            term = Terminator::Jump(cont);
+        }
+        caller.blocks.push(Block { insts, term });
+    }
+    caller.block_mut(at).term = Terminator::Jump(BlockId(base));
+
+    // The call's result keeps its number, now defined at the start of `cont`
+    if let Some(result) = call.result {
+        let kind = if returns.is_empty() {
+            InstKind::Undef
+        } else {
+            InstKind::Phi(returns.iter().map(|(from, value)| (*from, value.unwrap())).collect())
+        };
+        caller.block_mut(cont).insts.insert(0, Inst { result: Some(result), kind });
+    }
+}
+
+fn find_call(function: &Function, candidates: &HashMap<String, Function>) -> Option<(BlockId, usize, String)> {
+    for id in function.reverse_postorder() {
+        for (i, inst) in function.block(id).insts.iter().enumerate() {
+            if let InstKind::Call(name, _) = &inst.kind {
+                if *name != function.name && candidates.contains_key(name) {
+                    return Some((id, i, name.clone()));
+                }
+            }
+        }
+    }
+    None
+}
+
+impl Pass for Inline {
+    fn name(&self) -> &'static str {
+        "inline"
+    }
+
+    fn run(&mut self, module: &mut Module) -> bool {
+        // Callees are copied as they were before this pass started
+        let candidates: HashMap<String, Function> = module
+            .functions
+            .iter()
+            .filter(|function| is_candidate(function, self.max_insts))
+            .map(|function| (function.name.clone(), function.clone()))
+            .collect();
+        let mut changed = false;
+        for function in &mut module.functions {
+            for _ in 0..self.budget {
+                let Some((block, index, name)) = find_call(function, &candidates) else { break };
+                inline_call(function, block, index, &candidates[&name]);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,10 @@
+pub mod opt;
+pub mod fold;
+pub mod dce;
+pub mod simplify_cfg;
+pub mod inline;
+pub use opt::{Pass, OptLevel, PassManager};
+pub use fold::ConstFold;
+pub use dce::DeadCode;
+pub use simplify_cfg::SimplifyCfg;
+pub use inline::Inline;
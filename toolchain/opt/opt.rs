@@ -0,0 +1,191 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The pass manager runs optimization passes over an IR module, checking
+// the module with the verifier after every pass so a broken pass is
+// caught where it happened rather than in a backend.
+use std::collections::HashMap;
+use crate::handle_error::Error;
+use crate::ir::{verify, BlockId, Function, InstKind, Module, Value};
+use crate::opt::dce::DeadCode;
+use crate::opt::fold::ConstFold;
+use crate::opt::inline::Inline;
+use crate::opt::simplify_cfg::SimplifyCfg;
+
+/// A transformation of a whole module.
+pub trait Pass {
+    fn name(&self) -> &'static str;
+
+    /// Runs the pass, returning whether anything changed.
+    fn run(&mut self, module: &mut Module) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+}
+
+impl OptLevel {
+    /// Parses the part after `-O`, e.g. `2` for `-O2`.
+    pub fn from_flag(flag: &str) -> Option<OptLevel> {
+        match flag {
+            "0" => Some(OptLevel::O0),
+            "1" => Some(OptLevel::O1),
+            "2" => Some(OptLevel::O2),
+            _ => None,
+        }
+    }
+}
+
+// Upper bound on how often the passes are repeated
+const MAX_ROUNDS: usize = 8;
+
+pub struct PassManager {
+    // Run once, before the repeated passes
+    setup: Vec<Box<dyn Pass>>,
+    passes: Vec<Box<dyn Pass>>,
+    rounds: usize,
+}
+
+impl PassManager {
+    /// An empty pipeline that runs its passes once.
+    pub fn new() -> Self {
+        PassManager { setup: vec![], passes: vec![], rounds: 1 }
+    }
+
+    /// The pipeline for an optimization level. `-O1` folds constants and
+    /// cleans up the control flow until nothing changes; `-O2` inlines
+    /// small functions first.
+    pub fn for_level(level: OptLevel) -> Self {
+        let mut manager = PassManager::new();
+        if level == OptLevel::O0 {
+            return manager;
+        }
+        if level == OptLevel::O2 {
+            manager.setup.push(Box::new(Inline::default()));
+        }
+        manager.rounds = MAX_ROUNDS;
+        manager.add(ConstFold);
+        manager.add(SimplifyCfg);
+        manager.add(DeadCode);
+        manager
+    }
+
+    pub fn add(&mut self, pass: impl Pass + 'static) {
+        self.passes.push(Box::new(pass));
+    }
+
+    pub fn run(&mut self, module: &mut Module) -> Result<(), Vec<Error>> {
+        self.run_with_dump(module, &mut |_, _| {})
+    }
+
+    /// Runs the pipeline, calling `dump` with the pass name and the
+    /// module after every pass that changed something.
+    pub fn run_with_dump(&mut self, module: &mut Module, dump: &mut dyn FnMut(&str, &Module)) -> Result<(), Vec<Error>> {
+        for pass in &mut self.setup {
+            run_pass(pass.as_mut(), module, dump)?;
+        }
+        for _ in 0..self.rounds {
+            let mut changed = false;
+            for pass in &mut self.passes {
+                changed |= run_pass(pass.as_mut(), module, dump)?;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        PassManager::new()
+    }
+}
+
+fn run_pass(pass: &mut dyn Pass, module: &mut Module, dump: &mut dyn FnMut(&str, &Module)) -> Result<bool, Vec<Error>> {
+    let changed = pass.run(module);
+    if changed {
+        verify(module)?;
+        dump(pass.name(), module);
+    }
+    Ok(changed)
+}
+
+/// Rewrites every use of a key in `subst` to its value, following chains.
+pub fn replace_uses(function: &mut Function, subst: &HashMap<Value, Value>) {
+    if subst.is_empty() {
+        return;
+    }
+    let resolve = |mut value: Value| {
+        while let Some(next) = subst.get(&value) {
+            if *next == value {
+                break;
+            }
+            value = *next;
+        }
+        value
+    };
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            for operand in inst.operands_mut() {
+                *operand = resolve(*operand);
+            }
+        }
+        for operand in block.term.operands_mut() {
+            *operand = resolve(*operand);
+        }
+    }
+}
+
+/// Deletes the blocks not marked in `keep` and renumbers the rest. Phi
+/// entries coming from deleted blocks are dropped; kept blocks must not
+/// jump to deleted ones.
+pub fn remove_blocks(function: &mut Function, keep: &[bool]) -> bool {
+    if keep.iter().all(|kept| *kept) {
+        return false;
+    }
+    let mut map = vec![None; keep.len()];
+    let mut next = 0;
+    for (i, kept) in keep.iter().enumerate() {
+        if *kept {
+            map[i] = Some(BlockId(next));
+            next += 1;
+        }
+    }
+    let remap = |block: &mut BlockId| *block = map[block.0 as usize].expect("jump to a removed block");
+
+    let blocks = std::mem::take(&mut function.blocks);
+    for (block, kept) in blocks.into_iter().zip(keep) {
+        if *kept {
+            function.blocks.push(block);
+        }
+    }
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            if let InstKind::Phi(incoming) = &mut inst.kind {
+                incoming.retain(|(from, _)| map[from.0 as usize].is_some());
+                for (from, _) in incoming.iter_mut() {
+                    remap(from);
+                }
+            }
+        }
+        block.term.targets_mut().into_iter().for_each(remap);
+    }
+    true
+}
+
+/// Deletes the blocks that cannot be reached from the entry.
+pub fn remove_unreachable(function: &mut Function) -> bool {
+    if function.is_external() {
+        return false;
+    }
+    let mut keep = vec![false; function.blocks.len()];
+    for block in function.reverse_postorder() {
+        keep[block.0 as usize] = true;
+    }
+    remove_blocks(function, &keep)
+}
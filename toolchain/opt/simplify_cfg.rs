@@ -0,0 +1,136 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Control flow graph simplification: branches to the same block become
+// jumps, empty blocks that only jump on are bypassed, and a block is
+// merged into its predecessor when that predecessor jumps straight to
+// it and nothing else does. Blocks left unreachable are deleted.
+use std::collections::HashMap;
+use crate::ir::{BlockId, Function, InstKind, Module, Terminator};
+use crate::opt::opt::{remove_unreachable, replace_uses, Pass};
+
+pub struct SimplifyCfg;
+
+fn phi_incoming_rename(function: &mut Function, block: BlockId, from: BlockId, to: BlockId) {
+    for inst in &mut function.block_mut(block).insts {
+        if let InstKind::Phi(incoming) = &mut inst.kind {
+            for (pred, _) in incoming.iter_mut() {
+                if *pred == from {
+                    *pred = to;
+                }
+            }
+        }
+    }
+}
+
+fn has_phis(function: &Function, block: BlockId) -> bool {
+    function.block(block).insts.first().is_some_and(|inst| inst.is_phi())
+}
+
+/// Sends the predecessors of an empty block `block` that jumps to
+/// `target` straight to `target`. A predecessor already jumping to a
+/// target with phis is skipped, since it would need two phi entries.
+fn bypass(function: &mut Function, block: BlockId, target: BlockId, preds: &[Vec<BlockId>]) -> bool {
+    let mut changed = false;
+    let mut remaining = 0;
+    for pred in &preds[block.0 as usize] {
+        if has_phis(function, target) && preds[target.0 as usize].contains(pred) {
+            remaining += 1;
+            continue;
+        }
+        for succ in function.block_mut(*pred).term.targets_mut() {
+            if *succ == block {
+                *succ = target;
+            }
+        }
+        // The pred now brings whatever `block` brought
+        for inst in &mut function.block_mut(target).insts {
+            if let InstKind::Phi(incoming) = &mut inst.kind {
+                if let Some((_, value)) = incoming.iter().find(|(from, _)| *from == block).copied() {
+                    incoming.push((*pred, value));
+                }
+            }
+        }
+        changed = true;
+    }
+    if changed && remaining == 0 {
+        for inst in &mut function.block_mut(target).insts {
+            if let InstKind::Phi(incoming) = &mut inst.kind {
+                incoming.retain(|(from, _)| *from != block);
+            }
+        }
+    }
+    changed
+}
+
+/// Appends `block` to `pred`, its only predecessor, which jumps to it.
+fn merge(function: &mut Function, pred: BlockId, block: BlockId) {
+    let mut insts = std::mem::take(&mut function.block_mut(block).insts);
+    let mut subst = HashMap::new();
+    insts.retain(|inst| match (&inst.kind, inst.result) {
+        (InstKind::Phi(incoming), Some(result)) => {
+            subst.insert(result, incoming[0].1);
+            false
+        }
+        _ => true,
+    });
+    let term = std::mem::replace(&mut function.block_mut(block).term, Terminator::Unreachable);
+    for succ in term.successors() {
+        phi_incoming_rename(function, succ, block, pred);
+    }
+    let target = function.block_mut(pred);
+    target.insts.extend(insts);
+    target.term = term;
+    replace_uses(function, &subst);
+}
+
+fn simplify(function: &mut Function) -> bool {
+    let mut changed = false;
+    // Each step restarts the search, bounded in case bypasses cycle
+    for _ in 0..function.blocks.len() * 4 {
+        let preds = function.predecessors();
+        let mut step = false;
+        for id in function.block_ids() {
+            match function.block(id).term {
+                Terminator::Branch(_, then_block, else_block) if then_block == else_block => {
+                    function.block_mut(id).term = Terminator::Jump(then_block);
+                    step = true;
+                }
+                Terminator::Jump(target) if target != id => {
+                    let entry = function.entry();
+                    if preds[target.0 as usize].len() == 1 && target != entry {
+                        merge(function, id, target);
+                        step = true;
+                    } else if id != entry && function.block(id).insts.is_empty() && !preds[id.0 as usize].is_empty() {
+                        step = bypass(function, id, target, &preds);
+                    }
+                }
+                _ => {}
+            }
+            if step {
+                break;
+            }
+        }
+        if !step {
+            break;
+        }
+        changed = true;
+    }
+    remove_unreachable(function) || changed
+}
+
+impl Pass for SimplifyCfg {
+    fn name(&self) -> &'static str {
+        "simplify-cfg"
+    }
+
+    fn run(&mut self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            if !function.is_external() {
+                changed |= simplify(function);
+            }
+        }
+        changed
+    }
+}
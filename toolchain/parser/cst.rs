@@ -0,0 +1,206 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The concrete syntax tree. Where the AST keeps only meaning, the CST keeps
+// every token, with its trivia, grouped into nodes for the statements and
+// expressions the parser found, so `text()` is always exactly the source.
+// That makes it the tree to use for tools that rewrite source, like the
+// formatter or a rename.
+//
+// The tree is built from the AST: each node covers the tokens inside its
+// span, and tokens that no node covers (braces, `;`, parentheses, or code
+// that failed to parse) belong to the innermost node around them. Source
+// that doesn't parse still round-trips; it just has fewer nodes.
+use std::iter::Peekable;
+use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::trivia::attach_trivia;
+use crate::lexer::{lex, LosslessToken, Span, Trivia};
+use crate::parser::nodes::{Block, Expr, Stmt};
+use crate::parser::{parse_program_recovering, TokenStream};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    Root,
+    LetStmt,
+    UseStmt,
+    FnDecl,
+    Param,
+    IfStmt,
+    ForStmt,
+    WhileStmt,
+    AssignStmt,
+    ReturnStmt,
+    CallStmt,
+    Literal,
+    Variable,
+    BinaryOp,
+    UnaryOp,
+    FnCall,
+    Array,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(LosslessToken),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn write_to(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.write_to(out),
+                SyntaxElement::Token(token) => token.write_to(out),
+            }
+        }
+    }
+
+    /// The node's source text, trivia included.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.write_to(&mut out);
+        out
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Every token under this node, in source order.
+    pub fn tokens(&self) -> Vec<&LosslessToken> {
+        let mut tokens = vec![];
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+pub struct Cst {
+    pub root: SyntaxNode,
+    /// Trivia after the last token.
+    pub eof: Vec<Trivia>,
+    /// Lexer and parser errors. The tree is complete even if there are some.
+    pub errors: Vec<Error>,
+}
+
+impl Cst {
+    /// The source the tree was built from.
+    pub fn text(&self) -> String {
+        let mut out = self.root.text();
+        for trivia in &self.eof {
+            out.push_str(&trivia.text);
+        }
+        out
+    }
+}
+
+// The nodes of the tree before tokens are put in them
+struct Shape {
+    kind: NodeKind,
+    span: Span,
+    children: Vec<Shape>,
+}
+
+fn block_shapes(block: &Block) -> Vec<Shape> {
+    block.iter().map(stmt_shape).collect()
+}
+
+fn stmt_shape(stmt: &Stmt) -> Shape {
+    let (kind, children) = match stmt {
+        Stmt::LetStmt { value, .. } => (NodeKind::LetStmt, vec![expr_shape(value)]),
+        Stmt::UseStmt { .. } => (NodeKind::UseStmt, vec![]),
+        Stmt::FnDecl { params, body, .. } => {
+            let mut children: Vec<Shape> = params
+                .iter()
+                .map(|param| Shape { kind: NodeKind::Param, span: param.span, children: vec![] })
+                .collect();
+            children.extend(body.iter().flat_map(block_shapes));
+            (NodeKind::FnDecl, children)
+        }
+        Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+            let mut children = vec![expr_shape(condition)];
+            children.extend(block_shapes(then_branch));
+            children.extend(else_branch.iter().flat_map(block_shapes));
+            (NodeKind::IfStmt, children)
+        }
+        Stmt::ForStmt { iterable, body, .. } => {
+            let mut children = vec![expr_shape(iterable)];
+            children.extend(block_shapes(body));
+            (NodeKind::ForStmt, children)
+        }
+        Stmt::WhileStmt { condition, body, .. } => {
+            let mut children = vec![expr_shape(condition)];
+            children.extend(block_shapes(body));
+            (NodeKind::WhileStmt, children)
+        }
+        Stmt::AssignStmt { value, .. } => (NodeKind::AssignStmt, vec![expr_shape(value)]),
+        Stmt::ReturnStmt { value, .. } => (NodeKind::ReturnStmt, value.iter().map(|value| expr_shape(value)).collect()),
+        Stmt::CallStmt(call) => (NodeKind::CallStmt, vec![expr_shape(call)]),
+    };
+    Shape { kind, span: stmt.span(), children }
+}
+
+fn expr_shape(expr: &Expr) -> Shape {
+    let (kind, children) = match expr {
+        Expr::Literal { .. } => (NodeKind::Literal, vec![]),
+        Expr::Variable { .. } => (NodeKind::Variable, vec![]),
+        Expr::BinaryOp { left, right, .. } => (NodeKind::BinaryOp, vec![expr_shape(left), expr_shape(right)]),
+        Expr::UnaryOp { expr, .. } => (NodeKind::UnaryOp, vec![expr_shape(expr)]),
+        Expr::FnCall { args, .. } => (NodeKind::FnCall, args.iter().map(expr_shape).collect()),
+        Expr::Array { elements, .. } => (NodeKind::Array, elements.iter().map(expr_shape).collect()),
+    };
+    Shape { kind, span: expr.span(), children }
+}
+
+/// Fills a node with the tokens inside its span, in order.
+fn fill<I: Iterator<Item = LosslessToken>>(shape: Shape, tokens: &mut Peekable<I>) -> SyntaxNode {
+    let mut children = vec![];
+    for child in shape.children {
+        while let Some(token) = tokens.next_if(|token| token.span.start < child.span.start) {
+            children.push(SyntaxElement::Token(token));
+        }
+        children.push(SyntaxElement::Node(fill(child, tokens)));
+    }
+    while let Some(token) = tokens.next_if(|token| token.span.start < shape.span.end) {
+        children.push(SyntaxElement::Token(token));
+    }
+    SyntaxNode { kind: shape.kind, children }
+}
+
+/// Builds the lossless tree of `source`.
+pub fn parse_cst(source: &str) -> Cst {
+    let tokens = lex(source);
+    let lexed = attach_trivia(source, &tokens);
+    let mut errors: Vec<Error> = lexed.errors.iter().map(|error| Error::new(ErrorKind::InvalidToken, error.clone(), 0, 0)).collect();
+
+    // The parser works on the usual tokens; only the spans of what it
+    // finds are needed here
+    let mut stream = TokenStream::with_eof(tokens.tokens, tokens.eof);
+    let (program, parse_errors) = parse_program_recovering(&mut stream);
+    errors.extend(parse_errors);
+
+    let root = Shape {
+        kind: NodeKind::Root,
+        span: Span { start: 0, end: usize::MAX, line: 1, column: 1, ..Span::default() },
+        children: block_shapes(&program),
+    };
+    let mut tokens = lexed.tokens.into_iter().peekable();
+    let root = fill(root, &mut tokens);
+    Cst { root, eof: lexed.eof, errors }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// A readable dump of a parse tree, one node per line and indented by
+// depth, for `rotor parse` and for debugging the parser. Each line names
+// the node, its details, and where it starts (`@line:column`).
+use std::fmt::Write;
+use crate::lexer::{Span, TokenKind};
+use crate::parser::nodes::{Block, Expr, Program, Stmt, UseImports};
+
+struct Dump {
+    out: String,
+    depth: usize,
+}
+
+impl Dump {
+    fn line(&mut self, text: &str, span: Span) {
+        let _ = writeln!(self.out, "{}{} @{}:{}", "  ".repeat(self.depth), text, span.line, span.column);
+    }
+
+    // A label grouping the lines under it, like the branches of an `if`
+    fn label(&mut self, text: &str) {
+        let _ = writeln!(self.out, "{}{}", "  ".repeat(self.depth), text);
+    }
+
+    fn nested(&mut self, dump: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        dump(self);
+        self.depth -= 1;
+    }
+
+    fn block(&mut self, label: &str, block: &Block) {
+        self.label(label);
+        self.nested(|dump| block.iter().for_each(|stmt| dump.stmt(stmt)));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, ty, value, constant, span } => {
+                let keyword = if *constant { "Const" } else { "Let" };
+                let ty = ty.map_or(String::new(), |ty| format!(": {}", ty));
+                self.line(&format!("{} {}{}", keyword, name, ty), *span);
+                self.nested(|dump| dump.expr(value));
+            }
+            Stmt::UseStmt { stator, imports, span } => {
+                let imports = match imports {
+                    UseImports::Wildcard => "*".to_string(),
+                    UseImports::List(names) => names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", "),
+                };
+                self.line(&format!("Use {} [{}]", stator, imports), *span);
+            }
+            Stmt::FnDecl { name, params, return_ty, body, public, span } => {
+                let params: Vec<String> = params.iter().map(|param| format!("{}: {}", param.name, param.ty)).collect();
+                let ret = return_ty.map_or(String::new(), |ty| format!(" {}", ty));
+                let public = if *public { "pub " } else { "" };
+                self.line(&format!("FnDecl {}{}({}){}", public, name, params.join(", "), ret), *span);
+                if let Some(body) = body {
+                    self.nested(|dump| body.iter().for_each(|stmt| dump.stmt(stmt)));
+                }
+            }
+            Stmt::IfStmt { condition, then_branch, else_branch, span } => {
+                self.line("If", *span);
+                self.nested(|dump| {
+                    dump.expr(condition);
+                    dump.block("then", then_branch);
+                    if let Some(else_branch) = else_branch {
+                        dump.block("else", else_branch);
+                    }
+                });
+            }
+            Stmt::ForStmt { variable, iterable, body, span } => {
+                self.line(&format!("For {}", variable), *span);
+                self.nested(|dump| {
+                    dump.expr(iterable);
+                    dump.block("body", body);
+                });
+            }
+            Stmt::WhileStmt { condition, body, span } => {
+                self.line("While", *span);
+                self.nested(|dump| {
+                    dump.expr(condition);
+                    dump.block("body", body);
+                });
+            }
+            Stmt::AssignStmt { name, value, span } => {
+                self.line(&format!("Assign {}", name), *span);
+                self.nested(|dump| dump.expr(value));
+            }
+            Stmt::ReturnStmt { value, span } => {
+                self.line("Return", *span);
+                if let Some(value) = value {
+                    self.nested(|dump| dump.expr(value));
+                }
+            }
+            Stmt::CallStmt(call) => {
+                self.line("CallStmt", call.span());
+                self.nested(|dump| dump.expr(call));
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal { kind: TokenKind::String, value, span } => self.line(&format!("String {:?}", value), *span),
+            Expr::Literal { kind, value, span } => self.line(&format!("{:?} {}", kind, value), *span),
+            Expr::Variable { name, span, .. } => self.line(&format!("Variable {}", name), *span),
+            Expr::BinaryOp { left, op, right, span } => {
+                self.line(&format!("BinaryOp {}", op), *span);
+                self.nested(|dump| {
+                    dump.expr(left);
+                    dump.expr(right);
+                });
+            }
+            Expr::UnaryOp { op, expr, span } => {
+                self.line(&format!("UnaryOp {}", op), *span);
+                self.nested(|dump| dump.expr(expr));
+            }
+            Expr::FnCall { name, args, span } => {
+                self.line(&format!("FnCall {}", name), *span);
+                self.nested(|dump| args.iter().for_each(|arg| dump.expr(arg)));
+            }
+            Expr::Array { elements, span, .. } => {
+                self.line("Array", *span);
+                self.nested(|dump| elements.iter().for_each(|element| dump.expr(element)));
+            }
+        }
+    }
+}
+
+pub fn dump_program(program: &Program) -> String {
+    let mut dump = Dump { out: String::new(), depth: 0 };
+    for stmt in program {
+        dump.stmt(stmt);
+    }
+    dump.out
+}
+
+pub fn dump_expr(expr: &Expr) -> String {
+    let mut dump = Dump { out: String::new(), depth: 0 };
+    dump.expr(expr);
+    dump.out
+}
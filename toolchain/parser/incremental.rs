@@ -0,0 +1,213 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Reparsing after an edit. `parse_program_recovering` is a loop of
+// independent steps: each parses one top-level statement, or skips a
+// syntax error up to the next `fn`, `pub` or `use`, starting from nothing
+// but the tokens in front of it. What a step produces depends only on the
+// tokens it consumed and those it peeked at after them (the `TokenStream`
+// keeps track of how far it looked), so `Parsed` remembers those ranges as
+// "units". After an edit, units that end (lookahead included) before the
+// damaged tokens are kept; parsing restarts at the first unit that doesn't,
+// and stops once it reaches the start of an old unit lying wholly after the
+// damage, which is kept along with everything after it, moved to its new
+// place.
+//
+// A unit is a whole top-level item, so an edit inside a function reparses
+// that function. The result is always the same as a full reparse.
+use crate::handle_error::Error;
+use crate::lexer::incremental::{relex_damaged, Damage};
+use crate::lexer::{Edit, Lexed, Span};
+use crate::parser::nodes::{walk_expr_mut, walk_stmt_mut, Expr, FnParam, Program, Stmt, VisitorMut};
+use crate::parser::parser::{parse_recovering, TokenStream};
+
+/// One step of the parse: the tokens it read, and where the furthest token
+/// it peeked at after them is (`None` if it reached the end of the file).
+#[derive(Debug, Clone, Copy)]
+struct Unit {
+    start: usize,
+    lookahead: Option<Span>,
+    parsed: bool,
+}
+
+/// A parse that can be updated after an edit with `reparse`.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub program: Program,
+    pub errors: Vec<Error>,
+    units: Vec<Unit>,
+}
+
+impl Parsed {
+    /// Parses `lexed` as `parse_program_recovering` would.
+    pub fn new(lexed: &Lexed) -> Self {
+        let mut parsed = Parsed::default();
+        parsed.parse(lexed, 0, &[]);
+        parsed
+    }
+
+    /// Parses units from `lexed.tokens[from..]` until one would start
+    /// where an old unit in `resume` (already moved) starts. Returns how
+    /// many of `resume` were reached, or `None` if parsing ran to the end.
+    fn parse(&mut self, lexed: &Lexed, from: usize, resume: &[Unit]) -> Option<usize> {
+        let tokens = &lexed.tokens;
+        let index_of = |pos: usize| tokens.partition_point(|token| token.pos < pos);
+        let mut next = 0;
+        let mut from = from;
+        // Only the tokens up to the next place parsing could stop are
+        // copied into the stream, with one more for the lookahead
+        let window = |next: usize, from: usize| match resume.get(next) {
+            Some(unit) => (index_of(unit.start) + 1).max(from + 1).min(tokens.len()),
+            None => tokens.len(),
+        };
+        // A window that reaches the end of the file ends where the file does
+        let stream_of = |from: usize, end: usize| {
+            if end == tokens.len() {
+                TokenStream::with_eof(tokens[from..].to_vec(), lexed.eof)
+            } else {
+                TokenStream::new(tokens[from..end].to_vec())
+            }
+        };
+        let mut end = window(next, from);
+        let mut stream = stream_of(from, end);
+        while let Some(start) = stream.peek().map(|token| token.pos) {
+            while resume.get(next).is_some_and(|unit| unit.start < start) {
+                next += 1;
+            }
+            if resume.get(next).is_some_and(|unit| unit.start == start) {
+                return Some(next);
+            }
+            let result = parse_recovering(&mut stream);
+            // A unit reaches at least to where the next one starts
+            stream.peek();
+            let lookahead = stream.furthest();
+            // The step ran into the end of the window, not of the file, so
+            // it has to be done again with more tokens
+            if lookahead.is_none() && end < tokens.len() {
+                from = index_of(start);
+                next += 1;
+                end = window(next, from).max(end + 1);
+                stream = stream_of(from, end);
+                continue;
+            }
+            self.units.push(Unit { start, lookahead, parsed: result.is_ok() });
+            match result {
+                Ok(stmt) => self.program.push(stmt),
+                Err(error) => self.errors.push(error),
+            }
+        }
+        None
+    }
+}
+
+/// Moves the spans of a tree that now starts `delta` bytes and `lines`
+/// lines further on.
+struct Shift {
+    delta: isize,
+    lines: isize,
+}
+
+impl Shift {
+    fn span(&self, span: &mut Span) {
+        span.start = (span.start as isize + self.delta) as usize;
+        span.end = (span.end as isize + self.delta) as usize;
+        span.line = (span.line as isize + self.lines) as usize;
+    }
+}
+
+impl VisitorMut for Shift {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::LetStmt { span, .. }
+            | Stmt::UseStmt { span, .. }
+            | Stmt::FnDecl { span, .. }
+            | Stmt::IfStmt { span, .. }
+            | Stmt::ForStmt { span, .. }
+            | Stmt::WhileStmt { span, .. }
+            | Stmt::AssignStmt { span, .. }
+            | Stmt::ReturnStmt { span, .. } => self.span(span),
+            Stmt::CallStmt(_) => {}
+        }
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut FnParam) {
+        self.span(&mut param.span);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Literal { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::BinaryOp { span, .. }
+            | Expr::UnaryOp { span, .. }
+            | Expr::FnCall { span, .. }
+            | Expr::Array { span, .. } => self.span(span),
+        }
+        walk_expr_mut(self, expr);
+    }
+}
+
+fn reparse_damaged(old: Parsed, lexed: &Lexed, damage: Damage) -> Parsed {
+    let Parsed { mut program, mut errors, mut units } = old;
+    let mut shift = Shift { delta: damage.delta, lines: damage.lines };
+
+    // Keep the units that were done reading before the damage
+    let kept = units.iter().take_while(|unit| unit.lookahead.is_some_and(|next| next.end <= damage.start)).count();
+    // A unit that looked at a token has one after it
+    let restart = match kept {
+        0 => 0,
+        _ => units[kept].start,
+    };
+    // and those that start after it, moved
+    let mut resume: Vec<Unit> = units
+        .iter()
+        .skip(kept)
+        .map(|unit| {
+            let mut moved = *unit;
+            moved.start = (unit.start as isize + shift.delta) as usize;
+            if let Some(next) = &mut moved.lookahead {
+                shift.span(next);
+            }
+            moved
+        })
+        .collect();
+    let first_after = resume.partition_point(|unit| unit.start < damage.end);
+    resume.drain(..first_after);
+
+    let parsed_count = |units: &[Unit]| units.iter().filter(|unit| unit.parsed).count();
+    let (kept_stmts, kept_errors) = (parsed_count(&units[..kept]), kept - parsed_count(&units[..kept]));
+    let mut parsed = Parsed {
+        program: program.drain(..kept_stmts).collect(),
+        errors: errors.drain(..kept_errors).collect(),
+        units: units.drain(..kept).collect(),
+    };
+    let restart = lexed.tokens.partition_point(|token| token.pos < restart);
+    let Some(reached) = parsed.parse(lexed, restart, &resume) else { return parsed };
+
+    // Keep what the old parse made of the units after the damage
+    let skipped = first_after + reached;
+    let (skipped_stmts, skipped_errors) = (parsed_count(&units[..skipped]), skipped - parsed_count(&units[..skipped]));
+    let moved = shift.delta != 0 || shift.lines != 0;
+    for mut stmt in program.drain(skipped_stmts..) {
+        if moved {
+            shift.visit_stmt_mut(&mut stmt);
+        }
+        parsed.program.push(stmt);
+    }
+    for mut error in errors.drain(skipped_errors..) {
+        error.line = (error.line as isize + shift.lines) as usize;
+        parsed.errors.push(error);
+    }
+    parsed.units.extend(resume.drain(reached..));
+    parsed
+}
+
+/// Updates `old`, the parse of `old_lexed`, for `edit`, which turned the
+/// text into `source`. Only the lines around the edit are relexed, and only
+/// the top-level items they touch are reparsed.
+pub fn reparse<'src>(old: Parsed, old_lexed: Lexed<'_>, source: &'src str, edit: &Edit) -> (Lexed<'src>, Parsed) {
+    let (lexed, damage) = relex_damaged(old_lexed, source, edit);
+    let parsed = reparse_damaged(old, &lexed, damage);
+    (lexed, parsed)
+}
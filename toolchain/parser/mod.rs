@@ -1,5 +1,11 @@
 pub mod parser;
 pub mod nodes;
+pub mod cst;
+pub mod dump;
+pub mod incremental;
 
-pub use parser::{TokenStream, p_let_stmt, p_use_stmt, p_if_stmt};
-pub use nodes::{Expr, Stmt};
\ No newline at end of file
+pub use parser::{Checkpoint, TokenStream, p_let_stmt, p_use_stmt, p_if_stmt, p_fn_decl, parse_expr, parse_program, parse_program_recovering};
+pub use nodes::{Expr, Stmt};
+pub use cst::{Cst, NodeKind, SyntaxElement, SyntaxNode, parse_cst};
+pub use dump::{dump_expr, dump_program};
+pub use incremental::{Parsed, reparse};
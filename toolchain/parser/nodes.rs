@@ -1,77 +1,320 @@
-use crate::lexer::TokenKind;
+use crate::lexer::{Span, Symbol, TokenKind};
 
 pub type Program = Vec<Stmt>;
 pub type Block = Vec<Stmt>;
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     Literal {
         kind: TokenKind,
-        value: String
+        value: String,
+        span: Span
     },
     Variable {
-        name: String,
+        name: Symbol,
         // More memory stuff to come like references, pointers, etc
         // But right now, I'm just gonna add the type
-        ty: Option<TokenKind>
+        // (the type checker fills this in)
+        ty: Option<TokenKind>,
+        span: Span
     },
     BinaryOp {
         left: Box<Expr>,
         op: TokenKind,
-        right: Box<Expr> 
+        right: Box<Expr>,
+        span: Span
     },
     UnaryOp {
         op: TokenKind,
-        expr: Box<Expr>
+        expr: Box<Expr>,
+        span: Span
     },
     FnCall {
-        name: String,
-        args: Vec<Expr>
+        // Calls through a stator use a dotted name, e.g. `console.out`
+        name: Symbol,
+        args: Vec<Expr>,
+        span: Span
     },
     Array {
         elements: Vec<Expr>,
-        length: Option<usize>
+        length: Option<usize>,
+        span: Span
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
     LetStmt {
-        name: String,
+        name: Symbol,
+        // Filled in by the type checker when it isn't written out
         ty: Option<TokenKind>,
         value: Box<Expr>,
+        constant: bool,
+        span: Span
     },
     UseStmt {
-        stator: String,
-        imports: UseImports
+        stator: Symbol,
+        imports: UseImports,
+        span: Span
     },
     FnDecl {
-        name: String,
+        name: Symbol,
         params: Vec<FnParam>,
         return_ty: Option<TokenKind>,
-        body: Option<Vec<Stmt>>
+        // `None` for functions declared without a body
+        body: Option<Vec<Stmt>>,
+        public: bool,
+        span: Span
     },
     IfStmt {
         condition: Box<Expr>,
         then_branch: Vec<Stmt>,
-        else_branch: Option<Vec<Stmt>>
+        // `else if` is stored as an else branch holding a single IfStmt
+        else_branch: Option<Vec<Stmt>>,
+        span: Span
     },
     ForStmt {
-        variable: String,
+        variable: Symbol,
         iterable: Box<Expr>,
-        body: Vec<Stmt>
+        body: Vec<Stmt>,
+        span: Span
     },
     WhileStmt {
         condition: Box<Expr>,
-        body: Vec<Stmt>
+        body: Vec<Stmt>,
+        span: Span
+    },
+    AssignStmt {
+        name: Symbol,
+        value: Box<Expr>,
+        span: Span
+    },
+    ReturnStmt {
+        value: Option<Box<Expr>>,
+        span: Span
     },
-    CallStmt(Vec<String>)
+    // A function call used as a statement, e.g. `foo(1);`
+    CallStmt(Box<Expr>)
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UseImports {
-    List(Vec<String>),
+    List(Vec<Symbol>),
     Wildcard
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FnParam {
-    name: String,
-    ty: TokenKind
-}
\ No newline at end of file
+    pub name: Symbol,
+    pub ty: TokenKind,
+    pub span: Span
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::BinaryOp { span, .. }
+            | Expr::UnaryOp { span, .. }
+            | Expr::FnCall { span, .. }
+            | Expr::Array { span, .. } => *span,
+        }
+    }
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::LetStmt { span, .. }
+            | Stmt::UseStmt { span, .. }
+            | Stmt::FnDecl { span, .. }
+            | Stmt::IfStmt { span, .. }
+            | Stmt::ForStmt { span, .. }
+            | Stmt::WhileStmt { span, .. }
+            | Stmt::AssignStmt { span, .. }
+            | Stmt::ReturnStmt { span, .. } => *span,
+            Stmt::CallStmt(call) => call.span(),
+        }
+    }
+}
+
+// Visitors
+///////////////////////////////////////////////////////////////////////////////////
+// A pass implements `Visitor` (or `VisitorMut` to change the tree) and
+// overrides only the `visit_*` methods for the nodes it cares about. The
+// defaults call the matching `walk_*` function, which visits every child,
+// so an override that still wants to reach the children calls `walk_*`
+// itself, before or after its own work.
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_param(&mut self, _param: &FnParam) {}
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stmt in program {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for stmt in block {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::LetStmt { value, .. } | Stmt::AssignStmt { value, .. } => visitor.visit_expr(value),
+        Stmt::UseStmt { .. } => {}
+        Stmt::FnDecl { params, body, .. } => {
+            for param in params {
+                visitor.visit_param(param);
+            }
+            if let Some(body) = body {
+                visitor.visit_block(body);
+            }
+        }
+        Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+        Stmt::ForStmt { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            visitor.visit_block(body);
+        }
+        Stmt::WhileStmt { condition, body, .. } => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(body);
+        }
+        Stmt::ReturnStmt { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        Stmt::CallStmt(call) => visitor.visit_expr(call),
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Literal { .. } | Expr::Variable { .. } => {}
+        Expr::BinaryOp { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::UnaryOp { expr, .. } => visitor.visit_expr(expr),
+        Expr::FnCall { args: exprs, .. } | Expr::Array { elements: exprs, .. } => {
+            for expr in exprs {
+                visitor.visit_expr(expr);
+            }
+        }
+    }
+}
+
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_param_mut(&mut self, _param: &mut FnParam) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for stmt in program {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    for stmt in block {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::LetStmt { value, .. } | Stmt::AssignStmt { value, .. } => visitor.visit_expr_mut(value),
+        Stmt::UseStmt { .. } => {}
+        Stmt::FnDecl { params, body, .. } => {
+            for param in params {
+                visitor.visit_param_mut(param);
+            }
+            if let Some(body) = body {
+                visitor.visit_block_mut(body);
+            }
+        }
+        Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+            visitor.visit_expr_mut(condition);
+            visitor.visit_block_mut(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block_mut(else_branch);
+            }
+        }
+        Stmt::ForStmt { iterable, body, .. } => {
+            visitor.visit_expr_mut(iterable);
+            visitor.visit_block_mut(body);
+        }
+        Stmt::WhileStmt { condition, body, .. } => {
+            visitor.visit_expr_mut(condition);
+            visitor.visit_block_mut(body);
+        }
+        Stmt::ReturnStmt { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expr_mut(value);
+            }
+        }
+        Stmt::CallStmt(call) => visitor.visit_expr_mut(call),
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Literal { .. } | Expr::Variable { .. } => {}
+        Expr::BinaryOp { left, right, .. } => {
+            visitor.visit_expr_mut(left);
+            visitor.visit_expr_mut(right);
+        }
+        Expr::UnaryOp { expr, .. } => visitor.visit_expr_mut(expr),
+        Expr::FnCall { args: exprs, .. } | Expr::Array { elements: exprs, .. } => {
+            for expr in exprs {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+    }
+}
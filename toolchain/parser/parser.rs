@@ -1,28 +1,181 @@
-use std::iter::Peekable;
-use crate::lexer::{Token, TokenKind};
+use crate::lexer::{Lexer, Span, Symbol, Token, TokenKind};
 use crate::parser::nodes::{Expr, Stmt, UseImports, Program, Block, FnParam};
 use crate::handle_error::{ErrorKind, Error};
 
-pub struct TokenStream {
-    tokens: Peekable<std::vec::IntoIter<Token>>
+pub struct TokenStream<'src> {
+    // The tokens read so far, without newlines, which carry no meaning to
+    // the parser (statements end with ';'). `index` is the next one.
+    tokens: Vec<Token<'src>>,
+    index: usize,
+    // How many tokens have been looked at, counting the end of the input
+    // as one past the last, for incremental parsing
+    seen: usize,
+    // Where further tokens come from, until it runs out
+    lexer: Option<Lexer<'src>>,
+    eof: Token<'src>,
+    // Errors from a lexer the stream reads from, in the order they came
+    lex_errors: Vec<Error>,
+    // Span of the last token handed out, used to close off node spans
+    prev_span: Span
 }
 
-impl TokenStream {
-    pub fn new(tokens: Vec<Token>) -> Self {
+/// A place in a `TokenStream` to go back to, for parsing speculatively.
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint {
+    index: usize,
+    prev_span: Span,
+}
+
+/// The token marking the end of the input, right after `last`, for
+/// tokens that come without the source they were lexed from.
+fn end_after<'src>(last: Option<&Token<'src>>) -> Token<'src> {
+    match last {
+        None => Token::new(TokenKind::Eof, "", 1, 1, 0),
+        Some(token) if token.kind == TokenKind::Newline => Token::new(TokenKind::Eof, "", token.line + 1, 1, token.pos + 1),
+        Some(token) => Token::new(TokenKind::Eof, "", token.line, token.column + token.text.len(), token.pos + token.text.len()),
+    }
+}
+
+impl<'src> TokenStream<'src> {
+    /// A stream over `tokens`, ending right after the last one. Anything
+    /// the source has after it, like trailing comments, is unknown here;
+    /// `with_eof` takes the end from the lexer instead.
+    pub fn new(tokens: Vec<Token<'src>>) -> Self {
+        let eof = end_after(tokens.last());
+        TokenStream::with_eof(tokens, eof)
+    }
+
+    /// A stream over `tokens` that ends at `eof`, usually `Lexed::eof`.
+    pub fn with_eof(tokens: Vec<Token<'src>>, eof: Token<'src>) -> Self {
+        let mut stream = TokenStream::with(None);
+        stream.tokens = tokens.into_iter().filter(|token| token.kind != TokenKind::Newline).collect();
+        stream.eof = eof;
+        stream
+    }
+
+    /// A stream that lexes tokens as the parser asks for them, rather than
+    /// all before parsing starts. Errors from the lexer are kept aside for
+    /// `take_lex_errors`.
+    pub fn from_lexer(lexer: Lexer<'src>) -> Self {
+        TokenStream::with(Some(lexer))
+    }
+
+    fn with(lexer: Option<Lexer<'src>>) -> Self {
         TokenStream {
-            tokens: tokens.into_iter().peekable(),
+            tokens: vec![],
+            index: 0,
+            seen: 0,
+            lexer,
+            eof: end_after(None),
+            lex_errors: vec![],
+            prev_span: Span::default(),
         }
     }
 
-    pub fn next(&mut self) -> Option<Token> {
-        self.tokens.next()
+    /// The errors the lexer reported so far.
+    pub fn take_lex_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.lex_errors)
     }
 
-    pub fn peek(&mut self) -> Option<&Token> {
-        self.tokens.peek()
+    /// Reads one more token or error from the lexer. False once it has
+    /// run out.
+    fn pull(&mut self) -> bool {
+        let Some(lexer) = &mut self.lexer else { return false };
+        match lexer.next() {
+            Some(Ok(token)) => {
+                if token.kind != TokenKind::Newline {
+                    self.tokens.push(token);
+                }
+            }
+            Some(Err(error)) => self.lex_errors.push(error),
+            None => {
+                self.eof = lexer.eof();
+                self.lexer = None;
+            }
+        }
+        true
     }
 
-    pub fn expect(&mut self, kind: TokenKind) -> Result<Token, Error> {
+    /// Makes sure the token `n` places ahead is read, if the input has one.
+    fn fill(&mut self, n: usize) {
+        self.seen = self.seen.max(self.index + n + 1);
+        while self.tokens.len() <= self.index + n && self.pull() {}
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Token<'src>> {
+        self.fill(0);
+        let token = *self.tokens.get(self.index)?;
+        self.index += 1;
+        self.prev_span = token.span();
+        Some(token)
+    }
+
+    /// The next token, or `None` at the end of the input.
+    pub fn peek(&mut self) -> Option<&Token<'src>> {
+        self.fill(0);
+        self.tokens.get(self.index)
+    }
+
+    /// The token `n` places ahead (0 is the one `next` returns). Past the
+    /// end of the input this is the EOF token.
+    pub fn peek_nth(&mut self, n: usize) -> &Token<'src> {
+        self.fill(n);
+        self.tokens.get(self.index + n).unwrap_or(&self.eof)
+    }
+
+    /// The EOF token, at the end of the input.
+    pub fn eof(&mut self) -> Token<'src> {
+        while self.pull() {}
+        self.seen = self.tokens.len() + 1;
+        self.eof
+    }
+
+    /// The furthest token looked at so far, or `None` if that was the end
+    /// of the input. A parse of the tokens up to here doesn't depend on
+    /// anything after it.
+    pub(crate) fn furthest(&self) -> Option<Span> {
+        self.tokens.get(self.seen.checked_sub(1)?).map(Token::span)
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { index: self.index, prev_span: self.prev_span }
+    }
+
+    /// Goes back to `checkpoint`, so the tokens after it are read again.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.index = checkpoint.index;
+        self.prev_span = checkpoint.prev_span;
+    }
+
+    pub fn prev_span(&self) -> Span {
+        self.prev_span
+    }
+
+    pub fn is_eof(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn peek_is(&mut self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|token| token.kind == kind)
+    }
+
+    /// Consumes the next token if it is a `kind`.
+    pub fn eat(&mut self, kind: TokenKind) -> bool {
+        let found = self.peek_is(kind);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    // Running out of tokens, reported where the input ends
+    fn eof_error(&mut self, message: impl Into<String>) -> Error {
+        let eof = self.eof();
+        Error::new(ErrorKind::UnexpectedEof, message, eof.line, eof.column)
+    }
+
+    pub fn expect(&mut self, kind: TokenKind) -> Result<Token<'src>, Error> {
         match self.next() {
             Some(token) if token.kind == kind => Ok(token),
             Some(tok) => Err(Error::new(
@@ -31,15 +184,10 @@ impl TokenStream {
                 tok.line,
                 tok.column
             )),
-            None => Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                format!("Expected {}, found end of file", kind),
-                0,
-                0
-            ))
+            None => Err(self.eof_error(format!("Expected {}, found end of file", kind)))
         }
     }
-    pub fn expect_either(&mut self, kind: Vec<TokenKind>) -> Result<Token, Error> {
+    pub fn expect_either(&mut self, kind: Vec<TokenKind>) -> Result<Token<'src>, Error> {
         match self.next() {
             Some(token) if kind.contains(&token.kind) => Ok(token),
             Some(tok) => Err(Error::new(
@@ -48,12 +196,7 @@ impl TokenStream {
                 tok.line,
                 tok.column
             )),
-            None => Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                format!("Expected either {:?}, found end of file", kind),
-                0,
-                0
-            ))
+            None => Err(self.eof_error(format!("Expected either {:?}, found end of file", kind)))
         }
     }
 }
@@ -65,82 +208,84 @@ impl TokenStream {
 /*
 
 pub fn p_STMTNAME(stream: &mut TokenStream) -> Result<Stmt, Error> {
-    
+
 }
 
 */
 
+// The types that can be written after a ':' (or after a function's parameters)
+const TYPE_KINDS: [TokenKind; 3] = [TokenKind::I32, TokenKind::BOOL, TokenKind::STR];
+
+fn p_type(stream: &mut TokenStream) -> Result<TokenKind, Error> {
+    Ok(stream.expect_either(TYPE_KINDS.to_vec())?.kind)
+}
+
 pub fn p_let_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
     // syntax: (let|const) name[: type] = expr;
-    stream.expect_either(vec![TokenKind::Let, TokenKind::Const])?;
+    let keyword = stream.expect_either(vec![TokenKind::Let, TokenKind::Const])?;
 
     let name = stream.expect(TokenKind::Identifier)?;
     let mut ty = None;
 
-    if let Some(colon) = stream.peek() {
-        if colon.kind == TokenKind::Colon {
-            stream.next();
-            let ty_token = stream.expect_either(vec![
-                TokenKind::I32,
-                TokenKind::BOOL,
-                TokenKind::STR
-            ])?;
-            ty = Some(ty_token.kind);
-        }
+    if stream.eat(TokenKind::Colon) {
+        ty = Some(p_type(stream)?);
     }
 
     stream.expect(TokenKind::Equal)?;
 
-    let value_token = stream.expect(TokenKind::Integer)?;
-    //TODO: Add expression parsing
-    // For now, it's only integers
-    let value = Box::new(Expr::Literal {
-        kind: TokenKind::Integer,
-        value: value_token.value
-    });
+    let value = Box::new(parse_expr(stream)?);
 
     // Can't forget the semi-colon
     stream.expect(TokenKind::Semicolon)?;
 
     Ok(Stmt::LetStmt {
-        name: name.value,
+        name: name.symbol(),
         ty,
         value,
+        constant: keyword.kind == TokenKind::Const,
+        span: keyword.span().to(stream.prev_span()),
     })
-}  
+}
 
 pub fn p_use_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
-    // syntax: use stator [(import|*), [import], [import], ...]
-    stream.expect(TokenKind::Use)?;
-    let stator = stream.expect(TokenKind::Identifier);
-    
+    // syntax: use stator[.stator...] [(import|*), [import], [import], ...]
+    let keyword = stream.expect(TokenKind::Use)?;
+    let mut stator = stream.expect(TokenKind::Identifier)?.symbol();
+    while stream.eat(TokenKind::Dot) {
+        stator = Symbol::intern(&format!("{}.{}", stator, stream.expect(TokenKind::Identifier)?.value()));
+    }
+
     // Open square
     stream.expect(TokenKind::LSquare)?;
 
     // Loop until found closing square
     // But maybe also wildcard, idk
     let mut imports = UseImports::List(vec![]);
-    let mut import_list: Vec<String> = vec![];
+    let mut import_list: Vec<Symbol> = vec![];
     loop {
-        let curr = stream.next().unwrap();
+        let curr = match stream.next() {
+            Some(token) => token,
+            None => return Err(stream.eof_error("Expected ']', found end of file"))
+        };
+        let span = keyword.span().to(curr.span());
 
         if curr.kind == TokenKind::RSquare {
             // if the wildcard is used, then we dump the list
             if let UseImports::Wildcard = imports {
-                return Ok(Stmt::UseStmt { stator: stator.unwrap().value, imports: imports })
+                return Ok(Stmt::UseStmt { stator, imports, span })
             } else {
-                return Ok(Stmt::UseStmt { stator: stator.unwrap().value, imports: UseImports::List(import_list) })
+                return Ok(Stmt::UseStmt { stator, imports: UseImports::List(import_list), span })
             }
         } else if curr.kind == TokenKind::Star {
             imports = UseImports::Wildcard;
         } else if curr.kind == TokenKind::Identifier {
-            import_list.push(curr.value);
+            import_list.push(curr.symbol());
         } else if curr.kind == TokenKind::Comma {
             // do nothing, becuase what is there to do
             continue;
         } else {
             return Err(Error::new(
-                ErrorKind::InvalidToken, 
+                ErrorKind::InvalidToken,
                 format!("Expected either ']', ',' or identifier, found {}", curr.kind),
                 curr.line,
                 curr.column
@@ -150,105 +295,188 @@ pub fn p_use_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
 }
 
 pub fn p_if_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
-    // syntax: if expr { stmt; [stmt;]...} [else {stmt; [stmt;]...}]
-    stream.expect(TokenKind::If)?;
-
-    // Again, I don't have expr parsing yet, so it will just be a literal for now
-    let cond = Box::new(Expr::Literal {
-        kind: TokenKind::Boolean,
-        value: "true".to_string()
-    });
-
-    stream.expect(TokenKind::LCurly)?;
+    // syntax: if expr { stmt; [stmt;]...} [else (if ...|{stmt; [stmt;]...})]
+    let keyword = stream.expect(TokenKind::If)?;
 
-    #[allow(unused_mut)]
-    let mut then_stmts: Vec<Stmt> = vec![];
+    let condition = Box::new(parse_expr(stream)?);
+    let then_branch = parse_block(stream)?;
 
-    parse_block(stream)?;
-
-    if let Some(else_token) = stream.peek() {
-        if else_token.kind == TokenKind::Else {
-            stream.next();
-
-            stream.expect(TokenKind::LCurly)?;
-            stream.expect(TokenKind::RCurly)?;
+    let mut else_branch = None;
+    if stream.eat(TokenKind::Else) {
+        if stream.peek_is(TokenKind::If) {
+            else_branch = Some(vec![p_if_stmt(stream)?]);
+        } else {
+            else_branch = Some(parse_block(stream)?);
         }
     }
 
     Ok(Stmt::IfStmt {
-        condition: cond,
-        then_branch: then_stmts,
-        else_branch: None,
+        condition,
+        then_branch,
+        else_branch,
+        span: keyword.span().to(stream.prev_span()),
     })
 }
 
 pub fn p_for_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
     // syntax: for variable in Iterable { stmt; [stmt;]...}
-    stream.expect(TokenKind::For)?;
+    let keyword = stream.expect(TokenKind::For)?;
 
     let var = stream.expect(TokenKind::Identifier)?;
-    
-    // For now, we only assume the loop is over a collection
-    // which has been moved into the variable
+
     stream.expect(TokenKind::In)?;
-    let iterable = stream.expect(TokenKind::Identifier)?;
+    let iterable = Box::new(parse_expr(stream)?);
 
     // Enter loop body
     let body_stmts = parse_block(stream)?;
 
     Ok(Stmt::ForStmt {
-        variable: var.value,
-        iterable: Box::new(Expr::Variable {
-            name: iterable.value,
-            ty: None // No type inference yet
-        }),
-        body: body_stmts
+        variable: var.symbol(),
+        iterable,
+        body: body_stmts,
+        span: keyword.span().to(stream.prev_span()),
     })
 
 }
 
 pub fn p_while_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
     // syntax: while condition {stmt; [stmt;]...}
-    stream.expect(TokenKind::While)?;
+    let keyword = stream.expect(TokenKind::While)?;
 
-    let condition_token = stream.expect(TokenKind::Boolean)?;
-    let condition: Box<Expr> = Box::new(Expr::Literal {
-        kind: TokenKind::Boolean,
-        value: condition_token.value,
-    });
+    let condition = Box::new(parse_expr(stream)?);
 
     let body_stmts = parse_block(stream)?;
     Ok(Stmt::WhileStmt {
         condition,
         body: body_stmts,
+        span: keyword.span().to(stream.prev_span()),
     })
 }
 
+pub fn p_fn_decl(stream: &mut TokenStream) -> Result<Stmt, Error> {
+    // syntax: [pub] fn name([param: type], ...) [type] ({ stmt; [stmt;]...} | ;)
+    let first = stream.expect_either(vec![TokenKind::Pub, TokenKind::Fn])?;
+    let public = first.kind == TokenKind::Pub;
+    if public {
+        stream.expect(TokenKind::Fn)?;
+    }
+
+    let name = stream.expect(TokenKind::Identifier)?;
+
+    stream.expect(TokenKind::LParen)?;
+    let mut params: Vec<FnParam> = vec![];
+    while !stream.peek_is(TokenKind::RParen) {
+        let param = stream.expect(TokenKind::Identifier)?;
+        stream.expect(TokenKind::Colon)?;
+        let ty = p_type(stream)?;
+        params.push(FnParam {
+            name: param.symbol(),
+            ty,
+            span: param.span().to(stream.prev_span()),
+        });
+
+        if !stream.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    stream.expect(TokenKind::RParen)?;
+
+    let mut return_ty = None;
+    if stream.peek().is_some_and(|token| TYPE_KINDS.contains(&token.kind)) {
+        return_ty = Some(p_type(stream)?);
+    }
+
+    // A declaration without a body ends with a semi-colon
+    let body = if stream.eat(TokenKind::Semicolon) {
+        None
+    } else {
+        Some(parse_block(stream)?)
+    };
+
+    Ok(Stmt::FnDecl {
+        name: name.symbol(),
+        params,
+        return_ty,
+        body,
+        public,
+        span: first.span().to(stream.prev_span()),
+    })
+}
+
+pub fn p_return_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
+    // syntax: return [expr];
+    let keyword = stream.expect(TokenKind::Return)?;
+
+    let mut value = None;
+    if !stream.peek_is(TokenKind::Semicolon) {
+        value = Some(Box::new(parse_expr(stream)?));
+    }
+    stream.expect(TokenKind::Semicolon)?;
+
+    Ok(Stmt::ReturnStmt {
+        value,
+        span: keyword.span().to(stream.prev_span()),
+    })
+}
+
+pub fn p_expr_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
+    // syntax: name = expr; | call(args);
+    if stream.peek_nth(0).kind == TokenKind::Identifier && stream.peek_nth(1).kind == TokenKind::Equal {
+        let name = stream.expect(TokenKind::Identifier)?;
+        stream.expect(TokenKind::Equal)?;
+        let value = Box::new(parse_expr(stream)?);
+        stream.expect(TokenKind::Semicolon)?;
+        return Ok(Stmt::AssignStmt { name: name.symbol(), value, span: name.span().to(stream.prev_span()) });
+    }
+
+    let stmt = match parse_expr(stream)? {
+        call @ Expr::FnCall { .. } => Stmt::CallStmt(Box::new(call)),
+        other => {
+            let span = other.span();
+            return Err(Error::new(
+                ErrorKind::UnexpectedToken,
+                "Expected an assignment or a function call",
+                span.line,
+                span.column
+            ));
+        }
+    };
+
+    stream.expect(TokenKind::Semicolon)?;
+    Ok(stmt)
+}
+
 // Special parsing
 
 pub fn parse_block(stream: &mut TokenStream) -> Result<Block, Error> {
-    // Parsing blocks of statements.
+    // Parsing blocks of statements, including the surrounding curlies.
     // This can be used to detect single statements (e.g. dull functions)
+    stream.expect(TokenKind::LCurly)?;
 
     let mut block: Block = vec![];
-    while let Some(token) = stream.peek() {
-        if token.kind == TokenKind::RCurly {
-            stream.next(); // Consume the closing curly
-            break;
+    loop {
+        match stream.peek() {
+            Some(token) if token.kind == TokenKind::RCurly => {
+                stream.next(); // Consume the closing curly
+                break;
+            }
+            Some(_) => block.push(parse_stmt(stream)?),
+            None => return Err(stream.eof_error("Expected }, found end of file"))
         }
-
-        let stmt = parse_stmt(stream)?;
-        block.push(stmt);
     }
     Ok(block)
 }
 
-pub fn parse_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> { // TODO: Find the type to be returned
+pub fn parse_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> {
     // This is for parsing all the statements together
     // Contributers, if you add a new statement, please follow
     // the patterns below to create one. Ensure you have made the
     // 'p_{name}_stmt' before adding it.
-    match stream.peek().unwrap().kind {
+    if stream.is_eof() {
+        return Err(stream.eof_error("Expected a statement, found end of file"));
+    }
+    let token = stream.peek_nth(0);
+    match token.kind {
         TokenKind::Let | TokenKind::Const => {
             p_let_stmt(stream)
         },
@@ -264,21 +492,183 @@ pub fn parse_stmt(stream: &mut TokenStream) -> Result<Stmt, Error> { // TODO: Fi
         TokenKind::While => {
             p_while_stmt(stream)
         },
+        TokenKind::Fn | TokenKind::Pub => {
+            p_fn_decl(stream)
+        },
+        TokenKind::Return => {
+            p_return_stmt(stream)
+        },
+        TokenKind::Identifier => {
+            p_expr_stmt(stream)
+        },
         _ => {
-            // Currently, most of rotor's code will end up here
-            // because it is still in development.
-            Err(Error {
-                kind: ErrorKind::InvalidToken,
-                message: "Found invalid token".to_string(), // TODO: Make a better error message
-                line: stream.peek().unwrap().line,
-                column: stream.peek().unwrap().column
+            Err(Error::new(
+                ErrorKind::InvalidToken,
+                format!("Expected a statement, found {}", token.kind),
+                token.line,
+                token.column
+            ))
+        }
+    }
+}
+
+pub fn parse_program(stream: &mut TokenStream) -> Result<Program, Error> {
+    let mut program: Program = vec![];
+    while !stream.is_eof() {
+        program.push(parse_stmt(stream)?);
+    }
+    Ok(program)
+}
+
+/// Parses a whole program, carrying on after a syntax error so that every
+/// error in the file is found. After an error, tokens are skipped up to the
+/// next `fn`, `pub` or `use`, which can only start a top-level item.
+pub fn parse_program_recovering(stream: &mut TokenStream) -> (Program, Vec<Error>) {
+    let mut program: Program = vec![];
+    let mut errors = vec![];
+    while !stream.is_eof() {
+        match parse_recovering(stream) {
+            Ok(stmt) => program.push(stmt),
+            Err(error) => errors.push(error),
+        }
+    }
+    (program, errors)
+}
+
+/// Parses one top-level statement, or skips past a syntax error as
+/// `parse_program_recovering` does.
+pub(crate) fn parse_recovering(stream: &mut TokenStream) -> Result<Stmt, Error> {
+    parse_stmt(stream).inspect_err(|_| {
+        stream.next();
+        while !stream.is_eof() && ![TokenKind::Fn, TokenKind::Pub, TokenKind::Use].iter().any(|kind| stream.peek_is(*kind)) {
+            stream.next();
+        }
+    })
+}
+
+// Expression parsing
+///////////////////////////////////////////////////////////////////////////////////
+// Binary operators are parsed by precedence climbing. A higher binding
+// power binds tighter, and every operator is left associative.
+
+fn binding_power(kind: &TokenKind) -> Option<u8> {
+    match kind {
+        TokenKind::Or => Some(1),
+        TokenKind::And => Some(2),
+        TokenKind::EqualEqual | TokenKind::NotEqual => Some(3),
+        TokenKind::LessThan
+        | TokenKind::GreaterThan
+        | TokenKind::LessThanOrEqual
+        | TokenKind::GreaterThanOrEqual => Some(4),
+        TokenKind::Range => Some(5),
+        TokenKind::Plus | TokenKind::Line => Some(6),
+        TokenKind::Star | TokenKind::Slash | TokenKind::Modulus => Some(7),
+        _ => None,
+    }
+}
+
+pub fn parse_expr(stream: &mut TokenStream) -> Result<Expr, Error> {
+    p_binary_expr(stream, 0)
+}
+
+fn p_binary_expr(stream: &mut TokenStream, min_power: u8) -> Result<Expr, Error> {
+    let mut left = p_unary_expr(stream)?;
+
+    loop {
+        let power = match stream.peek().and_then(|token| binding_power(&token.kind)) {
+            Some(power) if power > min_power => power,
+            _ => break,
+        };
+        let op = stream.next().unwrap().kind;
+        let right = p_binary_expr(stream, power)?;
+        let span = left.span().to(right.span());
+        left = Expr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+            span,
+        };
+    }
+
+    Ok(left)
+}
+
+fn p_unary_expr(stream: &mut TokenStream) -> Result<Expr, Error> {
+    if stream.peek_is(TokenKind::Line) || stream.peek_is(TokenKind::Not) {
+        let op = stream.next().unwrap();
+        let expr = p_unary_expr(stream)?;
+        let span = op.span().to(expr.span());
+        return Ok(Expr::UnaryOp {
+            op: op.kind,
+            expr: Box::new(expr),
+            span,
+        });
+    }
+    p_primary_expr(stream)
+}
+
+fn p_primary_expr(stream: &mut TokenStream) -> Result<Expr, Error> {
+    let token = match stream.next() {
+        Some(token) => token,
+        None => return Err(stream.eof_error("Expected an expression, found end of file"))
+    };
+
+    match token.kind {
+        TokenKind::Integer | TokenKind::Float | TokenKind::String | TokenKind::Boolean => {
+            let span = token.span();
+            Ok(Expr::Literal { kind: token.kind, value: token.value().to_string(), span })
+        }
+        TokenKind::Identifier => {
+            let start = token.span();
+            let mut name = token.symbol();
+
+            // Dotted paths like `console.out` are only valid as calls
+            let mut dotted = false;
+            while stream.eat(TokenKind::Dot) {
+                name = Symbol::intern(&format!("{}.{}", name, stream.expect(TokenKind::Identifier)?.value()));
+                dotted = true;
+            }
+
+            if stream.eat(TokenKind::LParen) {
+                let args = p_expr_list(stream, TokenKind::RParen)?;
+                return Ok(Expr::FnCall { name, args, span: start.to(stream.prev_span()) });
+            }
+            if dotted {
+                stream.expect(TokenKind::LParen)?;
+            }
+            Ok(Expr::Variable { name, ty: None, span: start })
+        }
+        TokenKind::LParen => {
+            let expr = parse_expr(stream)?;
+            stream.expect(TokenKind::RParen)?;
+            Ok(expr)
+        }
+        TokenKind::LSquare => {
+            let elements = p_expr_list(stream, TokenKind::RSquare)?;
+            Ok(Expr::Array {
+                length: Some(elements.len()),
+                elements,
+                span: token.span().to(stream.prev_span()),
             })
         }
+        _ => Err(Error::new(
+            ErrorKind::UnexpectedToken,
+            format!("Expected an expression, found {}", token.kind),
+            token.line,
+            token.column
+        ))
     }
 }
 
-pub fn parse_expr() {
-    // This function will remain empty until I learn how to parse expressions.
-    // Unless a contributer who knows how to do this wants to do it.
-    // Please do not put work into here, but into the feat/expr branch.
-}
\ No newline at end of file
+fn p_expr_list(stream: &mut TokenStream, close: TokenKind) -> Result<Vec<Expr>, Error> {
+    // syntax: [expr], [expr], ... close
+    let mut exprs = vec![];
+    while !stream.peek_is(close) {
+        exprs.push(parse_expr(stream)?);
+        if !stream.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    stream.expect(close)?;
+    Ok(exprs)
+}
@@ -0,0 +1,373 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// `rotor.conf` is a small line based format: `key = value` pairs, where
+// a value is a "string", an integer or true/false, grouped under
+// `[section]` headers. `#` starts a comment. The keys before the first
+// section describe the project, `[dependencies]` maps stator names to
+// the directories of other projects, and `[profile.<name>]` sections
+// configure builds:
+//
+//     name = "my_first_project"
+//     version = "0.1.0"
+//
+//     [dependencies]
+//     shapes = "../shapes"
+//
+//     [profile.release]
+//     opt-level = 2
+//
+// Every problem is reported with the line and column it was found at,
+// and parsing carries on after an error so all of them are found at once.
+use std::collections::HashSet;
+use std::path::PathBuf;
+use crate::handle_error::{ErrorKind, Error};
+use crate::opt::OptLevel;
+use crate::project::project::is_valid_name;
+
+/// The only edition so far.
+pub const EDITION: &str = "2025";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A program, starting at `main`
+    Bin,
+    /// A stator other projects depend on
+    Lib,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// The stator name the dependency is imported by.
+    pub name: String,
+    /// The dependency's project directory, relative to this project.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub opt_level: OptLevel,
+    pub backend: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    pub kind: Kind,
+    /// The entry stator, relative to the project directory.
+    pub entry: PathBuf,
+    pub dependencies: Vec<Dependency>,
+    /// `dev` and `release` always exist; other profiles are added by name.
+    pub profiles: Vec<Profile>,
+}
+
+impl Manifest {
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl Value {
+    fn describe(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Int(_) => "an integer",
+            Value::Bool(_) => "a boolean",
+        }
+    }
+}
+
+enum Section {
+    Package,
+    Dependencies,
+    Profile(usize),
+    // Already reported; its keys are skipped
+    Unknown,
+}
+
+struct Parser {
+    line: usize,
+    errors: Vec<Error>,
+}
+
+impl Parser {
+    fn error(&mut self, message: impl Into<String>, column: usize) {
+        self.errors.push(Error::new(ErrorKind::InvalidManifest, message, self.line, column));
+    }
+
+    /// Parses the value starting at `column`, checking nothing follows it.
+    fn value(&mut self, text: &str, column: usize) -> Option<Value> {
+        let chars: Vec<char> = text.chars().collect();
+        let (value, used) = if chars.first() == Some(&'"') {
+            let mut out = String::new();
+            let mut i = 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        self.error("Unterminated string", column);
+                        return None;
+                    }
+                    Some('"') => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            _ => {
+                                self.error("Invalid escape sequence", column + i);
+                                return None;
+                            }
+                        };
+                        out.push(escaped);
+                        i += 2;
+                    }
+                    Some(&ch) => {
+                        out.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            (Value::Str(out), i + 1)
+        } else {
+            let used = chars.iter().position(|ch| ch.is_whitespace()).unwrap_or(chars.len());
+            let word: String = chars[..used].iter().collect();
+            let value = match word.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "" => {
+                    self.error("Expected a value after `=`", column);
+                    return None;
+                }
+                _ => match word.parse::<i64>() {
+                    Ok(number) => Value::Int(number),
+                    Err(_) => {
+                        self.error(format!("Invalid value `{}` (strings need double quotes)", word), column);
+                        return None;
+                    }
+                },
+            };
+            (value, used)
+        };
+        let rest: String = chars[used..].iter().collect();
+        if !rest.trim().is_empty() {
+            let offset = rest.chars().count() - rest.trim_start().chars().count();
+            self.error("Unexpected text after the value", column + used + offset);
+            return None;
+        }
+        Some(value)
+    }
+}
+
+/// Removes a `#` comment, unless it is inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+fn is_valid_version(version: &str) -> bool {
+    let parts: Vec<&str> = version.split('.').collect();
+    parts.len() == 3 && parts.iter().all(|part| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+/// The profiles every project has, before its manifest changes them.
+pub fn builtin_profiles() -> Vec<Profile> {
+    vec![
+        Profile { name: "dev".to_string(), opt_level: OptLevel::O0, backend: "c".to_string() },
+        Profile { name: "release".to_string(), opt_level: OptLevel::O2, backend: "c".to_string() },
+    ]
+}
+
+/// Parses the contents of a `rotor.conf`.
+pub fn parse_manifest(source: &str) -> Result<Manifest, Vec<Error>> {
+    let mut parser = Parser { line: 0, errors: vec![] };
+    let mut name = None;
+    let mut version = None;
+    let mut edition = None;
+    let mut kind = None;
+    let mut entry = None;
+    let mut dependencies: Vec<Dependency> = vec![];
+    let mut profiles = builtin_profiles();
+
+    let mut section = Section::Package;
+    let mut seen_keys: HashSet<String> = HashSet::new();
+    let mut seen_sections: HashSet<String> = HashSet::new();
+    for (i, raw) in source.lines().enumerate() {
+        parser.line = i + 1;
+        let line = strip_comment(raw);
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let column = raw[..indent].chars().count() + 1;
+
+        if let Some(header) = line.strip_prefix('[') {
+            let Some(header) = header.strip_suffix(']').map(str::trim) else {
+                parser.error("Expected `]` at the end of the section header", column + line.chars().count());
+                continue;
+            };
+            if !seen_sections.insert(header.to_string()) {
+                parser.error(format!("Section `[{}]` appears more than once", header), column);
+                continue;
+            }
+            seen_keys.clear();
+            section = if header == "dependencies" {
+                Section::Dependencies
+            } else if let Some(profile) = header.strip_prefix("profile.").filter(|profile| is_valid_key(profile)) {
+                match profiles.iter().position(|existing| existing.name == profile) {
+                    Some(index) => Section::Profile(index),
+                    None => {
+                        profiles.push(Profile { name: profile.to_string(), opt_level: OptLevel::O0, backend: "c".to_string() });
+                        Section::Profile(profiles.len() - 1)
+                    }
+                }
+            } else {
+                parser.error(format!("Unknown section `[{}]`", header), column);
+                Section::Unknown
+            };
+            continue;
+        }
+        if let Section::Unknown = section {
+            continue;
+        }
+
+        let Some(equals) = line.find('=') else {
+            parser.error("Expected `key = value`", column);
+            continue;
+        };
+        let key = line[..equals].trim();
+        let value = &line[equals + 1..];
+        let value_column = column + line[..=equals].chars().count() + value.chars().count() - value.trim_start().chars().count();
+        if !is_valid_key(key) {
+            parser.error(format!("Invalid key `{}`", key), column);
+            continue;
+        }
+        let Some(value) = parser.value(value.trim(), value_column) else { continue };
+        if !seen_keys.insert(key.to_string()) {
+            parser.error(format!("`{}` is set more than once", key), column);
+            continue;
+        }
+
+        let expect_str = |parser: &mut Parser, value: Value| match value {
+            Value::Str(text) => Some(text),
+            other => {
+                parser.error(format!("`{}` must be a string, found {}", key, other.describe()), value_column);
+                None
+            }
+        };
+        match section {
+            Section::Package => match key {
+                "name" => {
+                    let Some(text) = expect_str(&mut parser, value) else { continue };
+                    if !is_valid_name(&text) {
+                        parser.error(format!("`{}` is not a valid project name", text), value_column);
+                    }
+                    name = Some(text);
+                }
+                "version" => {
+                    let Some(text) = expect_str(&mut parser, value) else { continue };
+                    if !is_valid_version(&text) {
+                        parser.error(format!("`{}` is not a version of the form `1.2.3`", text), value_column);
+                    }
+                    version = Some(text);
+                }
+                "edition" => {
+                    let Some(text) = expect_str(&mut parser, value) else { continue };
+                    if text != EDITION {
+                        parser.error(format!("Unknown edition `{}` (the only edition is {})", text, EDITION), value_column);
+                    }
+                    edition = Some(text);
+                }
+                "kind" => {
+                    let Some(text) = expect_str(&mut parser, value) else { continue };
+                    kind = match text.as_str() {
+                        "bin" => Some(Kind::Bin),
+                        "lib" => Some(Kind::Lib),
+                        _ => {
+                            parser.error(format!("`kind` must be \"bin\" or \"lib\", found \"{}\"", text), value_column);
+                            continue;
+                        }
+                    };
+                }
+                "entry" => entry = expect_str(&mut parser, value).map(PathBuf::from),
+                _ => parser.error(format!("Unknown key `{}`", key), column),
+            },
+            Section::Dependencies => {
+                let Some(path) = expect_str(&mut parser, value) else { continue };
+                if !is_valid_name(key) {
+                    parser.error(format!("`{}` is not a valid dependency name", key), column);
+                    continue;
+                }
+                dependencies.push(Dependency { name: key.to_string(), path: PathBuf::from(path) });
+            }
+            Section::Profile(index) => match (key, value) {
+                ("opt-level", Value::Int(level)) => match OptLevel::from_flag(&level.to_string()) {
+                    Some(level) => profiles[index].opt_level = level,
+                    None => parser.error(format!("`opt-level` must be 0, 1 or 2, found {}", level), value_column),
+                },
+                ("backend", Value::Str(backend)) => {
+                    if !matches!(backend.as_str(), "c" | "x86_64" | "wasm") {
+                        parser.error(format!("Unknown backend `{}` (expected c, x86_64 or wasm)", backend), value_column);
+                        continue;
+                    }
+                    profiles[index].backend = backend;
+                }
+                ("opt-level", other) => {
+                    parser.error(format!("`opt-level` must be an integer, found {}", other.describe()), value_column)
+                }
+                ("backend", other) => {
+                    parser.error(format!("`backend` must be a string, found {}", other.describe()), value_column)
+                }
+                _ => parser.error(format!("Unknown key `{}`", key), column),
+            },
+            Section::Unknown => {}
+        }
+    }
+
+    if name.is_none() {
+        parser.line = 0;
+        parser.error("The manifest has no `name`", 0);
+    }
+    if !parser.errors.is_empty() {
+        return Err(parser.errors);
+    }
+    let kind = kind.unwrap_or(Kind::Bin);
+    let entry = entry.unwrap_or_else(|| PathBuf::from(if kind == Kind::Lib { "src/lib.rt" } else { "src/main.rt" }));
+    Ok(Manifest {
+        name: name.unwrap(),
+        version: version.unwrap_or_else(|| "0.1.0".to_string()),
+        edition: edition.unwrap_or_else(|| EDITION.to_string()),
+        kind,
+        entry,
+        dependencies,
+        profiles,
+    })
+}
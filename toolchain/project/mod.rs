@@ -0,0 +1,4 @@
+pub mod project;
+pub mod manifest;
+pub use project::{Template, Project, new_project, init_project, is_valid_name, find_manifest, MANIFEST_FILE};
+pub use manifest::{Manifest, Kind, Dependency, Profile, parse_manifest, builtin_profiles, EDITION};
@@ -0,0 +1,216 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// A Rotor project is a directory holding a `rotor.conf` next to a `src/`
+// tree of stators. `rotor new` creates one in a fresh directory and
+// `rotor init` turns an existing directory into one. Neither ever
+// replaces a file: if any file of the layout already exists, nothing is
+// written at all.
+//
+// Commands run inside a project find it by walking up from the current
+// directory to the closest `rotor.conf`.
+use std::path::{Path, PathBuf};
+use crate::handle_error::{ErrorKind, Error};
+use crate::loader::Loader;
+use crate::parser::nodes::Program;
+use crate::project::manifest::{parse_manifest, Manifest};
+
+/// What a new project starts out as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// A program with a `main` in `src/main.rt`
+    Bin,
+    /// A stator for other projects to import, in `src/lib.rt`
+    Lib,
+}
+
+const MAIN_RT: &str = "use std.io [console]
+
+pub fn main() i32 {
+    console.out(\"Rotor is spinning!\");
+    return 0;
+}
+";
+
+const LIB_RT: &str = "// Public functions here can be imported by other stators.
+pub fn add(a: i32, b: i32) i32 {
+    return a + b;
+}
+";
+
+impl Template {
+    fn entry(self) -> &'static str {
+        match self {
+            Template::Bin => "src/main.rt",
+            Template::Lib => "src/lib.rt",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Template::Bin => MAIN_RT,
+            Template::Lib => LIB_RT,
+        }
+    }
+
+    fn manifest(self, name: &str) -> String {
+        let kind = match self {
+            Template::Bin => "bin",
+            Template::Lib => "lib",
+        };
+        format!(
+            "# The configuration of this project\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2025\"\n\
+             kind = \"{}\"\n\
+             entry = \"{}\"\n\
+             \n\
+             [dependencies]\n",
+            name,
+            kind,
+            self.entry()
+        )
+    }
+}
+
+/// Project names become stator paths, so they follow the identifier
+/// rules, with `-` allowed as well.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(first) if first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+fn project_error(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::Project, message, 0, 0)
+}
+
+fn check_name(name: &str) -> Result<(), Error> {
+    if is_valid_name(name) {
+        return Ok(());
+    }
+    Err(project_error(format!(
+        "`{}` is not a valid project name (use letters, digits, `_` and `-`, starting with a letter or `_`)",
+        name
+    )))
+}
+
+/// Writes the project layout into `dir`, which must exist, returning the
+/// files created.
+fn scaffold(dir: &Path, name: &str, template: Template) -> Result<Vec<PathBuf>, Error> {
+    check_name(name)?;
+    let files = [
+        (dir.join("rotor.conf"), template.manifest(name)),
+        (dir.join(template.entry()), template.source().to_string()),
+    ];
+    if let Some((existing, _)) = files.iter().find(|(file, _)| file.exists()) {
+        return Err(project_error(format!("`{}` already exists", existing.display())));
+    }
+    for (file, contents) in &files {
+        let written = std::fs::create_dir_all(file.parent().unwrap()).and_then(|_| std::fs::write(file, contents));
+        if let Err(error) = written {
+            return Err(project_error(format!("Unable to write `{}`: {}", file.display(), error)));
+        }
+    }
+    Ok(files.into_iter().map(|(file, _)| file).collect())
+}
+
+/// Creates the project `name` in a new directory `dir`.
+pub fn new_project(dir: &Path, name: &str, template: Template) -> Result<Vec<PathBuf>, Error> {
+    if dir.exists() {
+        return Err(project_error(format!("Destination `{}` already exists", dir.display())));
+    }
+    check_name(name)?;
+    if let Err(error) = std::fs::create_dir_all(dir) {
+        return Err(project_error(format!("Unable to create `{}`: {}", dir.display(), error)));
+    }
+    scaffold(dir, name, template)
+}
+
+/// Makes the existing directory `dir` a project, named after the directory.
+pub fn init_project(dir: &Path, template: Template) -> Result<Vec<PathBuf>, Error> {
+    let full = dir.canonicalize().map_err(|error| project_error(format!("Unable to open `{}`: {}", dir.display(), error)))?;
+    let name = full.file_name().unwrap_or_default().to_string_lossy().to_string();
+    scaffold(dir, &name, template)
+}
+
+/// The name of the manifest file at the root of every project.
+pub const MANIFEST_FILE: &str = "rotor.conf";
+
+/// The closest `rotor.conf` in `start` or any directory above it.
+pub fn find_manifest(start: &Path) -> Option<PathBuf> {
+    let start = start.canonicalize().ok()?;
+    start.ancestors().map(|dir| dir.join(MANIFEST_FILE)).find(|file| file.is_file())
+}
+
+/// A project on disk: the directory holding `rotor.conf`, and its contents.
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    /// Reads and parses the manifest at `manifest_file`.
+    pub fn open(manifest_file: &Path) -> Result<Project, Vec<Error>> {
+        let in_manifest = |error: Error| {
+            let message = format!("{} (in {})", error.message, manifest_file.display());
+            Error::new(error.kind, message, error.line, error.column)
+        };
+        let source = std::fs::read_to_string(manifest_file).map_err(|error| {
+            vec![in_manifest(project_error(format!("Unable to read the manifest: {}", error)))]
+        })?;
+        let manifest = parse_manifest(&source).map_err(|errors| errors.into_iter().map(in_manifest).collect::<Vec<_>>())?;
+        let root = manifest_file.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Ok(Project { root, manifest })
+    }
+
+    /// Opens the project `start` is in.
+    pub fn discover(start: &Path) -> Result<Project, Vec<Error>> {
+        match find_manifest(start) {
+            Some(manifest_file) => Project::open(&manifest_file),
+            None => Err(vec![project_error(format!(
+                "Could not find `{}` in `{}` or any directory above it",
+                MANIFEST_FILE,
+                start.display()
+            ))]),
+        }
+    }
+
+    /// The entry stator's file.
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.entry)
+    }
+
+    /// Registers the entry of every dependency with `loader`, including the
+    /// dependencies of dependencies. `seen` holds the projects already visited.
+    fn add_dependencies(&self, loader: &mut Loader, seen: &mut Vec<PathBuf>) -> Result<(), Vec<Error>> {
+        for dependency in &self.manifest.dependencies {
+            let root = self.root.join(&dependency.path);
+            let project = Project::open(&root.join(MANIFEST_FILE))?;
+            loader.add_dependency(&dependency.name, project.entry());
+            let canonical = root.canonicalize().unwrap_or(root);
+            if !seen.contains(&canonical) {
+                seen.push(canonical);
+                project.add_dependencies(loader, seen)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A loader for the project that knows its dependencies, ready to load
+    /// the entry stator.
+    pub fn loader(&self) -> Result<Loader, Vec<Error>> {
+        let entry = self.entry();
+        let mut loader = Loader::new(entry.parent().unwrap_or(Path::new(".")));
+        self.add_dependencies(&mut loader, &mut vec![])?;
+        Ok(loader)
+    }
+
+    /// Loads the project's program, with its dependencies.
+    pub fn load_program(&self) -> Result<Program, Vec<Error>> {
+        let mut loader = self.loader()?;
+        loader.load_entry(&self.entry())?;
+        loader.link()
+    }
+}
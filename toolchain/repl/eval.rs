@@ -0,0 +1,304 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// A tree-walking evaluator for linked, type-checked programs, so the REPL
+// can run code without a C compiler or assembler. It follows the compiled
+// code: i32 arithmetic wraps, dividing by zero panics with the runtime's
+// message, `&&` and `||` short-circuit, and a range loop evaluates its
+// bounds once. The standard library's intrinsics are implemented here.
+//
+// Globals live outside the evaluator, in a map the caller keeps, so their
+// values survive from one evaluation to the next.
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::{Span, Symbol, TokenKind};
+use crate::parser::nodes::{Block, Expr, FnParam, Program, Stmt};
+
+// Deep enough for real recursion, shallow enough not to overflow the stack
+const MAX_DEPTH: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Int(i32),
+    Bool(bool),
+    Str(String),
+    Void,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Void => write!(f, "()"),
+        }
+    }
+}
+
+enum Flow {
+    Next,
+    Return(Value),
+}
+
+struct Function<'a> {
+    params: &'a [FnParam],
+    // `None` for intrinsics
+    body: Option<&'a Block>,
+}
+
+pub struct Evaluator<'a> {
+    functions: HashMap<Symbol, Function<'a>>,
+    globals: &'a mut HashMap<Symbol, Value>,
+    // The locals of the running function, innermost scope last
+    scopes: Vec<HashMap<Symbol, Value>>,
+    depth: usize,
+}
+
+fn runtime(message: impl Into<String>, span: Span) -> Error {
+    Error::at(ErrorKind::Runtime, message, span)
+}
+
+fn literal(kind: TokenKind, value: &str) -> Value {
+    match kind {
+        TokenKind::Boolean => Value::Bool(value == "true"),
+        TokenKind::String => Value::Str(value.to_string()),
+        _ => Value::Int(value.parse().unwrap_or_default()),
+    }
+}
+
+impl<'a> Evaluator<'a> {
+    /// An evaluator for `program`. Top-level values missing from `globals`
+    /// are added with their initial value.
+    pub fn new(program: &'a Program, globals: &'a mut HashMap<Symbol, Value>) -> Self {
+        let mut functions = HashMap::new();
+        for stmt in program {
+            match stmt {
+                Stmt::FnDecl { name, params, body, .. } => {
+                    functions.insert(*name, Function { params, body: body.as_ref() });
+                }
+                // The type checker only allows literals here
+                Stmt::LetStmt { name, value, .. } => {
+                    if let Expr::Literal { kind, value, .. } = value.as_ref() {
+                        globals.entry(*name).or_insert_with(|| literal(*kind, value));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Evaluator { functions, globals, scopes: vec![], depth: 0 }
+    }
+
+    /// Calls the function `name`, which must exist.
+    pub fn call(&mut self, name: Symbol, args: Vec<Value>, span: Span) -> Result<Value, Error> {
+        let Some(function) = self.functions.get(&name) else {
+            return Err(runtime(format!("Unknown function `{}`", name), span));
+        };
+        let (params, body) = (function.params, function.body);
+        let Some(body) = body else { return intrinsic(&name, args, span) };
+        if self.depth == MAX_DEPTH {
+            return Err(runtime(format!("Stack overflow: more than {} nested calls", MAX_DEPTH), span));
+        }
+
+        let locals = params.iter().map(|param| param.name).zip(args).collect();
+        let caller = std::mem::replace(&mut self.scopes, vec![locals]);
+        self.depth += 1;
+        let flow = self.block(body);
+        self.depth -= 1;
+        self.scopes = caller;
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            Flow::Next => Ok(Value::Void),
+        }
+    }
+
+    /// Runs `body` as the top of a function and returns the variables it
+    /// declared, with their final values.
+    pub fn exec(&mut self, body: &[Stmt]) -> Result<HashMap<Symbol, Value>, Error> {
+        self.scopes.push(HashMap::new());
+        let flow = self.stmts(body);
+        let locals = self.scopes.pop().unwrap_or_default();
+        flow.map(|_| locals)
+    }
+
+    fn lookup(&mut self, name: Symbol) -> Option<&mut Value> {
+        match self.scopes.iter_mut().rev().find(|scope| scope.contains_key(&name)) {
+            Some(scope) => scope.get_mut(&name),
+            None => self.globals.get_mut(&name),
+        }
+    }
+
+    fn block(&mut self, block: &Block) -> Result<Flow, Error> {
+        self.scopes.push(HashMap::new());
+        let flow = self.stmts(block);
+        self.scopes.pop();
+        flow
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<Flow, Error> {
+        for stmt in stmts {
+            if let Flow::Return(value) = self.stmt(stmt)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<Flow, Error> {
+        match stmt {
+            Stmt::LetStmt { name, value, .. } => {
+                let value = self.expr(value)?;
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(*name, value);
+                }
+            }
+            Stmt::AssignStmt { name, value, span } => {
+                let value = self.expr(value)?;
+                match self.lookup(*name) {
+                    Some(slot) => *slot = value,
+                    None => return Err(runtime(format!("Unknown variable `{}`", name), *span)),
+                }
+            }
+            Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+                if self.condition(condition)? {
+                    return self.block(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.block(else_branch);
+                }
+            }
+            Stmt::WhileStmt { condition, body, .. } => {
+                while self.condition(condition)? {
+                    if let Flow::Return(value) = self.block(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Stmt::ForStmt { variable, iterable, body, span } => {
+                let Expr::BinaryOp { left, op: TokenKind::Range, right, .. } = iterable.as_ref() else {
+                    return Err(runtime("For loops can only iterate over ranges", *span));
+                };
+                let (start, end) = (self.int(left)?, self.int(right)?);
+                for index in start..end {
+                    self.scopes.push(HashMap::from([(*variable, Value::Int(index))]));
+                    let flow = self.block(body);
+                    self.scopes.pop();
+                    if let Flow::Return(value) = flow? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Stmt::ReturnStmt { value, .. } => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Void,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::CallStmt(call) => {
+                self.expr(call)?;
+            }
+            Stmt::FnDecl { .. } | Stmt::UseStmt { .. } => {}
+        }
+        Ok(Flow::Next)
+    }
+
+    fn condition(&mut self, expr: &Expr) -> Result<bool, Error> {
+        match self.expr(expr)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(runtime(format!("Expected a bool, found {}", other), expr.span())),
+        }
+    }
+
+    fn int(&mut self, expr: &Expr) -> Result<i32, Error> {
+        match self.expr(expr)? {
+            Value::Int(value) => Ok(value),
+            other => Err(runtime(format!("Expected an i32, found {}", other), expr.span())),
+        }
+    }
+
+    pub fn expr(&mut self, expr: &Expr) -> Result<Value, Error> {
+        match expr {
+            Expr::Literal { kind, value, .. } => Ok(literal(*kind, value)),
+            Expr::Variable { name, span, .. } => match self.lookup(*name) {
+                Some(value) => Ok(value.clone()),
+                None => Err(runtime(format!("Unknown variable `{}`", name), *span)),
+            },
+            Expr::BinaryOp { left, op: TokenKind::And, right, .. } => Ok(Value::Bool(self.condition(left)? && self.condition(right)?)),
+            Expr::BinaryOp { left, op: TokenKind::Or, right, .. } => Ok(Value::Bool(self.condition(left)? || self.condition(right)?)),
+            Expr::BinaryOp { left, op, right, span } => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+                binary(*op, left, right, *span)
+            }
+            Expr::UnaryOp { op, expr: inner, span } => match (op, self.expr(inner)?) {
+                (TokenKind::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+                (TokenKind::Line, Value::Int(value)) => Ok(Value::Int(value.wrapping_neg())),
+                (op, value) => Err(runtime(format!("Cannot apply {} to {}", op, value), *span)),
+            },
+            Expr::FnCall { name, args, span } => {
+                let args = args.iter().map(|arg| self.expr(arg)).collect::<Result<Vec<_>, _>>()?;
+                self.call(*name, args, *span)
+            }
+            Expr::Array { span, .. } => Err(runtime("Arrays are not supported yet", *span)),
+        }
+    }
+}
+
+fn binary(op: TokenKind, left: Value, right: Value, span: Span) -> Result<Value, Error> {
+    let value = match (op, &left, &right) {
+        (TokenKind::EqualEqual, _, _) => Value::Bool(left == right),
+        (TokenKind::NotEqual, _, _) => Value::Bool(left != right),
+        (_, Value::Int(a), Value::Int(b)) => {
+            let (a, b) = (*a, *b);
+            match op {
+                TokenKind::Plus => Value::Int(a.wrapping_add(b)),
+                TokenKind::Line => Value::Int(a.wrapping_sub(b)),
+                TokenKind::Star => Value::Int(a.wrapping_mul(b)),
+                TokenKind::Slash if b == 0 => return Err(runtime("panic: attempt to divide by zero", span)),
+                TokenKind::Slash => Value::Int(a.wrapping_div(b)),
+                TokenKind::Modulus if b == 0 => {
+                    return Err(runtime("panic: attempt to calculate the remainder with a divisor of zero", span))
+                }
+                TokenKind::Modulus => Value::Int(a.wrapping_rem(b)),
+                TokenKind::LessThan => Value::Bool(a < b),
+                TokenKind::GreaterThan => Value::Bool(a > b),
+                TokenKind::LessThanOrEqual => Value::Bool(a <= b),
+                TokenKind::GreaterThanOrEqual => Value::Bool(a >= b),
+                _ => return Err(runtime(format!("Cannot apply {} to {} and {}", op, left, right), span)),
+            }
+        }
+        _ => return Err(runtime(format!("Cannot apply {} to {} and {}", op, left, right), span)),
+    };
+    Ok(value)
+}
+
+/// The functions every backend implements natively (see `stdlib::INTRINSICS`).
+fn intrinsic(name: &str, args: Vec<Value>, span: Span) -> Result<Value, Error> {
+    let value = match (name, args.as_slice()) {
+        ("std.io.console.out", [message]) => {
+            println!("{}", message);
+            Value::Void
+        }
+        ("std.io.console.err", [message]) => {
+            eprintln!("{}", message);
+            Value::Void
+        }
+        ("std.io.console.read_line", []) => {
+            let mut line = String::new();
+            let _ = std::io::stdin().lock().read_line(&mut line);
+            Value::Str(line.trim_end_matches(['\n', '\r']).to_string())
+        }
+        ("std.process.exit", [Value::Int(code)]) => return Err(runtime(format!("The program exited with code {}", code), span)),
+        // The REPL has no command line of its own to hand out
+        ("std.process.arg_count", []) => Value::Int(0),
+        ("std.process.arg", [_]) => return Err(runtime("panic: argument index out of range", span)),
+        ("std.string.len", [Value::Str(s)]) => Value::Int(s.len() as i32),
+        ("std.string.byte_at", [Value::Str(s), Value::Int(index)]) => match usize::try_from(*index).ok().and_then(|index| s.as_bytes().get(index)) {
+            Some(byte) => Value::Int(*byte as i32),
+            None => return Err(runtime("panic: byte index out of range", span)),
+        },
+        _ => return Err(runtime(format!("`{}` has no body and can't be run here", name), span)),
+    };
+    Ok(value)
+}
@@ -0,0 +1,5 @@
+pub mod repl;
+pub mod eval;
+
+pub use repl::{Repl, Session, history_file, is_complete, run_stdio};
+pub use eval::{Evaluator, Value};
@@ -0,0 +1,407 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// `rotor repl`: read a line, evaluate it, print the result. Every input is
+// compiled as a whole program, so it is linked against the standard library
+// and the current project and type checked exactly as `rotor check` would,
+// then run by the evaluator in `eval.rs`.
+//
+// The session is the list of items entered so far, as source. Functions and
+// imports are kept as written; entering an item with the name of an earlier
+// one replaces it. A `let` or `const` is evaluated straight away and kept as
+// a declaration with a placeholder literal (top-level values must be
+// literals), while its real value lives in `globals`. Expressions are
+// compiled as the initializer of a local in a wrapper function, which is
+// how their type is found; anything else is the wrapper's body.
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use crate::handle_error::{ErrorKind, Error};
+use crate::lexer::{lex, Symbol, TokenKind};
+use crate::loader::Loader;
+use crate::parser::nodes::{Program, Stmt, UseImports};
+use crate::parser::{dump_expr, parse_expr, parse_program_recovering, TokenStream};
+use crate::project::find_manifest;
+use crate::repl::eval::{Evaluator, Value};
+use crate::typeck::{check_program, Type};
+
+const FILE: &str = "repl.rt";
+const WRAPPER: &str = "__repl";
+const VALUE: &str = "__value";
+const STACK_SIZE: usize = 256 << 20;
+
+const HELP: &str = "\
+Enter an expression to see its value and type, or a statement or item to
+run it. Input continues over several lines while braces are left open.
+
+  :type <expr>     Print the type of an expression
+  :ast <expr>      Print the syntax tree of an expression
+  :tokens <expr>   Print the tokens of an expression
+  :history         Print the inputs saved in the history file
+  :help            Print this help
+  :quit            Leave the REPL
+";
+
+#[derive(Clone)]
+struct Item {
+    // The names the item defines, e.g. a function's, or what a `use` imports
+    names: Vec<Symbol>,
+    source: String,
+}
+
+/// What a session has been given so far.
+pub struct Session {
+    root: PathBuf,
+    items: Vec<Item>,
+    globals: HashMap<Symbol, Value>,
+}
+
+/// A program compiled from the session and one input.
+struct Compiled {
+    program: Program,
+    // The line the input starts on, to make error positions relative to it
+    first_line: usize,
+}
+
+fn placeholder(ty: Type) -> &'static str {
+    match ty {
+        Type::I32 => "0",
+        Type::Bool => "false",
+        Type::Str => "\"\"",
+        Type::Void => "",
+    }
+}
+
+fn show(value: &Value) -> String {
+    match value {
+        Value::Str(text) => format!("{:?}", text),
+        value => value.to_string(),
+    }
+}
+
+/// The stators `use` can reach from `dir`: the project's sources if it is
+/// in a project, `dir` itself otherwise.
+fn stator_root(dir: &Path) -> PathBuf {
+    match find_manifest(dir) {
+        Some(manifest) => manifest.parent().unwrap_or(Path::new(".")).join("src"),
+        None => dir.to_path_buf(),
+    }
+}
+
+impl Session {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Session { root: root.into(), items: vec![], globals: HashMap::new() }
+    }
+
+    /// A session importing from the current directory, or its project.
+    pub fn in_current_dir() -> Self {
+        Session::new(stator_root(Path::new(".")))
+    }
+
+    fn items_source(&self) -> String {
+        self.items.iter().map(|item| format!("{}\n", item.source)).collect()
+    }
+
+    /// Links and checks the session's items followed by `extra`, whose
+    /// first line is the input's.
+    fn compile(&self, extra: &str, input_offset: usize) -> Result<Compiled, Vec<Error>> {
+        let source = format!("{}{}", self.items_source(), extra);
+        let first_line = source[..source.len() - extra.len()].lines().count() + input_offset + 1;
+        let relative = |errors: Vec<Error>| errors.into_iter().map(|error| self.relative(error, first_line)).collect::<Vec<_>>();
+
+        let mut loader = Loader::new(&self.root);
+        loader.load_entry_source(Path::new(FILE), &source).map_err(relative)?;
+        let mut program = loader.link().map_err(relative)?;
+        check_program(&mut program).map_err(relative)?;
+        Ok(Compiled { program, first_line })
+    }
+
+    /// Counts the lines of `error` from the start of the input.
+    fn relative(&self, error: Error, first_line: usize) -> Error {
+        let line = (error.line() + 1).saturating_sub(first_line);
+        Error::new(error.kind().clone(), error.message(), line, error.column())
+    }
+
+    fn wrapper_body(program: &Program) -> &[Stmt] {
+        program
+            .iter()
+            .find_map(|stmt| match stmt {
+                Stmt::FnDecl { name, body: Some(body), .. } if name == WRAPPER => Some(body.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn compile_expr(&self, input: &str) -> Result<(Compiled, Type), Vec<Error>> {
+        let compiled = self.compile(&format!("fn {}() {{\nlet {} =\n{}\n;\n}}\n", WRAPPER, VALUE, input), 2)?;
+        let ty = match Session::wrapper_body(&compiled.program).first() {
+            Some(Stmt::LetStmt { ty: Some(ty), .. }) => Type::from_kind(*ty).unwrap_or(Type::Void),
+            _ => Type::Void,
+        };
+        Ok((compiled, ty))
+    }
+
+    fn compile_stmts(&self, input: &str) -> Result<Compiled, Vec<Error>> {
+        let input = input.trim_end();
+        let semicolon = if input.ends_with(';') || input.ends_with('}') { "" } else { ";" };
+        self.compile(&format!("fn {}() {{\n{}{}\n}}\n", WRAPPER, input, semicolon), 1)
+    }
+
+    /// The type of the expression `input`.
+    pub fn type_of(&self, input: &str) -> Result<Type, Vec<Error>> {
+        self.compile_expr(input).map(|(_, ty)| ty)
+    }
+
+    /// Runs one input and returns what to print, if anything.
+    pub fn eval(&mut self, input: &str) -> Result<String, Vec<Error>> {
+        let lexed = lex(input);
+        if let Some(error) = lexed.errors.first() {
+            return Err(vec![Error::new(ErrorKind::InvalidToken, error.clone(), 0, 0)]);
+        }
+        let kinds: Vec<TokenKind> = lexed.tokens.iter().map(|token| token.kind).filter(|kind| *kind != TokenKind::Newline).collect();
+        match kinds.first() {
+            None => Ok(String::new()),
+            Some(TokenKind::Fn | TokenKind::Pub | TokenKind::Use) => self.define(input),
+            Some(TokenKind::If | TokenKind::For | TokenKind::While | TokenKind::Return | TokenKind::Let | TokenKind::Const) => {
+                self.run(input)
+            }
+            _ if kinds.last() == Some(&TokenKind::Semicolon) || kinds.contains(&TokenKind::Equal) => self.run(input),
+            _ => match self.compile_expr(input) {
+                Ok((compiled, ty)) => {
+                    let value = self.evaluate(&compiled, |evaluator, body| match body.first() {
+                        Some(Stmt::LetStmt { value, .. }) => evaluator.expr(value),
+                        _ => Ok(Value::Void),
+                    })?;
+                    Ok(format!("{}: {}", show(&value), ty))
+                }
+                // Calls to functions that return nothing only make sense as statements
+                Err(errors) => self.run(input).map_err(|_| errors),
+            },
+        }
+    }
+
+    /// Runs `run` on the wrapper's body with the session's globals.
+    fn evaluate(
+        &mut self,
+        compiled: &Compiled,
+        run: impl FnOnce(&mut Evaluator, &[Stmt]) -> Result<Value, Error>,
+    ) -> Result<Value, Vec<Error>> {
+        let mut globals = self.globals.clone();
+        let mut evaluator = Evaluator::new(&compiled.program, &mut globals);
+        let value = run(&mut evaluator, Session::wrapper_body(&compiled.program))
+            .map_err(|error| vec![self.relative(error, compiled.first_line)])?;
+        // A failed input leaves the globals as they were
+        self.globals = globals;
+        Ok(value)
+    }
+
+    /// Runs statements, keeping the variables a `let` or `const` declares.
+    fn run(&mut self, input: &str) -> Result<String, Vec<Error>> {
+        let compiled = self.compile_stmts(input)?;
+        let mut locals = HashMap::new();
+        self.evaluate(&compiled, |evaluator, body| {
+            locals = evaluator.exec(body)?;
+            Ok(Value::Void)
+        })?;
+        let mut out = vec![];
+        for stmt in Session::wrapper_body(&compiled.program) {
+            let Stmt::LetStmt { name, ty: Some(ty), constant, .. } = stmt else { continue };
+            let Some(value) = locals.remove(name) else { continue };
+            let ty = Type::from_kind(*ty).unwrap_or(Type::Void);
+            let keyword = if *constant { "const" } else { "let" };
+            let source = format!("{} {}: {} = {};", keyword, name, ty, placeholder(ty));
+            self.replace(Item { names: vec![*name], source });
+            out.push(format!("{}: {} = {}", name, ty, show(&value)));
+            self.globals.insert(*name, value);
+        }
+        Ok(out.join("\n"))
+    }
+
+    /// Adds the items in `input`, replacing earlier ones of the same name.
+    fn define(&mut self, input: &str) -> Result<String, Vec<Error>> {
+        let lexed = lex(input);
+        let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+        let (program, errors) = parse_program_recovering(&mut stream);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let mut names = vec![];
+        for stmt in &program {
+            match stmt {
+                Stmt::FnDecl { name, .. } => names.push(*name),
+                Stmt::UseStmt { stator, imports: UseImports::Wildcard, .. } => names.push(Symbol::from(format!("{}.*", stator))),
+                Stmt::UseStmt { imports: UseImports::List(imported), .. } => names.extend(imported.iter().cloned()),
+                _ => {}
+            }
+        }
+        let previous = self.items.clone();
+        self.replace(Item { names, source: input.trim().to_string() });
+        if let Err(errors) = self.compile("", 0) {
+            self.items = previous;
+            return Err(errors);
+        }
+        Ok(String::new())
+    }
+
+    fn replace(&mut self, item: Item) {
+        self.items.retain(|old| !old.names.iter().any(|name| item.names.contains(name)));
+        self.items.push(item);
+    }
+}
+
+/// Whether `input` is ready to run: every brace opened has been closed.
+pub fn is_complete(input: &str) -> bool {
+    let lexed = lex(input);
+    let depth = lexed.tokens.iter().fold(0isize, |depth, token| match token.kind {
+        TokenKind::LCurly => depth + 1,
+        TokenKind::RCurly => depth - 1,
+        _ => depth,
+    });
+    depth <= 0
+}
+
+/// The file inputs are saved to: `$ROTOR_HISTORY`, or `~/.rotor_history`.
+pub fn history_file() -> Option<PathBuf> {
+    match std::env::var_os("ROTOR_HISTORY") {
+        Some(file) if !file.is_empty() => Some(PathBuf::from(file)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".rotor_history")),
+    }
+}
+
+/// A REPL fed one line at a time.
+pub struct Repl {
+    session: Session,
+    pending: String,
+    history: Option<PathBuf>,
+}
+
+impl Repl {
+    pub fn new(session: Session, history: Option<PathBuf>) -> Self {
+        Repl { session, pending: String::new(), history }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() { "> " } else { "... " }
+    }
+
+    fn save(&self, input: &str) {
+        let Some(file) = &self.history else { return };
+        // Losing history isn't worth interrupting the session for
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(file) {
+            let _ = writeln!(file, "{}", input.replace('\n', "\\n"));
+        }
+    }
+
+    fn command(&mut self, command: &str, arg: &str) -> Option<String> {
+        let out = match command {
+            ":quit" | ":q" => return None,
+            ":help" => HELP.trim_end().to_string(),
+            ":type" => match self.session.type_of(arg) {
+                Ok(ty) => ty.to_string(),
+                Err(errors) => report(&errors),
+            },
+            ":ast" => {
+                let lexed = lex(arg);
+                let mut stream = TokenStream::with_eof(lexed.tokens, lexed.eof);
+                match lexed.errors.first() {
+                    Some(error) => format!("error: {}", error),
+                    None => match parse_expr(&mut stream) {
+                        Ok(_) if !stream.is_eof() => "error: Expected a single expression".to_string(),
+                        Ok(expr) => dump_expr(&expr).trim_end().to_string(),
+                        Err(error) => report(&[error]),
+                    },
+                }
+            }
+            ":tokens" => {
+                let lexed = lex(arg);
+                let mut lines: Vec<String> = lexed.tokens.iter().map(|token| token.get_debug_info()).collect();
+                lines.extend(lexed.errors.iter().map(|error| format!("error: {}", error)));
+                lines.join("\n")
+            }
+            ":history" => match &self.history {
+                Some(file) => std::fs::read_to_string(file).unwrap_or_default().trim_end().to_string(),
+                None => "error: No history file; set ROTOR_HISTORY or HOME".to_string(),
+            },
+            _ => format!("error: Unknown command `{}`; try `:help`", command),
+        };
+        Some(out)
+    }
+
+    /// Takes one line of input and returns what to print, or `None` when
+    /// the session is over.
+    pub fn feed(&mut self, line: &str) -> Option<String> {
+        if !self.pending.is_empty() {
+            self.pending.push('\n');
+        }
+        self.pending.push_str(line.trim_end_matches(['\n', '\r']));
+        if !is_complete(&self.pending) {
+            return Some(String::new());
+        }
+        let input = std::mem::take(&mut self.pending);
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return Some(String::new());
+        }
+        self.save(trimmed);
+        if trimmed.starts_with(':') {
+            let (command, arg) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+            return self.command(command, arg.trim());
+        }
+        Some(match self.session.eval(trimmed) {
+            Ok(out) => out,
+            Err(errors) => report(&errors),
+        })
+    }
+}
+
+fn report(errors: &[Error]) -> String {
+    let lines: Vec<String> = errors
+        .iter()
+        .map(|error| match error.line() {
+            0 => format!("error: {}", error.message()),
+            line => format!("error: {} (line {}, column {})", error.message(), line, error.column()),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Runs a REPL on stdin and stdout. Prompts are only shown to a terminal.
+pub fn run_stdio() -> i32 {
+    // The evaluator recurses with the program, so give it room for
+    // `MAX_DEPTH` calls even in a debug build
+    let session = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(session_stdio);
+    match session.map(|thread| thread.join()) {
+        Ok(Ok(code)) => code,
+        _ => 1,
+    }
+}
+
+fn session_stdio() -> i32 {
+    let mut repl = Repl::new(Session::in_current_dir(), history_file());
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!("Rotor REPL. Type `:help` for help, `:quit` to leave.");
+    }
+    loop {
+        if interactive {
+            print!("{}", repl.prompt());
+            let _ = io::stdout().flush();
+        }
+        // Read a line at a time, so `console.read_line` can read the next one
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => return 0,
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("error: {}", error);
+                return 1;
+            }
+        }
+        match repl.feed(&line) {
+            Some(out) if out.is_empty() => {}
+            Some(out) => println!("{}", out),
+            None => return 0,
+        }
+    }
+}
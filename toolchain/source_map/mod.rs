@@ -0,0 +1,2 @@
+pub mod source_map;
+pub use source_map::{FileId, SourceFile, SourceMap};
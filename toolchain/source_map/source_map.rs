@@ -0,0 +1,123 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The files a compilation reads. Each file added to a `SourceMap` gets a
+// `FileId`, which spans and errors carry instead of a path, so a
+// diagnostic from any stator of a project can be traced back to the file
+// it is about once everything has been linked into one program. The map
+// owns the text of every file, and turns byte offsets into lines and
+// columns the way the lexer counts them.
+//
+// Code parsed without a map has its spans in file 0, which is also where
+// the first file added to a map goes, so a single file needs no map.
+use std::path::{Path, PathBuf};
+use crate::handle_error::Error;
+
+/// A file in a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileId(u32);
+
+impl FileId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+pub struct SourceFile {
+    /// Where the file was read from. Standard library stators are
+    /// labelled `<std>/io/console.rt` and so on.
+    pub path: PathBuf,
+    pub text: String,
+    // Offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(path: impl Into<PathBuf>, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(offset, _)| offset + 1)).collect();
+        SourceFile { path: path.into(), text, line_starts }
+    }
+
+    /// The line and column of `offset`, both 1-based, as the lexer counts
+    /// them: in bytes, with a tab taking four columns. Offsets past the end
+    /// are at the end.
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let start = self.line_starts[line - 1];
+        let column = self.text.as_bytes()[start..offset].iter().map(|byte| if *byte == b'\t' { 4 } else { 1 }).sum::<usize>();
+        (line, column + 1)
+    }
+}
+
+/// The files of a compilation, by `FileId`.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// Adds a file, returning its id. Every call adds a new file, even for
+    /// a path that is already in the map.
+    pub fn add(&mut self, path: impl Into<PathBuf>, text: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(path, text));
+        id
+    }
+
+    /// The file `id` stands for. Panics if it is from another map.
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.index()]
+    }
+
+    pub fn path(&self, id: FileId) -> &Path {
+        &self.get(id).path
+    }
+
+    pub fn text(&self, id: FileId) -> &str {
+        &self.get(id).text
+    }
+
+    /// The first file added from `path`.
+    pub fn find(&self, path: &Path) -> Option<FileId> {
+        self.files.iter().position(|file| file.path == path).map(|index| FileId(index as u32))
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files.iter().enumerate().map(|(index, file)| (FileId(index as u32), file))
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The line and column of `offset` in file `id`.
+    pub fn location(&self, id: FileId, offset: usize) -> (usize, usize) {
+        self.get(id).location(offset)
+    }
+
+    /// The file `error` is about, if it says and the file is in the map.
+    pub fn file_of(&self, error: &Error) -> Option<&SourceFile> {
+        error.file().and_then(|id| self.files.get(id.index()))
+    }
+
+    /// `error` as `path:line:column: message`, leaving out what it doesn't
+    /// know.
+    pub fn describe(&self, error: &Error) -> String {
+        match (self.file_of(error), error.line()) {
+            (Some(file), 0) => format!("{}: {}", file.path.display(), error.message()),
+            (Some(file), line) => format!("{}:{}:{}: {}", file.path.display(), line, error.column(), error.message()),
+            (None, _) => error.to_string(),
+        }
+    }
+}
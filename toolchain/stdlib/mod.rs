@@ -0,0 +1,2 @@
+pub mod stdlib;
+pub use stdlib::{source, stators, is_intrinsic, INTRINSICS};
@@ -0,0 +1,42 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// The standard library ships inside the toolchain. Its stators are
+// ordinary Rotor sources, embedded in the binary and handed to the
+// loader whenever a program imports something under `std`. Whatever
+// cannot be written in Rotor itself is declared there as a function
+// without a body, an intrinsic, and implemented by each backend.
+
+const SOURCES: &[(&str, &str)] = &[
+    ("std.io", include_str!("std/io.rt")),
+    ("std.io.console", include_str!("std/io/console.rt")),
+    ("std.math", include_str!("std/math.rt")),
+    ("std.process", include_str!("std/process.rt")),
+    ("std.string", include_str!("std/string.rt")),
+];
+
+/// The functions the backends implement natively, by linked name.
+pub const INTRINSICS: &[&str] = &[
+    "std.io.console.out",
+    "std.io.console.err",
+    "std.io.console.read_line",
+    "std.process.exit",
+    "std.process.arg_count",
+    "std.process.arg",
+    "std.string.len",
+    "std.string.byte_at",
+];
+
+/// The source of a standard library stator, e.g. `std.io.console`.
+pub fn source(stator: &str) -> Option<&'static str> {
+    SOURCES.iter().find(|(name, _)| *name == stator).map(|(_, source)| *source)
+}
+
+/// The name of every standard library stator.
+pub fn stators() -> impl Iterator<Item = &'static str> {
+    SOURCES.iter().map(|(name, _)| *name)
+}
+
+pub fn is_intrinsic(name: &str) -> bool {
+    INTRINSICS.contains(&name)
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+//
+// Warnings about code that is valid but probably not what was meant. They
+// never stop a build, so they are kept out of the type checker and only
+// `rotor check` asks for them. Works on a single stator's parse tree, before
+// linking, so spans still refer to the stator's own file.
+//
+// Variables (including parameters and loop variables) that are never read
+// are reported, unless their name starts with `_`. Assigning to a variable
+// does not count as reading it. Statements after a `return` in the same
+// block are reported once per block.
+use crate::lexer::Span;
+use crate::parser::nodes::{walk_expr, walk_stmt, Block, Expr, FnParam, Program, Stmt, Visitor};
+use crate::handle_error::{ErrorKind, Error};
+
+struct Local {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+struct Linter {
+    scopes: Vec<Vec<Local>>,
+    warnings: Vec<Error>,
+}
+
+impl Linter {
+    fn warn(&mut self, kind: ErrorKind, message: impl Into<String>, span: Span) {
+        self.warnings.push(Error::at(kind, message, span));
+    }
+
+    // Globals are outside every scope and never reported
+    fn declare(&mut self, name: &str, span: Span) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Local { name: name.to_string(), span, used: false });
+        }
+    }
+
+    fn enter(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    fn leave(&mut self) {
+        for local in self.scopes.pop().unwrap() {
+            if !local.used && !local.name.starts_with('_') {
+                self.warn(
+                    ErrorKind::UnusedVariable,
+                    format!("Variable `{}` is never read", local.name),
+                    local.span,
+                );
+            }
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        // Later declarations shadow earlier ones, even in the same scope
+        let local = self.scopes.iter_mut().rev().find_map(|scope| scope.iter_mut().rev().find(|local| local.name == name));
+        if let Some(local) = local {
+            local.used = true;
+        }
+    }
+}
+
+impl Visitor for Linter {
+    fn visit_block(&mut self, block: &Block) {
+        self.enter();
+        let mut returned = false;
+        for stmt in block {
+            if returned {
+                self.warn(ErrorKind::UnreachableCode, "Unreachable statement after `return`", stmt.span());
+                returned = false;
+            }
+            self.visit_stmt(stmt);
+            returned |= matches!(stmt, Stmt::ReturnStmt { .. });
+        }
+        self.leave();
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, span, .. } => {
+                walk_stmt(self, stmt);
+                self.declare(name, *span);
+            }
+            Stmt::FnDecl { .. } => {
+                self.enter();
+                walk_stmt(self, stmt);
+                self.leave();
+            }
+            Stmt::ForStmt { variable, iterable, body, span } => {
+                self.visit_expr(iterable);
+                self.enter();
+                self.declare(variable, *span);
+                self.visit_block(body);
+                self.leave();
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_param(&mut self, param: &FnParam) {
+        self.declare(&param.name, param.span);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable { name, .. } => self.read(name),
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+/// The warnings for one stator's program.
+pub fn lint_program(program: &Program) -> Vec<Error> {
+    let mut linter = Linter { scopes: vec![], warnings: vec![] };
+    linter.visit_program(program);
+    linter.warnings.sort_by_key(|warning| (warning.line, warning.column));
+    linter.warnings
+}
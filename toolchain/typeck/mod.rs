@@ -0,0 +1,4 @@
+pub mod typeck;
+pub mod lint;
+pub use typeck::{Type, FnSig, Checked, check_program};
+pub use lint::lint_program;
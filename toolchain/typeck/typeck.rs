@@ -0,0 +1,444 @@
+// Copyright (c) 2025, Rotor Language Project
+// All rights reserved.
+use std::collections::HashMap;
+use std::fmt;
+use crate::lexer::{Span, Symbol, TokenKind};
+use crate::parser::nodes::{Expr, Stmt, Program, Block};
+use crate::handle_error::{ErrorKind, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    I32,
+    Bool,
+    Str,
+    // Only used as the return type of functions that don't return anything
+    Void,
+}
+
+impl Type {
+    pub fn from_kind(kind: TokenKind) -> Option<Type> {
+        match kind {
+            TokenKind::I32 => Some(Type::I32),
+            TokenKind::BOOL => Some(Type::Bool),
+            TokenKind::STR => Some(Type::Str),
+            _ => None,
+        }
+    }
+
+    pub fn to_kind(self) -> Option<TokenKind> {
+        match self {
+            Type::I32 => Some(TokenKind::I32),
+            Type::Bool => Some(TokenKind::BOOL),
+            Type::Str => Some(TokenKind::STR),
+            Type::Void => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Type::I32 => "i32",
+            Type::Bool => "bool",
+            Type::Str => "str",
+            Type::Void => "void",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FnSig {
+    pub params: Vec<Type>,
+    pub ret: Type,
+}
+
+/// The result of type checking a program. Variables in the checked
+/// program have their `ty` filled in, so together with the function
+/// signatures here every expression's type can be recovered.
+pub struct Checked {
+    pub functions: HashMap<Symbol, FnSig>,
+    pub globals: HashMap<Symbol, Type>,
+}
+
+impl Checked {
+    pub fn type_of(&self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal { kind, .. } => match kind {
+                TokenKind::Boolean => Type::Bool,
+                TokenKind::String => Type::Str,
+                _ => Type::I32,
+            },
+            Expr::Variable { ty, .. } => ty.and_then(Type::from_kind).unwrap_or(Type::Void),
+            Expr::BinaryOp { left, op, .. } => {
+                if is_comparison(*op) || is_logical(*op) {
+                    Type::Bool
+                } else {
+                    self.type_of(left)
+                }
+            }
+            Expr::UnaryOp { op, .. } => {
+                if *op == TokenKind::Not { Type::Bool } else { Type::I32 }
+            }
+            Expr::FnCall { name, .. } => {
+                self.functions.get(name).map(|sig| sig.ret).unwrap_or(Type::Void)
+            }
+            Expr::Array { .. } => Type::Void,
+        }
+    }
+}
+
+fn is_arithmetic(op: TokenKind) -> bool {
+    matches!(op, TokenKind::Plus | TokenKind::Line | TokenKind::Star | TokenKind::Slash | TokenKind::Modulus)
+}
+
+fn is_comparison(op: TokenKind) -> bool {
+    matches!(
+        op,
+        TokenKind::LessThan
+            | TokenKind::GreaterThan
+            | TokenKind::LessThanOrEqual
+            | TokenKind::GreaterThanOrEqual
+            | TokenKind::EqualEqual
+            | TokenKind::NotEqual
+    )
+}
+
+fn is_logical(op: TokenKind) -> bool {
+    matches!(op, TokenKind::And | TokenKind::Or)
+}
+
+/// Whether every path through `block` ends in a `return`.
+pub fn block_returns(block: &Block) -> bool {
+    block.iter().any(|stmt| match stmt {
+        Stmt::ReturnStmt { .. } => true,
+        Stmt::IfStmt { then_branch, else_branch: Some(else_branch), .. } => {
+            block_returns(then_branch) && block_returns(else_branch)
+        }
+        _ => false,
+    })
+}
+
+struct Var {
+    ty: Type,
+    constant: bool,
+}
+
+struct Checker {
+    functions: HashMap<Symbol, FnSig>,
+    scopes: Vec<HashMap<Symbol, Var>>,
+    // Return type of the function currently being checked
+    ret: Type,
+    errors: Vec<Error>,
+}
+
+impl Checker {
+    fn error(&mut self, kind: ErrorKind, message: impl Into<String>, span: Span) {
+        self.errors.push(Error::at(kind, message, span));
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<&Var> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+
+    fn declare(&mut self, name: Symbol, var: Var, span: Span) {
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.contains_key(&name) {
+            self.error(
+                ErrorKind::DuplicateDefinition,
+                format!("`{}` is already defined in this scope", name),
+                span,
+            );
+            return;
+        }
+        scope.insert(name, var);
+    }
+
+    fn expect_type(&mut self, expected: Type, found: Option<Type>, span: Span) {
+        if let Some(found) = found {
+            if found != expected {
+                self.error(
+                    ErrorKind::TypeMismatch,
+                    format!("Mismatched types: expected {}, found {}", expected, found),
+                    span,
+                );
+            }
+        }
+    }
+
+    fn check_block(&mut self, block: &mut Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in block.iter_mut() {
+            self.check_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::LetStmt { name, ty, value, constant, span } => {
+                let found = self.check_expr(value);
+                let var_ty = match ty.and_then(Type::from_kind) {
+                    Some(declared) => {
+                        self.expect_type(declared, found, value.span());
+                        Some(declared)
+                    }
+                    None => found,
+                };
+                let Some(var_ty) = var_ty else { return };
+                if var_ty == Type::Void {
+                    self.error(ErrorKind::TypeMismatch, format!("`{}` can't hold a value of type void", name), *span);
+                    return;
+                }
+                *ty = var_ty.to_kind();
+                self.declare(*name, Var { ty: var_ty, constant: *constant }, *span);
+            }
+            Stmt::AssignStmt { name, value, span } => {
+                let found = self.check_expr(value);
+                let target = self.lookup(*name).map(|var| (var.ty, var.constant));
+                match target {
+                    None => self.error(ErrorKind::UnknownIdentifier, format!("Unknown variable `{}`", name), *span),
+                    Some((_, true)) => self.error(
+                        ErrorKind::ConstAssignment,
+                        format!("Cannot assign twice to constant `{}`", name),
+                        *span,
+                    ),
+                    Some((ty, false)) => self.expect_type(ty, found, value.span()),
+                }
+            }
+            Stmt::IfStmt { condition, then_branch, else_branch, .. } => {
+                let found = self.check_expr(condition);
+                self.expect_type(Type::Bool, found, condition.span());
+                self.check_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_block(else_branch);
+                }
+            }
+            Stmt::WhileStmt { condition, body, .. } => {
+                let found = self.check_expr(condition);
+                self.expect_type(Type::Bool, found, condition.span());
+                self.check_block(body);
+            }
+            Stmt::ForStmt { variable, iterable, body, span } => {
+                // Only ranges can be iterated over for now
+                match iterable.as_mut() {
+                    Expr::BinaryOp { left, op: TokenKind::Range, right, .. } => {
+                        let start = self.check_expr(left);
+                        self.expect_type(Type::I32, start, left.span());
+                        let end = self.check_expr(right);
+                        self.expect_type(Type::I32, end, right.span());
+                    }
+                    other => {
+                        let other_span = other.span();
+                        self.check_expr(other);
+                        self.error(ErrorKind::Unsupported, "For loops can only iterate over ranges (e.g. `0..10`)", other_span);
+                    }
+                }
+                self.scopes.push(HashMap::new());
+                self.declare(*variable, Var { ty: Type::I32, constant: true }, *span);
+                self.check_block(body);
+                self.scopes.pop();
+            }
+            Stmt::ReturnStmt { value, span } => {
+                let found = match value {
+                    Some(value) => self.check_expr(value),
+                    None => Some(Type::Void),
+                };
+                let ret = self.ret;
+                self.expect_type(ret, found, *span);
+            }
+            Stmt::CallStmt(call) => {
+                self.check_expr(call);
+            }
+            Stmt::FnDecl { span, .. } => {
+                self.error(ErrorKind::Unsupported, "Functions can only be declared at the top level", *span);
+            }
+            Stmt::UseStmt { span, .. } => {
+                self.error(ErrorKind::Unsupported, "`use` can only appear at the top level", *span);
+            }
+        }
+    }
+
+    /// Returns `None` if the expression had an error (which was already reported).
+    fn check_expr(&mut self, expr: &mut Expr) -> Option<Type> {
+        match expr {
+            Expr::Literal { kind, value, span } => match kind {
+                TokenKind::Integer => {
+                    if value.parse::<i32>().is_err() {
+                        self.error(ErrorKind::TypeMismatch, format!("Integer literal `{}` is too large for i32", value), *span);
+                        return None;
+                    }
+                    Some(Type::I32)
+                }
+                TokenKind::Boolean => Some(Type::Bool),
+                TokenKind::String => Some(Type::Str),
+                _ => {
+                    self.error(ErrorKind::Unsupported, format!("{} values are not supported yet", kind), *span);
+                    None
+                }
+            },
+            Expr::Variable { name, ty, span } => match self.lookup(*name) {
+                Some(var) => {
+                    *ty = var.ty.to_kind();
+                    Some(var.ty)
+                }
+                None => {
+                    self.error(ErrorKind::UnknownIdentifier, format!("Unknown variable `{}`", name), *span);
+                    None
+                }
+            },
+            Expr::BinaryOp { left, op, right, span } => {
+                let op = *op;
+                let left_ty = self.check_expr(left)?;
+                let right_ty = self.check_expr(right)?;
+
+                if op == TokenKind::Range {
+                    self.error(ErrorKind::Unsupported, "Ranges can only be used in for loops", *span);
+                    return None;
+                }
+
+                let operand = if is_logical(op) {
+                    Type::Bool
+                } else if matches!(op, TokenKind::EqualEqual | TokenKind::NotEqual) {
+                    // Strings can't be compared until there is a runtime to do it
+                    if left_ty == Type::Str || left_ty == Type::Void {
+                        self.error(ErrorKind::TypeMismatch, format!("Cannot compare values of type {}", left_ty), *span);
+                        return None;
+                    }
+                    left_ty
+                } else {
+                    Type::I32
+                };
+
+                if left_ty != operand || right_ty != operand {
+                    self.error(
+                        ErrorKind::TypeMismatch,
+                        format!("Cannot apply {} to {} and {}", op, left_ty, right_ty),
+                        *span,
+                    );
+                    return None;
+                }
+
+                if is_arithmetic(op) { Some(Type::I32) } else { Some(Type::Bool) }
+            }
+            Expr::UnaryOp { op, expr: inner, span } => {
+                let op = *op;
+                let found = self.check_expr(inner)?;
+                let expected = if op == TokenKind::Not { Type::Bool } else { Type::I32 };
+                if found != expected {
+                    self.error(ErrorKind::TypeMismatch, format!("Cannot apply {} to {}", op, found), *span);
+                    return None;
+                }
+                Some(expected)
+            }
+            Expr::FnCall { name, args, span } => {
+                let arg_types: Vec<(Option<Type>, Span)> = args
+                    .iter_mut()
+                    .map(|arg| (self.check_expr(arg), arg.span()))
+                    .collect();
+
+                let Some(sig) = self.functions.get(name).cloned() else {
+                    self.error(ErrorKind::UnknownIdentifier, format!("Unknown function `{}`", name), *span);
+                    return None;
+                };
+
+                if sig.params.len() != arg_types.len() {
+                    self.error(
+                        ErrorKind::WrongArgumentCount,
+                        format!("`{}` takes {} argument(s) but {} were given", name, sig.params.len(), arg_types.len()),
+                        *span,
+                    );
+                } else {
+                    for (expected, (found, arg_span)) in sig.params.iter().zip(arg_types) {
+                        self.expect_type(*expected, found, arg_span);
+                    }
+                }
+                Some(sig.ret)
+            }
+            Expr::Array { span, .. } => {
+                self.error(ErrorKind::Unsupported, "Arrays are not supported yet", *span);
+                None
+            }
+        }
+    }
+}
+
+pub fn check_program(program: &mut Program) -> Result<Checked, Vec<Error>> {
+    let mut checker = Checker {
+        functions: HashMap::new(),
+        scopes: vec![HashMap::new()],
+        ret: Type::Void,
+        errors: vec![],
+    };
+
+    // Collect every signature first so functions can be called before
+    // they are declared.
+    for stmt in program.iter() {
+        if let Stmt::FnDecl { name, params, return_ty, span, .. } = stmt {
+            if checker.functions.contains_key(name) {
+                checker.error(
+                    ErrorKind::DuplicateDefinition,
+                    format!("Function `{}` is defined more than once", name),
+                    *span,
+                );
+                continue;
+            }
+            let sig = FnSig {
+                params: params.iter().filter_map(|param| Type::from_kind(param.ty)).collect(),
+                ret: return_ty.and_then(Type::from_kind).unwrap_or(Type::Void),
+            };
+            checker.functions.insert(*name, sig);
+        }
+    }
+
+    let mut globals = HashMap::new();
+    for stmt in program.iter_mut() {
+        match stmt {
+            Stmt::LetStmt { value, span, .. } => {
+                if !matches!(value.as_ref(), Expr::Literal { .. }) {
+                    let span = *span;
+                    checker.error(ErrorKind::Unsupported, "Top-level values must be literals", span);
+                    continue;
+                }
+                checker.check_stmt(stmt);
+                if let Stmt::LetStmt { name, ty: Some(ty), .. } = stmt {
+                    globals.insert(*name, Type::from_kind(*ty).unwrap());
+                }
+            }
+            Stmt::FnDecl { params, return_ty, body: Some(body), name, span, .. } => {
+                checker.ret = return_ty.and_then(Type::from_kind).unwrap_or(Type::Void);
+                checker.scopes.push(HashMap::new());
+                for param in params.iter() {
+                    let ty = Type::from_kind(param.ty).unwrap();
+                    checker.declare(param.name, Var { ty, constant: false }, param.span);
+                }
+                checker.check_block(body);
+                checker.scopes.pop();
+
+                if checker.ret != Type::Void && !block_returns(body) {
+                    checker.error(
+                        ErrorKind::MissingReturn,
+                        format!("Function `{}` must return a value of type {}", name, checker.ret),
+                        *span,
+                    );
+                }
+            }
+            // Modules aren't resolved yet, so imports are accepted as-is
+            Stmt::FnDecl { .. } | Stmt::UseStmt { .. } => {}
+            other => {
+                let span = other.span();
+                checker.error(
+                    ErrorKind::Unsupported,
+                    "Only functions, `use` and `let`/`const` are allowed at the top level",
+                    span,
+                );
+            }
+        }
+    }
+
+    if checker.errors.is_empty() {
+        Ok(Checked { functions: checker.functions, globals })
+    } else {
+        Err(checker.errors)
+    }
+}